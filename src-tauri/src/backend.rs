@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use crate::apm::is_windows_7_or_newer;
+use crate::declare::{PrintJob, PrintOptions, Printer};
+use crate::settings::PrintSettings;
+use crate::{cups, macos, windows, windows7};
+
+// 各平台打印后端的统一接口，启动时选定一个实现，Tauri 命令只做转发
+pub trait PrinterBackend: Send + Sync {
+    // 后端名称，用于拼接返回给前端的提示信息
+    fn name(&self) -> &'static str;
+
+    // 获取所有打印机
+    fn get_printers(&self) -> Result<Vec<Printer>, String>;
+
+    // 根据打印机名称获取打印机信息
+    fn get_printer(&self, printer_name: String) -> Result<Option<Printer>, String>;
+
+    // 提交打印任务，返回打印队列中的作业 ID，无法确定时返回空字符串
+    fn print_pdf(&self, options: PrintOptions) -> Result<String, String>;
+
+    // 打印设置中该后端无法实现的选项，默认全部不支持
+    fn unsupported_settings(&self, settings: &PrintSettings) -> Vec<&'static str> {
+        settings.specified()
+    }
+
+    // 把文件内容原样提交到打印队列，不经过驱动渲染，返回作业 ID
+    fn print_raw(&self, _printer_name: String, _path: String, _document_name: String) -> Result<String, String> {
+        Err("Unsupported OS".to_string())
+    }
+
+    // 获取指定打印机的打印作业
+    fn get_jobs(&self, printer_name: String) -> Result<Vec<PrintJob>, String>;
+
+    // 根据作业 ID 获取打印作业
+    fn get_job(&self, _printer_name: String, _job_id: String) -> Result<Option<PrintJob>, String> {
+        Err("Unsupported OS".to_string())
+    }
+
+    // 暂停打印作业
+    fn pause_job(&self, _printer_name: String, _job_id: String) -> Result<(), String> {
+        Err("Unsupported OS".to_string())
+    }
+
+    // 恢复打印作业
+    fn resume_job(&self, _printer_name: String, _job_id: String) -> Result<(), String> {
+        Err("Unsupported OS".to_string())
+    }
+
+    // 重新开始打印作业
+    fn restart_job(&self, _printer_name: String, _job_id: String) -> Result<(), String> {
+        Err("Unsupported OS".to_string())
+    }
+
+    // 删除打印作业
+    fn remove_job(&self, _printer_name: String, _job_id: String) -> Result<(), String> {
+        Err("Unsupported OS".to_string())
+    }
+}
+
+pub type SharedBackend = Arc<dyn PrinterBackend>;
+
+// 根据当前操作系统选择打印后端，只在启动时调用一次
+pub fn detect() -> SharedBackend {
+    if cfg!(windows) {
+        unsafe {
+            if is_windows_7_or_newer() {
+                println!("使用 Windows 打印后端");
+                return Arc::new(windows::WindowsBackend);
+            }
+        }
+        println!("检测到 Win7 系统，使用 Windows7 打印后端");
+        return Arc::new(windows7::Windows7Backend);
+    }
+    if cfg!(target_os = "macos") {
+        println!("使用 MacOS 打印后端");
+        return Arc::new(macos::MacosBackend);
+    }
+    println!("使用 CUPS 打印后端");
+    Arc::new(cups::CupsBackend)
+}
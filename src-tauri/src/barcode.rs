@@ -0,0 +1,497 @@
+use datamatrix::{DataMatrix, SymbolList};
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Serialize, Deserialize};
+use crate::bitmap::MonoBitmap;
+
+// 支持的条码类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symbology {
+    Code128,
+    // GS1-128，内容按 "(01)09501101530003(10)ABC" 的格式书写应用标识符
+    #[serde(rename = "gs1_128")]
+    Gs1128,
+    Qrcode,
+    DataMatrix,
+    Pdf417,
+}
+
+// 枚举上的 #[default] 需要 Rust 1.62，这里手写
+#[allow(clippy::derivable_impls)]
+impl Default for Symbology {
+    fn default() -> Self {
+        Symbology::Code128
+    }
+}
+
+impl Symbology {
+    pub fn is_linear(&self) -> bool {
+        matches!(self, Symbology::Code128 | Symbology::Gs1128)
+    }
+
+    // 各码制要求的静区宽度 (模块数)
+    pub fn quiet_zone(&self) -> usize {
+        match self {
+            Symbology::Code128 | Symbology::Gs1128 => 10,
+            Symbology::Qrcode => 4,
+            Symbology::DataMatrix => 1,
+            Symbology::Pdf417 => 2,
+        }
+    }
+}
+
+// 条码模块矩阵，true 为深色模块；一维条码只有一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarcodeMatrix {
+    pub width: usize,
+    pub height: usize,
+    pub modules: Vec<bool>,
+}
+
+impl BarcodeMatrix {
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.modules[y * self.width + x]
+    }
+
+    // 第 y 行连续深色模块的 (起点, 长度)，绘制时合并为一个矩形
+    pub fn runs(&self, y: usize) -> Vec<(usize, usize)> {
+        let mut runs = Vec::new();
+        let mut x = 0;
+        while x < self.width {
+            if self.get(x, y) {
+                let start = x;
+                while x < self.width && self.get(x, y) {
+                    x += 1;
+                }
+                runs.push((start, x - start));
+            } else {
+                x += 1;
+            }
+        }
+        runs
+    }
+
+    // 转换为热敏打印机使用的位图，每个模块 module 个点，每行模块高 row_height 个点
+    // 一维条码 row_height 即条高，二维码传入与 module 相同的值
+    pub fn to_bitmap(&self, module: u32, row_height: u32) -> MonoBitmap {
+        let module = module.max(1);
+        let row_height = row_height.max(1);
+        let mut bitmap = MonoBitmap::new(self.width as u32 * module, self.height as u32 * row_height);
+        for row in 0..self.height {
+            for (start, length) in self.runs(row) {
+                for y in row as u32 * row_height..(row as u32 + 1) * row_height {
+                    for x in start as u32 * module..(start + length) as u32 * module {
+                        bitmap.set(x, y, true);
+                    }
+                }
+            }
+        }
+        bitmap
+    }
+}
+
+// 按码制生成模块矩阵
+pub fn encode(symbology: Symbology, data: &str) -> Result<BarcodeMatrix, String> {
+    match symbology {
+        Symbology::Code128 => code128(data),
+        Symbology::Gs1128 => gs1_128(data),
+        Symbology::Qrcode => qr(data),
+        Symbology::DataMatrix => data_matrix(data),
+        Symbology::Pdf417 => crate::pdf417::pdf417(data),
+    }
+}
+
+// Code128 符号的条空宽度，106 为停止符
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const CODE_C: usize = 99;
+const CODE_B: usize = 100;
+const CODE_A: usize = 101;
+const FNC1: usize = 102;
+const START_A: usize = 103;
+const STOP: usize = 106;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Code128Set {
+    A,
+    B,
+    C,
+}
+
+// 符号数相同时依次优先 B、A、C
+const CODE128_SETS: [Code128Set; 3] = [Code128Set::B, Code128Set::A, Code128Set::C];
+
+// Code128 的逻辑符号，打印语言按各自的语法输出，条码图形按符号值绘制
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Code128Code {
+    Start(Code128Set),
+    Switch(Code128Set),
+    Fnc1,
+    // A / B 字符集中的单个 ASCII 字符
+    Char(u8),
+    // C 字符集中的两位数字 00-99
+    Pair(u8),
+}
+
+impl Code128Code {
+    fn value(&self) -> usize {
+        match self {
+            Code128Code::Start(set) => START_A + *set as usize,
+            Code128Code::Switch(Code128Set::A) => CODE_A,
+            Code128Code::Switch(Code128Set::B) => CODE_B,
+            Code128Code::Switch(Code128Set::C) => CODE_C,
+            Code128Code::Fnc1 => FNC1,
+            Code128Code::Char(c) if *c < 32 => *c as usize + 64,
+            Code128Code::Char(c) => *c as usize - 32,
+            Code128Code::Pair(pair) => *pair as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Code128Input {
+    Char(u8),
+    Fnc1,
+}
+
+// 从 position 开始用 set 编码一个符号，返回符号和下一个位置
+fn code128_step(input: &[Code128Input], position: usize, set: Code128Set) -> Option<(Code128Code, usize)> {
+    match (set, input[position]) {
+        (_, Code128Input::Fnc1) => Some((Code128Code::Fnc1, position + 1)),
+        (Code128Set::A, Code128Input::Char(c)) if c < 96 => Some((Code128Code::Char(c), position + 1)),
+        (Code128Set::B, Code128Input::Char(c)) if (32..128).contains(&c) => Some((Code128Code::Char(c), position + 1)),
+        (Code128Set::C, Code128Input::Char(high)) => match input.get(position + 1) {
+            Some(Code128Input::Char(low)) if high.is_ascii_digit() && low.is_ascii_digit() => {
+                Some((Code128Code::Pair((high - b'0') * 10 + (low - b'0')), position + 2))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 动态规划选择符号数最少的字符集切换方案
+fn code128_plan(input: &[Code128Input]) -> Result<Vec<Code128Code>, String> {
+    if input.is_empty() {
+        return Err("条码内容为空".to_string());
+    }
+    if let Some(Code128Input::Char(c)) = input.iter().find(|item| matches!(item, Code128Input::Char(c) if *c >= 128)) {
+        return Err(format!("Code128 不支持字符: {}", *c as char));
+    }
+
+    const UNREACHABLE: usize = usize::MAX / 2;
+    let length = input.len();
+    // cost[i][s]：当前处于字符集 s 时编码 input[i..] 还需要的符号数
+    let mut cost = vec![[UNREACHABLE; 3]; length + 1];
+    cost[length] = [0; 3];
+    for position in (0..length).rev() {
+        // 不切换字符集时编码下一个符号的总代价，按字符集下标存放
+        let mut direct = [UNREACHABLE; 3];
+        for set in CODE128_SETS {
+            if let Some((_, next)) = code128_step(input, position, set) {
+                direct[set as usize] = 1 + cost[next][set as usize];
+            }
+        }
+        for set in CODE128_SETS {
+            let switched = CODE128_SETS
+                .iter()
+                .filter(|other| **other != set)
+                .map(|other| 1 + direct[*other as usize])
+                .min()
+                .unwrap_or(UNREACHABLE);
+            cost[position][set as usize] = direct[set as usize].min(switched);
+        }
+    }
+
+    let mut set = CODE128_SETS
+        .iter()
+        .copied()
+        .min_by_key(|set| match code128_step(input, 0, *set) {
+            Some((_, next)) => cost[next][*set as usize],
+            None => UNREACHABLE,
+        })
+        .unwrap_or(Code128Set::B);
+    if code128_step(input, 0, set).is_none() {
+        return Err("Code128 无法编码该内容".to_string());
+    }
+
+    let mut codes = vec![Code128Code::Start(set)];
+    let mut position = 0;
+    while position < length {
+        // 优先留在当前字符集，只有切换后更短时才切换
+        let stay = code128_step(input, position, set).map(|(code, next)| (code, next, 1 + cost[next][set as usize]));
+        match stay {
+            Some((code, next, total)) if total == cost[position][set as usize] => {
+                codes.push(code);
+                position = next;
+            }
+            _ => {
+                let target = CODE128_SETS
+                    .iter()
+                    .copied()
+                    .filter(|other| *other != set)
+                    .find(|other| match code128_step(input, position, *other) {
+                        Some((_, next)) => 2 + cost[next][*other as usize] == cost[position][set as usize],
+                        None => false,
+                    })
+                    .ok_or("Code128 无法编码该内容")?;
+                codes.push(Code128Code::Switch(target));
+                set = target;
+            }
+        }
+    }
+    Ok(codes)
+}
+
+// Code128 编码，自动在 A / B / C 字符集之间切换
+pub fn code128_codes(data: &str) -> Result<Vec<Code128Code>, String> {
+    let input: Vec<Code128Input> = data.bytes().map(Code128Input::Char).collect();
+    if let Some(c) = data.chars().find(|c| !c.is_ascii()) {
+        return Err(format!("Code128 不支持字符: {}", c));
+    }
+    code128_plan(&input)
+}
+
+// 长度固定的 GS1 应用标识符 (按前两位)，总长度包含 AI 本身；其余 AI 为变长，后面需要 FNC1 分隔
+fn gs1_fixed_length(ai: &str) -> Option<usize> {
+    let length = match &ai[..2] {
+        "00" => 20,
+        "01" | "02" | "03" => 16,
+        "04" => 18,
+        "11" | "12" | "13" | "14" | "15" | "16" | "17" | "18" | "19" => 8,
+        "20" => 4,
+        "31" | "32" | "33" | "34" | "35" | "36" => 10,
+        "41" => 16,
+        _ => return None,
+    };
+    Some(length)
+}
+
+// 解析 "(AI)数据" 形式的 GS1 内容，转换为以 FNC1 开头、变长字段后以 FNC1 分隔的序列
+fn gs1_input(data: &str) -> Result<Vec<Code128Input>, String> {
+    let mut input = vec![Code128Input::Fnc1];
+    let mut rest = data.trim();
+    if !rest.starts_with('(') {
+        return Err("GS1-128 内容需要以 (AI) 开头".to_string());
+    }
+
+    while let Some(stripped) = rest.strip_prefix('(') {
+        let close = stripped.find(')').ok_or("GS1-128 应用标识符缺少右括号")?;
+        let ai = &stripped[..close];
+        if !(2..=4).contains(&ai.len()) || !ai.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("无效的 GS1 应用标识符: ({})", ai));
+        }
+        let after = &stripped[close + 1..];
+        let end = after.find('(').unwrap_or(after.len());
+        let value = &after[..end];
+        rest = &after[end..];
+
+        if value.is_empty() {
+            return Err(format!("GS1 应用标识符 ({}) 缺少数据", ai));
+        }
+        let fixed = gs1_fixed_length(ai);
+        if let Some(length) = fixed {
+            if ai.len() + value.len() != length {
+                return Err(format!("GS1 应用标识符 ({}) 的数据长度应为 {}", ai, length - ai.len()));
+            }
+        }
+
+        input.extend(ai.bytes().chain(value.bytes()).map(Code128Input::Char));
+        if fixed.is_none() && !rest.is_empty() {
+            input.push(Code128Input::Fnc1);
+        }
+    }
+    Ok(input)
+}
+
+pub fn gs1_128_codes(data: &str) -> Result<Vec<Code128Code>, String> {
+    code128_plan(&gs1_input(data)?)
+}
+
+fn code128_matrix(codes: &[Code128Code]) -> BarcodeMatrix {
+    let mut values: Vec<usize> = codes.iter().map(Code128Code::value).collect();
+    // 校验位：起始符 + 各符号值 × 位置
+    let checksum = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| if index == 0 { *value } else { value * index })
+        .sum::<usize>()
+        % 103;
+    values.push(checksum);
+    values.push(STOP);
+
+    let mut modules = Vec::new();
+    for value in values {
+        for (index, width) in CODE128_PATTERNS[value].bytes().enumerate() {
+            let bar = index % 2 == 0;
+            modules.resize(modules.len() + (width - b'0') as usize, bar);
+        }
+    }
+
+    BarcodeMatrix {
+        width: modules.len(),
+        height: 1,
+        modules,
+    }
+}
+
+pub fn code128(data: &str) -> Result<BarcodeMatrix, String> {
+    Ok(code128_matrix(&code128_codes(data)?))
+}
+
+pub fn gs1_128(data: &str) -> Result<BarcodeMatrix, String> {
+    Ok(code128_matrix(&gs1_128_codes(data)?))
+}
+
+// 二维码，纠错等级 M，不含静区
+pub fn qr(data: &str) -> Result<BarcodeMatrix, String> {
+    let code = QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+        .map_err(|e| format!("生成二维码失败: {}", e))?;
+    let width = code.width();
+    let modules = code.to_colors().into_iter().map(|color| color == Color::Dark).collect();
+    Ok(BarcodeMatrix {
+        width,
+        height: width,
+        modules,
+    })
+}
+
+// DataMatrix ECC200，自动选择最小的正方形符号，不含静区
+// 与二维码、PDF417 一样直接写入 UTF-8 字节，不加 ECI 标记
+pub fn data_matrix(data: &str) -> Result<BarcodeMatrix, String> {
+    if data.is_empty() {
+        return Err("条码内容为空".to_string());
+    }
+    let code = DataMatrix::encode(data.as_bytes(), SymbolList::default().enforce_square())
+        .map_err(|e| format!("生成 DataMatrix 失败: {:?}", e))?;
+    let bitmap = code.bitmap();
+    let (width, height) = (bitmap.width(), bitmap.height());
+    let mut modules = vec![false; width * height];
+    for (x, y) in bitmap.pixels() {
+        modules[y * width + x] = true;
+    }
+    Ok(BarcodeMatrix {
+        width,
+        height,
+        modules,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Code128Code::{Char, Fnc1, Pair, Start, Switch};
+
+    // 按条空宽度把一维条码还原为符号值，最后一个是停止符
+    fn code128_values(matrix: &BarcodeMatrix) -> Vec<usize> {
+        let mut widths = String::new();
+        let mut x = 0;
+        while x < matrix.width {
+            let dark = matrix.get(x, 0);
+            let start = x;
+            while x < matrix.width && matrix.get(x, 0) == dark {
+                x += 1;
+            }
+            widths.push((b'0' + (x - start) as u8) as char);
+        }
+        let (symbols, stop) = widths.split_at(widths.len() - 7);
+        let mut values: Vec<usize> = symbols
+            .as_bytes()
+            .chunks(6)
+            .map(|chunk| CODE128_PATTERNS.iter().position(|p| p.as_bytes() == chunk).unwrap())
+            .collect();
+        assert_eq!(stop, CODE128_PATTERNS[STOP]);
+        values.push(STOP);
+        values
+    }
+
+    fn bits(matrix: &BarcodeMatrix, range: std::ops::Range<usize>) -> String {
+        range.map(|x| if matrix.get(x, 0) { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn code128_known_answer() {
+        let matrix = code128("Wikipedia").unwrap();
+        // 起始符 B、9 个字符、校验位 88、停止符
+        assert_eq!(code128_values(&matrix), vec![104, 55, 73, 75, 73, 80, 69, 68, 73, 65, 88, 106]);
+        assert_eq!(matrix.width, 11 * 11 + 13);
+        assert_eq!(bits(&matrix, 0..11), "11010010000");
+        assert_eq!(bits(&matrix, matrix.width - 13..matrix.width), "1100011101011");
+    }
+
+    #[test]
+    fn code128_set_selection() {
+        // 全数字用 C 字符集
+        assert_eq!(code128_codes("12345678").unwrap(), vec![Start(Code128Set::C), Pair(12), Pair(34), Pair(56), Pair(78)]);
+        // 符号数相同时优先 B
+        assert_eq!(code128_codes("123").unwrap(), vec![Start(Code128Set::B), Char(b'1'), Char(b'2'), Char(b'3')]);
+        // 字母后的长数字切换到 C
+        assert_eq!(
+            code128_codes("AB1234").unwrap(),
+            vec![Start(Code128Set::B), Char(b'A'), Char(b'B'), Switch(Code128Set::C), Pair(12), Pair(34)]
+        );
+        // 控制字符只在 A 字符集中，小写字母只在 B 中
+        assert_eq!(
+            code128_codes("\tAB").unwrap(),
+            vec![Start(Code128Set::A), Char(b'\t'), Char(b'A'), Char(b'B')]
+        );
+        assert_eq!(
+            code128_codes("\ta").unwrap(),
+            vec![Start(Code128Set::A), Char(b'\t'), Switch(Code128Set::B), Char(b'a')]
+        );
+
+        // 校验位：起始符 A (103)、\t (73)、切换到 B (100)、a (65) 按位置加权后对 103 取余
+        let matrix = code128("\ta").unwrap();
+        let checksum = (103 + 73 + 100 * 2 + 65 * 3) % 103;
+        assert_eq!(code128_values(&matrix), vec![103, 73, 100, 65, checksum, 106]);
+
+        let matrix = code128("AB1234").unwrap();
+        let checksum = (104 + 33 + 34 * 2 + 99 * 3 + 12 * 4 + 34 * 5) % 103;
+        assert_eq!(code128_values(&matrix), vec![104, 33, 34, 99, 12, 34, checksum, 106]);
+    }
+
+    #[test]
+    fn code128_rejects_invalid_input() {
+        assert!(code128_codes("").is_err());
+        assert!(code128_codes("中文").is_err());
+        assert!(code128_codes("\u{7F}").is_ok());
+    }
+
+    #[test]
+    fn gs1_fnc1_placement() {
+        let codes = gs1_128_codes("(01)09501101530003(17)201231(10)AB12(21)XY").unwrap();
+        // 开头的 FNC1 在起始符之后，固定长度的 (01)、(17) 后不加分隔符，变长的 (10) 后加 FNC1，最后一个字段后不加
+        let mut expected = vec![Start(Code128Set::C), Fnc1];
+        expected.extend([1, 9, 50, 11, 1, 53, 0, 3, 17, 20, 12, 31, 10].iter().map(|pair| Pair(*pair)));
+        expected.extend([Switch(Code128Set::B), Char(b'A'), Char(b'B'), Char(b'1'), Char(b'2'), Fnc1]);
+        expected.extend([Char(b'2'), Char(b'1'), Char(b'X'), Char(b'Y')]);
+        assert_eq!(codes, expected);
+
+        // 起始符 C (105) 之后是 FNC1 (102)
+        let values = code128_values(&gs1_128("(01)09501101530003").unwrap());
+        assert_eq!(&values[..3], &[105, 102, 1]);
+        let checksum = (105 + 102 + [1, 9, 50, 11, 1, 53, 0, 3].iter().enumerate().map(|(i, v)| v * (i + 2)).sum::<usize>()) % 103;
+        assert_eq!(values[values.len() - 2], checksum);
+    }
+
+    #[test]
+    fn gs1_rejects_malformed_data() {
+        assert!(gs1_128_codes("0109501101530003").is_err());
+        assert!(gs1_128_codes("(01)123").is_err());
+        assert!(gs1_128_codes("(01").is_err());
+        assert!(gs1_128_codes("(1)5").is_err());
+        assert!(gs1_128_codes("(10)").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use base64::{Engine as _, engine::general_purpose};
+use image::imageops::FilterType;
+
+// 单色位图，每行按字节对齐，高位在前，1 表示黑点
+// ESC/POS 的 GS v 0、ZPL 的 ^GF 与 TSPL 的 BITMAP 都使用这种排列
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonoBitmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+// 每行占用的字节数，不足 8 个点的部分补齐一个字节
+fn row_bytes(width: u32) -> usize {
+    (width as usize + 7) >> 3
+}
+
+impl MonoBitmap {
+    pub fn new(width: u32, height: u32) -> Self {
+        let bytes_per_row = row_bytes(width);
+        Self {
+            width,
+            height,
+            data: vec![0; bytes_per_row * height as usize],
+        }
+    }
+
+    pub fn bytes_per_row(&self) -> usize {
+        row_bytes(self.width)
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, black: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = y as usize * self.bytes_per_row() + (x / 8) as usize;
+        let mask = 0x80 >> (x % 8);
+        if black {
+            self.data[index] |= mask;
+        } else {
+            self.data[index] &= !mask;
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let index = y as usize * self.bytes_per_row() + (x / 8) as usize;
+        self.data[index] & (0x80 >> (x % 8)) != 0
+    }
+
+    // 取第 y 行的字节
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = y as usize * self.bytes_per_row();
+        &self.data[start..start + self.bytes_per_row()]
+    }
+
+    // 从 PNG / JPEG 数据生成位图，超过 max_width 时等比缩小
+    // 透明像素按白色处理，灰度低于 128 的像素为黑点
+    pub fn from_image_bytes(bytes: &[u8], max_width: Option<u32>) -> Result<Self, String> {
+        let mut image = image::load_from_memory(bytes).map_err(|e| format!("解析图片失败: {}", e))?;
+        if let Some(max_width) = max_width {
+            if max_width > 0 && image.width() > max_width {
+                let height = (image.height() as u64 * max_width as u64 / image.width() as u64).max(1) as u32;
+                image = image.resize_exact(max_width, height, FilterType::Triangle);
+            }
+        }
+
+        let rgba = image.to_rgba8();
+        let mut bitmap = Self::new(rgba.width(), rgba.height());
+        for (x, y, pixel) in rgba.enumerate_pixels() {
+            let [r, g, b, a] = pixel.0;
+            let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+            // 与白色背景混合
+            let luma = (luma * a as u32 + 255 * (255 - a as u32)) / 255;
+            bitmap.set(x, y, luma < 128);
+        }
+        Ok(bitmap)
+    }
+
+    // 前端传入的图片为 base64，可能带 data URL 前缀
+    pub fn from_base64(data: &str, max_width: Option<u32>) -> Result<Self, String> {
+        let encoded = data.split_once("base64,").map_or(data, |(_, rest)| rest);
+        let bytes = general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("图片 base64 解码失败: {}", e))?;
+        Self::from_image_bytes(&bytes, max_width)
+    }
+}
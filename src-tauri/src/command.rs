@@ -0,0 +1,364 @@
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::process::Command;
+use base64::{Engine as _, engine::general_purpose};
+use encoding_rs::Encoding;
+
+// 外部命令统一在这里构建：打印机名称、作业 ID、文件路径等来自前端的值
+// 只能作为独立参数传递，或者转义为字面量后再拼进 PowerShell / WQL，不允许直接 format! 到脚本里
+
+// 不弹出控制台窗口的子进程
+pub fn hidden_command<S: AsRef<OsStr>>(program: S) -> Command {
+    #[allow(unused_mut)]
+    let mut command = Command::new(program);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+// PowerShell 进程，脚本以 -EncodedCommand 传入，避免命令行解析再处理一遍引号
+pub fn powershell(script: &str) -> Command {
+    let mut command = hidden_command("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-EncodedCommand", &encode_powershell(script)]);
+    command
+}
+
+// 执行 PowerShell 脚本，失败时返回 stderr
+pub fn run_powershell(script: &str) -> Result<String, String> {
+    let output = powershell(script)
+        .output()
+        .map_err(|e| format!("Failed to execute PowerShell command: {}", e))?;
+    if output.status.success() {
+        Ok(decode_output(&output.stdout))
+    } else {
+        Err(format!("Command failed with error: {}", powershell_error(&decode_output(&output.stderr))))
+    }
+}
+
+// -EncodedCommand 要求 UTF-16LE 编码后再 base64，进度条会占用 stderr，这里一并关闭
+// 输出改为不带 BOM 的 UTF-8，没有控制台时设置可能失败，此时仍按控制台代码页输出，由 decode_output 处理
+pub fn encode_powershell(script: &str) -> String {
+    let script = format!(
+        "$ProgressPreference = 'SilentlyContinue'\ntry {{ [Console]::OutputEncoding = New-Object System.Text.UTF8Encoding $false }} catch {{}}\n{}",
+        script
+    );
+    let bytes: Vec<u8> = script.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+    general_purpose::STANDARD.encode(bytes)
+}
+
+// 子进程输出转为字符串：合法的 UTF-8 原样使用，否则按控制台代码页解码
+// 中文系统的控制台代码页是 936，Windows 7 自带的 PowerShell 2.0 等情况下输出仍是 GBK
+pub fn decode_output(bytes: &[u8]) -> String {
+    decode_code_page(bytes, console_code_page())
+}
+
+pub fn decode_code_page(bytes: &[u8], code_page: u32) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => code_page_encoding(code_page).decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+// Windows 代码页对应的编码，不支持的代码页按 Windows-1252 解码
+fn code_page_encoding(code_page: u32) -> &'static Encoding {
+    match code_page {
+        936 | 54936 => encoding_rs::GB18030,
+        950 => encoding_rs::BIG5,
+        932 => encoding_rs::SHIFT_JIS,
+        949 => encoding_rs::EUC_KR,
+        866 => encoding_rs::IBM866,
+        1250 => encoding_rs::WINDOWS_1250,
+        1251 => encoding_rs::WINDOWS_1251,
+        1253 => encoding_rs::WINDOWS_1253,
+        1254 => encoding_rs::WINDOWS_1254,
+        1255 => encoding_rs::WINDOWS_1255,
+        1256 => encoding_rs::WINDOWS_1256,
+        1257 => encoding_rs::WINDOWS_1257,
+        1258 => encoding_rs::WINDOWS_1258,
+        65001 => encoding_rs::UTF_8,
+        _ => encoding_rs::WINDOWS_1252,
+    }
+}
+
+// 隐藏窗口启动的控制台程序使用 OEM 代码页输出
+#[cfg(windows)]
+fn console_code_page() -> u32 {
+    extern "system" {
+        fn GetOEMCP() -> u32;
+    }
+    unsafe { GetOEMCP() }
+}
+
+#[cfg(not(windows))]
+fn console_code_page() -> u32 {
+    65001
+}
+
+// 使用 -EncodedCommand 时错误以 CLIXML 形式写入 stderr，取出其中的错误文本
+pub fn powershell_error(stderr: &str) -> String {
+    let xml = match stderr.trim().strip_prefix("#< CLIXML") {
+        Some(xml) => xml,
+        None => return stderr.trim().to_string(),
+    };
+    let mut message = String::new();
+    for part in xml.split("<S S=\"Error\">").skip(1) {
+        if let Some((text, _)) = part.split_once("</S>") {
+            message.push_str(text);
+        }
+    }
+    message
+        .replace("_x000D_", "")
+        .replace("_x000A_", "\n")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+// PowerShell 把这几个字符都当作单引号，单引号字符串中需要各自写两遍
+const PS_SINGLE_QUOTES: [char; 5] = ['\'', '\u{2018}', '\u{2019}', '\u{201A}', '\u{201B}'];
+
+// 转为 PowerShell 单引号字面量，其中的 $、`、" 和换行都不会被解释
+pub fn ps_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        if PS_SINGLE_QUOTES.contains(&c) {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+// 逐个添加参数的 PowerShell 命令，参数值一律转为字面量
+// 例如 PsCommand::new("Get-PrintJob").param("PrinterName", name).pipe("ConvertTo-Json")
+pub struct PsCommand {
+    script: String,
+}
+
+impl PsCommand {
+    pub fn new(cmdlet: &'static str) -> Self {
+        Self { script: cmdlet.to_string() }
+    }
+
+    pub fn param(mut self, name: &'static str, value: &str) -> Self {
+        let _ = write!(self.script, " -{} {}", name, ps_quote(value));
+        self
+    }
+
+    // 管道后的命令只能是代码中的常量
+    pub fn pipe(mut self, command: &str) -> Self {
+        let _ = write!(self.script, " | {}", command);
+        self
+    }
+
+    pub fn build(self) -> String {
+        self.script
+    }
+}
+
+// 转为 WQL 单引号字符串，反斜杠和单引号需要用反斜杠转义
+pub fn wql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// LIKE 中 %、_、[ 是通配符，放进方括号后按普通字符匹配
+pub fn wql_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' | '_' | '[' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 作业 ID 只能是数字
+pub fn job_id(value: &str) -> Result<u32, String> {
+    value.trim().parse().map_err(|_| format!("无效的作业 ID: {}", value))
+}
+
+// 以 - 开头的文件路径会被当成命令行选项，加上 ./ 前缀
+pub fn path_arg(path: &str) -> String {
+    if path.starts_with('-') {
+        format!("./{}", path)
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTILE_NAMES: [&str; 9] = [
+        "HP LaserJet",
+        "O'Brien's printer",
+        "x'; Remove-Item -Recurse C:\\ ; '",
+        "x\"; calc.exe; \"",
+        "$(Start-Process calc)",
+        "`$env:PATH` & calc",
+        "line1\nline2\r\n",
+        "smart \u{2018}quote\u{2019} \u{201A}low\u{201B}",
+        "' , '\\' ; --",
+    ];
+
+    // 按 PowerShell 的规则读取开头的单引号字面量，返回内容和剩余部分
+    fn read_ps_literal(script: &str) -> Option<(String, &str)> {
+        let mut chars = script.char_indices();
+        let (_, first) = chars.next()?;
+        if !PS_SINGLE_QUOTES.contains(&first) {
+            return None;
+        }
+        let mut value = String::new();
+        let mut chars = chars.peekable();
+        while let Some((index, c)) = chars.next() {
+            if PS_SINGLE_QUOTES.contains(&c) {
+                match chars.peek() {
+                    Some(&(_, next)) if PS_SINGLE_QUOTES.contains(&next) => {
+                        value.push(c);
+                        chars.next();
+                    }
+                    _ => return Some((value, &script[index + c.len_utf8()..])),
+                }
+            } else {
+                value.push(c);
+            }
+        }
+        None
+    }
+
+    // 按 WQL 的规则读取开头的单引号字符串
+    fn read_wql_literal(query: &str) -> Option<(String, &str)> {
+        let rest = query.strip_prefix('\'')?;
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\\' => value.push(chars.next()?.1),
+                '\'' => return Some((value, &rest[index + 1..])),
+                _ => value.push(c),
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn ps_quote_keeps_hostile_names_in_one_literal() {
+        for name in HOSTILE_NAMES {
+            let quoted = ps_quote(name);
+            assert_eq!(read_ps_literal(&quoted), Some((name.to_string(), "")), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn ps_command_params_do_not_leak_into_script() {
+        for name in HOSTILE_NAMES {
+            let script = PsCommand::new("Remove-PrintJob").param("PrinterName", name).param("ID", "7").build();
+            let rest = script.strip_prefix("Remove-PrintJob -PrinterName ").unwrap();
+            let (value, rest) = read_ps_literal(rest).unwrap();
+            assert_eq!(value, name);
+            assert_eq!(rest, " -ID '7'");
+        }
+    }
+
+    #[test]
+    fn wql_string_keeps_hostile_names_in_one_literal() {
+        for name in HOSTILE_NAMES {
+            let query = format!("SELECT * FROM Win32_Printer WHERE Name={}", wql_string(name));
+            let rest = query.strip_prefix("SELECT * FROM Win32_Printer WHERE Name=").unwrap();
+            assert_eq!(read_wql_literal(rest), Some((name.to_string(), "")), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn wql_inside_powershell_literal() {
+        let name = "a' OR Name LIKE '%";
+        let wql = format!("SELECT * FROM Win32_PrintJob WHERE Name LIKE {}", wql_string(&format!("%{}%", wql_like(name))));
+        assert_eq!(wql, "SELECT * FROM Win32_PrintJob WHERE Name LIKE '%a\\' OR Name LIKE \\'[%]%'");
+        let quoted = ps_quote(&wql);
+        let (value, rest) = read_ps_literal(&quoted).unwrap();
+        assert_eq!(value, wql);
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn wql_like_escapes_wildcards() {
+        assert_eq!(wql_like("50%_off[1]"), "50[%][_]off[[]1]");
+    }
+
+    #[test]
+    fn encoded_command_is_utf16le_base64() {
+        let script = "Get-Printer -Name '打印机'";
+        let bytes = general_purpose::STANDARD.decode(encode_powershell(script)).unwrap();
+        let units: Vec<u16> = bytes.chunks(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        let decoded = String::from_utf16(&units).unwrap();
+        assert!(decoded.ends_with(script));
+    }
+
+    #[test]
+    fn clixml_error_is_unwrapped() {
+        let stderr = "#< CLIXML\r\n<Objs Version=\"1.1.0.1\" xmlns=\"http://schemas.microsoft.com/powershell/2004/04\"><S S=\"Error\">Get-Printer : &lt;x&gt; not found_x000D__x000A_</S><S S=\"Error\">At line:1_x000D__x000A_</S></Objs>";
+        assert_eq!(powershell_error(stderr), "Get-Printer : <x> not found\nAt line:1");
+        assert_eq!(powershell_error("plain error\n"), "plain error");
+    }
+
+    // 中文 Windows 上 Get-Printer | ConvertTo-Json 的原始输出（代码页 936）
+    const GBK_PRINTERS_JSON: &[u8] = b"[{\"Name\":\"\xC7\xB0\xCC\xA8\xB4\xF2\xD3\xA1\xBB\xFA\",\"DriverName\":\"HP LaserJet\"}]\r\n";
+    // 同一输出改为 UTF-8 后，前面带 BOM
+    const UTF8_PRINTERS_JSON: &[u8] = b"\xEF\xBB\xBF[{\"Name\":\"\xE5\x89\x8D\xE5\x8F\xB0\xE6\x89\x93\xE5\x8D\xB0\xE6\x9C\xBA\",\"DriverName\":\"HP LaserJet\"}]\r\n";
+    // Get-WmiObject Win32_PrintJob 输出的 "打印机名称, 作业ID" 和用户名（代码页 936）
+    const GBK_JOB_NAME: &[u8] = b"\xB2\xC6\xCE\xF1\xB2\xBF\xB4\xF2\xD3\xA1\xBB\xFA, 12 \xD5\xC5\xC8\xFD";
+
+    #[test]
+    fn gbk_output_keeps_chinese_printer_names() {
+        let printers: Vec<serde_json::Value> = serde_json::from_str(&decode_code_page(GBK_PRINTERS_JSON, 936)).unwrap();
+        assert_eq!(printers[0]["Name"], "前台打印机");
+        assert_eq!(decode_code_page(GBK_JOB_NAME, 936), "财务部打印机, 12 张三");
+        assert_eq!(decode_code_page(GBK_JOB_NAME, 54936), "财务部打印机, 12 张三");
+    }
+
+    #[test]
+    fn utf8_output_is_used_as_is() {
+        let text = decode_code_page(UTF8_PRINTERS_JSON, 936);
+        assert!(text.starts_with('['));
+        let printers: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        assert_eq!(printers[0]["Name"], "前台打印机");
+        assert_eq!(decode_code_page(b"HP LaserJet\r\n", 936), "HP LaserJet\r\n");
+    }
+
+    #[test]
+    fn other_code_pages() {
+        assert_eq!(decode_code_page(b"\xA6\x4C\xAA\xED", 950), "印表");
+        assert_eq!(decode_code_page(b"Drucker f\xFCr B\xFCro", 1252), "Drucker für Büro");
+    }
+
+    #[test]
+    fn job_id_must_be_numeric() {
+        assert_eq!(job_id(" 42 "), Ok(42));
+        assert!(job_id("42; calc").is_err());
+        assert!(job_id("$(calc)").is_err());
+        assert!(job_id("").is_err());
+    }
+
+    #[test]
+    fn path_arg_is_never_an_option() {
+        assert_eq!(path_arg("-o evil.pdf"), "./-o evil.pdf");
+        assert_eq!(path_arg("C:\\tmp\\a b;c.pdf"), "C:\\tmp\\a b;c.pdf");
+    }
+}
@@ -0,0 +1,626 @@
+use std::collections::HashMap;
+use std::process::{Command, Output};
+use crate::backend::PrinterBackend;
+use crate::command::path_arg;
+use crate::declare::{JobStatus, PrintJob, PrintOptions, Printer, PrinterState};
+use crate::fsys::remove_file;
+use crate::timestamp::Timestamp;
+use crate::settings::{ColorType, Method, Orientation, Paper, PrintSettings, Scale, TranslatedSettings};
+
+// 以 C locale 运行 CUPS 命令行工具，保证输出不随系统语言变化
+fn cups_command(program: &str, args: &[&str]) -> Result<Output, String> {
+    println!("执行命令: {} {}", program, args.join(" "));
+    Command::new(program)
+        .args(args)
+        .env("LC_ALL", "C")
+        .env("LANG", "C")
+        .output()
+        .map_err(|e| format!("无法执行{}命令: {}", program, e))
+}
+
+// 执行命令并返回标准输出，失败时返回标准错误
+fn cups_stdout(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = cups_command(program, args)?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let error_message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(format!("{} 执行失败: {}", program, error_message))
+    }
+}
+
+// 前端传入的打印机 ID 会带上 PowerShell 需要的引号，这里去掉
+pub fn queue_name(id: &str) -> &str {
+    id.trim().trim_matches('"')
+}
+
+// CUPS 作业 ID 形如 "打印机名称-123"，取出末尾的数字部分
+fn split_job_id(job: &str) -> Option<(&str, &str)> {
+    let (printer, id) = job.rsplit_once('-')?;
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((printer, id))
+}
+
+// 解析 lpoptions -p 的输出：以空格分隔的 key=value，值可能带单引号或反斜杠转义
+fn parse_lpoptions(output: &str) -> HashMap<String, String> {
+    let mut options = HashMap::new();
+    let mut chars = output.trim().chars().peekable();
+
+    while chars.peek().is_some() {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+            chars.next();
+        }
+
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'=') {
+            chars.next();
+            let mut quote: Option<char> = None;
+            while let Some(c) = chars.next() {
+                match (quote, c) {
+                    (None, c) if c.is_whitespace() => break,
+                    (None, '\'') | (None, '"') => quote = Some(c),
+                    (Some(q), c) if c == q => quote = None,
+                    (_, '\\') => {
+                        if let Some(escaped) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    (_, c) => value.push(c),
+                }
+            }
+        }
+
+        if !key.is_empty() {
+            options.insert(key, value);
+        }
+    }
+
+    options
+}
+
+// CUPS printer-state (3 空闲 / 4 打印中 / 5 已停止) 转换为 Win32_Printer 的 PrinterStatus 取值
+fn printer_status_from_state(state: &str) -> u32 {
+    match state {
+        "3" => 3,
+        "4" => 4,
+        "5" => 6,
+        _ => 2,
+    }
+}
+
+// 根据 lpoptions 的属性构造打印机信息
+fn printer_from_options(name: &str, options: &HashMap<String, String>) -> Printer {
+    let get = |key: &str| options.get(key).cloned().unwrap_or_default();
+    // printer-type 的 0x2 位表示远程打印机 (CUPS_PRINTER_REMOTE)
+    let printer_type = get("printer-type").parse::<u32>().unwrap_or_default();
+
+    Printer {
+        driver_name: get("printer-make-and-model"),
+        port_name: get("device-uri"),
+        share_name: if get("printer-is-shared") == "true" { name.to_string() } else { String::new() },
+        computer_name: get("printer-location"),
+        printer_status: printer_status_from_state(&get("printer-state")),
+        shared: get("printer-is-shared") == "true",
+        printer_type: if printer_type & 0x2 != 0 { 1 } else { 0 },
+        priority: get("job-priority").parse().unwrap_or(50),
+        state: PrinterState::from_ipp(get("printer-state").parse().unwrap_or_default(), get("printer-state-reasons").split(',')),
+        ..Printer::new(name)
+    }
+}
+
+// 根据 lpstat -l -o 中 Alerts 行的 job-state-reasons 关键字推断 Windows 风格的作业状态
+fn job_status_from_alerts(alerts: &str) -> JobStatus {
+    let code = if alerts.contains("job-printing") {
+        16
+    } else if alerts.contains("job-hold") || alerts.contains("held") {
+        1
+    } else if alerts.contains("job-stopped") || alerts.contains("error") {
+        2
+    } else if alerts.contains("job-completed") {
+        4096
+    } else {
+        0
+    };
+    JobStatus::from_code(code)
+}
+
+// lpstat -l -o 附加信息行的标签会随系统语言变化 (Alerts: / 警告： / Warnungen:)
+// 只按内容识别：冒号后全部是 job-state-reasons 关键字 (带连字符的小写单词，或 none) 的行即为 Alerts 行
+fn alerts_value(detail: &str) -> Option<&str> {
+    let (_, value) = detail.split_once([':', '：'])?;
+    let value = value.trim();
+    let keywords: Vec<&str> = value.split(|c: char| c == ',' || c.is_whitespace()).filter(|word| !word.is_empty()).collect();
+    let is_keyword = |word: &&str| {
+        (*word == "none" || word.contains('-')) && word.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    };
+    if !keywords.is_empty() && keywords.iter().all(is_keyword) && keywords.iter().any(|word| word.starts_with("job-") || *word == "none") {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+// 解析 lpstat -l -o 的输出，不依赖系统语言
+// 作业行形如 "PDF-12   root   1024   Sun Oct 18 10:00:00 2026"，前三列依次是作业 ID、用户和大小，其余为提交时间
+// 命令以 C locale 运行，其他语言的日期无法解析时不填提交时间
+// 后续缩进行是该作业的附加信息
+fn parse_jobs(jobs_output: &str) -> Vec<PrintJob> {
+    let mut jobs: Vec<PrintJob> = Vec::new();
+
+    for line in jobs_output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            // 附加信息行，例如 "\tAlerts: job-printing"
+            if let Some(job) = jobs.last_mut() {
+                if let Some(alerts) = alerts_value(line.trim()) {
+                    job.job_status = Some(job_status_from_alerts(alerts));
+                }
+            }
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 3 {
+            continue;
+        }
+
+        let (printer_name, job_id) = match split_job_id(parts[0]) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+
+        jobs.push(PrintJob {
+            username: parts[1].to_string(),
+            size: parts[2].parse().unwrap_or_default(),
+            job_status: Some(JobStatus::from_code(0)),
+            ..PrintJob::new(printer_name, job_id).with_submitted(Timestamp::from_cups(&parts[3..].join(" ")))
+        });
+    }
+
+    // 排队顺序即 lpstat 的输出顺序
+    for (index, job) in jobs.iter_mut().enumerate() {
+        job.position = index as u32 + 1;
+    }
+
+    jobs
+}
+
+// 从 lp 的输出中取出作业 ID，例如 "request id is PDF-12 (1 file(s))"、"请求 ID 为 PDF-12（1 个文件）"
+// 提示文字随系统语言变化，这里直接查找 "打印机名称-数字"
+pub fn parse_request_id(output: &str, printer_name: &str) -> Option<String> {
+    let prefix = format!("{}-", queue_name(printer_name));
+    output.match_indices(&prefix).find_map(|(start, _)| {
+        let rest = &output[start + prefix.len()..];
+        let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        if digits.is_empty() {
+            None
+        } else {
+            Some(format!("{}{}", prefix, digits))
+        }
+    })
+}
+
+// 全部打印队列的名称，lpstat -e 每行输出一个名称，不受语言影响，名称原样保留
+pub fn printer_names() -> Result<Vec<String>, String> {
+    let names = cups_stdout("lpstat", &["-e"])?;
+    Ok(parse_printer_names(&names))
+}
+
+fn parse_printer_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// 获取所有打印机，单个队列查询失败时跳过并记录日志，不影响其他打印机
+pub fn get_printers_cups() -> Result<Vec<Printer>, String> {
+    let mut printers = Vec::new();
+    for name in printer_names()? {
+        match printer_info(&name) {
+            Ok(printer) => printers.push(printer),
+            Err(e) => println!("获取打印机 {} 信息失败: {}", name, e),
+        }
+    }
+    Ok(printers)
+}
+
+// 根据打印机名称获取打印机信息，名称需与 lpstat -e 中的完全一致，队列不存在时返回 None
+pub fn get_printer_cups(printer_name: &str) -> Result<Option<Printer>, String> {
+    let name = queue_name(printer_name);
+    if !printer_names()?.iter().any(|item| item == name) {
+        return Ok(None);
+    }
+    printer_info(name).map(Some)
+}
+
+fn printer_info(printer_name: &str) -> Result<Printer, String> {
+    let name = queue_name(printer_name);
+    let options = parse_lpoptions(&cups_stdout("lpoptions", &["-p", name])?);
+    let mut printer = printer_from_options(name, &options);
+    printer.job_count = get_jobs_cups(name).map(|jobs| jobs.len() as u32).unwrap_or_default();
+    Ok(printer)
+}
+
+// 打印设置翻译为 lp 的 -n / -o 参数，同时给出 IPP 属性与旧版 PPD 选项以兼容不同驱动
+pub fn lp_print_settings(settings: &PrintSettings) -> Result<TranslatedSettings, String> {
+    let mut options: Vec<String> = Vec::new();
+
+    if let Some(paper) = settings.paper {
+        options.push(match paper.custom_size()? {
+            Some((width, height)) => format!("media=Custom.{}x{}mm", width, height),
+            None => format!("media={}", match paper {
+                Paper::Letter => "Letter",
+                Paper::Legal => "Legal",
+                Paper::Tabloid => "Tabloid",
+                paper => paper.name().unwrap_or_default(),
+            }),
+        });
+    }
+    if let Some(method) = settings.method {
+        options.push(format!("sides={}", match method {
+            Method::Simplex => "one-sided",
+            Method::Duplex => "two-sided-long-edge",
+            Method::Duplexshort => "two-sided-short-edge",
+        }));
+    }
+    match settings.scale {
+        Some(Scale::Noscale) => options.push("print-scaling=none".to_string()),
+        Some(Scale::Shrink) => options.push("print-scaling=auto-fit".to_string()),
+        Some(Scale::Fit) => {
+            options.push("print-scaling=fit".to_string());
+            options.push("fit-to-page".to_string());
+        }
+        None => {}
+    }
+    if let Some(color_type) = settings.color_type {
+        options.push(format!("print-color-mode={}", match color_type {
+            ColorType::Color => "color",
+            ColorType::Monochrome => "monochrome",
+        }));
+    }
+    if let Some(orientation) = settings.orientation {
+        // 3 为纵向，4 为横向
+        options.push(format!("orientation-requested={}", match orientation {
+            Orientation::Portrait => 3,
+            Orientation::Landscape => 4,
+        }));
+    }
+    if let Some(range) = &settings.range {
+        options.push(format!("page-ranges={}", range.normalized()?));
+    }
+
+    let mut args = Vec::new();
+    if settings.repeat.is_some() {
+        args.push("-n".to_string());
+        args.push(settings.copies()?.to_string());
+    }
+    for option in options {
+        args.push("-o".to_string());
+        args.push(option);
+    }
+    Ok(TranslatedSettings {
+        args,
+        unsupported: Vec::new(),
+    })
+}
+
+// 提交打印任务，返回 CUPS 作业 ID
+pub fn print_pdf_cups(options: PrintOptions) -> Result<String, String> {
+    let name = queue_name(&options.id);
+    let settings = lp_print_settings(&options.print_setting)?;
+    let mut args = vec!["-d", name];
+    args.extend(settings.args.iter().map(String::as_str));
+    let path = path_arg(&options.path);
+    args.push(path.as_str());
+    let output = cups_command("lp", &args)?;
+
+    if !output.status.success() {
+        let error_message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        eprintln!("打印 PDF 文件失败: {}", error_message);
+        return Err(error_message);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    println!("成功打印 PDF 文件: {}", stdout.trim());
+
+    // 作业已经提交，删除失败只记录日志，返回错误会被当作打印失败而重试
+    if options.remove_after_print {
+        match remove_file(&options.path) {
+            Ok(_) => println!("文件已删除: {}", options.path),
+            Err(e) => println!("删除文件失败: {}", e),
+        }
+    }
+
+    Ok(parse_request_id(&stdout, name).unwrap_or_default())
+}
+
+// 以 raw 方式提交 (ESC/POS、ZPL、TSPL 等)，CUPS 不做任何过滤，返回 CUPS 作业 ID
+pub fn print_raw_cups(printer_name: &str, path: &str, document_name: &str) -> Result<String, String> {
+    let name = queue_name(printer_name);
+    let path = path_arg(path);
+    let stdout = cups_stdout("lp", &["-d", name, "-o", "raw", "-t", document_name, &path])?;
+    println!("成功发送 RAW 数据: {}", stdout.trim());
+    // lp 已经接收了数据，解析不到作业 ID 时不能返回错误，否则会被重试而重复打印
+    Ok(parse_request_id(&stdout, name).unwrap_or_else(|| {
+        println!("无法解析作业 ID: {}", stdout.trim());
+        String::new()
+    }))
+}
+
+// 获取指定打印机未完成的打印作业
+pub fn get_jobs_cups(printer_name: &str) -> Result<Vec<PrintJob>, String> {
+    let name = queue_name(printer_name);
+    let output = cups_stdout("lpstat", &["-l", "-o", name])?;
+    Ok(parse_jobs(&output))
+}
+
+// 根据作业 ID 获取打印作业
+// 作业 ID 可以是 lpstat 中的数字，也可以是 lp 返回的 "打印机名称-123"
+pub fn get_job_cups(printer_name: &str, job_id: &str) -> Result<Option<PrintJob>, String> {
+    let (_, number) = job_arg(printer_name, job_id)?;
+    let jobs = get_jobs_cups(printer_name)?;
+    Ok(jobs.into_iter().find(|job| job.job_id == number))
+}
+
+// 把前端传入的作业 ID 统一为 ("打印机名称-123", "123")
+// 只接受数字或属于该打印机的 "打印机名称-数字"，避免以 - 开头的值被 lp / cancel 当作选项
+fn job_arg(printer_name: &str, job_id: &str) -> Result<(String, String), String> {
+    let name = queue_name(printer_name);
+    let job_id = job_id.trim();
+    let number = if !job_id.is_empty() && job_id.chars().all(|c| c.is_ascii_digit()) {
+        job_id
+    } else {
+        match split_job_id(job_id) {
+            Some((printer, number)) if printer == name => number,
+            _ => return Err(format!("无效的作业 ID: {}", job_id)),
+        }
+    };
+    Ok((format!("{}-{}", name, number), number.to_string()))
+}
+
+// 通过 lp -i <id> -H hold|resume|restart 控制作业
+pub fn change_job_cups(printer_name: &str, job_id: &str, action: &str) -> Result<(), String> {
+    let (job, _) = job_arg(printer_name, job_id)?;
+    cups_stdout("lp", &["-i", &job, "-H", action]).map(|_| ())
+}
+
+// 取消作业
+pub fn cancel_job_cups(printer_name: &str, job_id: &str) -> Result<(), String> {
+    let (job, _) = job_arg(printer_name, job_id)?;
+    cups_stdout("cancel", &[&job]).map(|_| ())
+}
+
+// 基于 CUPS 命令行工具的打印后端，用于 Linux
+pub struct CupsBackend;
+
+impl PrinterBackend for CupsBackend {
+    fn name(&self) -> &'static str {
+        "CUPS"
+    }
+
+    fn get_printers(&self) -> Result<Vec<Printer>, String> {
+        get_printers_cups()
+    }
+
+    fn get_printer(&self, printer_name: String) -> Result<Option<Printer>, String> {
+        get_printer_cups(&printer_name)
+    }
+
+    fn print_pdf(&self, options: PrintOptions) -> Result<String, String> {
+        print_pdf_cups(options)
+    }
+
+    fn unsupported_settings(&self, settings: &PrintSettings) -> Vec<&'static str> {
+        lp_print_settings(settings).map(|translated| translated.unsupported).unwrap_or_default()
+    }
+
+    fn print_raw(&self, printer_name: String, path: String, document_name: String) -> Result<String, String> {
+        print_raw_cups(&printer_name, &path, &document_name)
+    }
+
+    fn get_jobs(&self, printer_name: String) -> Result<Vec<PrintJob>, String> {
+        get_jobs_cups(&printer_name)
+    }
+
+    fn get_job(&self, printer_name: String, job_id: String) -> Result<Option<PrintJob>, String> {
+        get_job_cups(&printer_name, &job_id)
+    }
+
+    fn pause_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        change_job_cups(&printer_name, &job_id, "hold")
+    }
+
+    fn resume_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        change_job_cups(&printer_name, &job_id, "resume")
+    }
+
+    fn restart_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        change_job_cups(&printer_name, &job_id, "restart")
+    }
+
+    fn remove_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        cancel_job_cups(&printer_name, &job_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::PageRange;
+
+    #[test]
+    fn print_settings_become_lp_options() {
+        let settings = PrintSettings::from_legacy("A4,simplex,fit,color,landscape,2x,1-3,5").unwrap();
+        let args = lp_print_settings(&settings).unwrap().args;
+        assert_eq!(
+            args,
+            vec![
+                "-n", "2",
+                "-o", "media=A4",
+                "-o", "sides=one-sided",
+                "-o", "print-scaling=fit",
+                "-o", "fit-to-page",
+                "-o", "print-color-mode=color",
+                "-o", "orientation-requested=4",
+                "-o", "page-ranges=1-3,5",
+            ]
+        );
+
+        let settings = PrintSettings::from_legacy("letter,duplexshort,noscale,monochrome,portrait").unwrap();
+        let args = lp_print_settings(&settings).unwrap().args;
+        assert_eq!(
+            args,
+            vec![
+                "-o", "media=Letter",
+                "-o", "sides=two-sided-short-edge",
+                "-o", "print-scaling=none",
+                "-o", "print-color-mode=monochrome",
+                "-o", "orientation-requested=3",
+            ]
+        );
+
+        let settings = PrintSettings::from_legacy("76x130mm").unwrap();
+        assert_eq!(lp_print_settings(&settings).unwrap().args, vec!["-o", "media=Custom.76x130mm"]);
+
+        assert!(lp_print_settings(&PrintSettings::default()).unwrap().args.is_empty());
+        let invalid = PrintSettings { repeat: Some(0), ..PrintSettings::default() };
+        assert!(lp_print_settings(&invalid).is_err());
+        let invalid = PrintSettings { paper: Some(Paper::Custom { width_mm: 76, height_mm: 0 }), ..PrintSettings::default() };
+        assert!(lp_print_settings(&invalid).is_err());
+        let invalid = PrintSettings { range: Some(PageRange::List("3-1".to_string())), ..PrintSettings::default() };
+        assert!(lp_print_settings(&invalid).is_err());
+    }
+
+    // 同一个队列在不同系统语言下 lpstat -l -o 的输出，标签和日期格式各不相同
+    const JOBS_EN: &str = "Front_Desk-12          alice             1024   Sat 18 Oct 2026 10:00:00 AM CST
+\tStatus: Spooling job, 0% done.
+\tAlerts: job-printing
+\tqueued for Front_Desk
+Front_Desk-13          bob              20480   Sat 18 Oct 2026 10:01:00 AM CST
+\tAlerts: job-hold-until-specified
+\tqueued for Front_Desk
+";
+
+    const JOBS_ZH: &str = "前台-打印机-12          张三             1024   2026年10月18日 星期六 10时00分00秒
+\t状态：正在打印第 1 页
+\t警告：job-printing
+\t已为 前台-打印机 排队
+前台-打印机-13          李四            20480   2026年10月18日 星期六 10时01分00秒
+\t警告：job-hold-until-specified
+\t已为 前台-打印机 排队
+";
+
+    const JOBS_DE: &str = "Büro_Drucker-12       max              1024   Sa 18 Okt 10:00:00 2026
+\tStatus: Druckauftrag wird gespoolt
+\tWarnungen: job-printing
+\tin Warteschlange für Büro_Drucker
+Büro_Drucker-13       max             20480   Sa 18 Okt 10:01:00 2026
+\tWarnungen: job-hold-until-specified
+\tin Warteschlange für Büro_Drucker
+";
+
+    const JOBS_JA: &str = "事務所プリンタ-12      田中             1024   2026年10月18日 土曜日 10時00分00秒
+\tステータス: printing page 1
+\t警告: job-printing
+\t事務所プリンタ のキューに追加されました
+事務所プリンタ-13      田中            20480   2026年10月18日 土曜日 10時01分00秒
+\t警告: job-hold-until-specified
+";
+
+    #[test]
+    fn parse_jobs_in_several_languages() {
+        for (output, printer, user) in [
+            (JOBS_EN, "Front_Desk", "alice"),
+            (JOBS_ZH, "前台-打印机", "张三"),
+            (JOBS_DE, "Büro_Drucker", "max"),
+            (JOBS_JA, "事務所プリンタ", "田中"),
+        ] {
+            let jobs = parse_jobs(output);
+            assert_eq!(jobs.len(), 2, "{}", printer);
+            assert_eq!(jobs[0].printer_name, printer);
+            assert_eq!(jobs[0].job_id, "12");
+            assert_eq!(jobs[0].username, user);
+            assert_eq!(jobs[0].size, 1024);
+            assert_eq!(jobs[0].position, 1);
+            assert_eq!(jobs[0].job_status.as_ref().map(|s| s.code), Some(16), "{}", printer);
+            assert_eq!(jobs[1].job_id, "13");
+            assert_eq!(jobs[1].size, 20480);
+            assert_eq!(jobs[1].job_status.as_ref().map(|s| s.code), Some(1), "{}", printer);
+        }
+    }
+
+    #[test]
+    fn parse_submitted_time_in_c_locale() {
+        let jobs = parse_jobs("Front_Desk-12          alice             1024   Sun Oct 18 10:00:00 2026\n\tAlerts: job-printing\n");
+        assert!(jobs[0].submitted_time.is_some());
+        assert_eq!(jobs[0].create_time, jobs[0].submitted_time);
+        assert!(matches!(jobs[0].submitted_at.as_deref(), Some(at) if at.starts_with("2026-10-18T10:00:00.000")));
+        assert_eq!(parse_jobs(JOBS_ZH)[0].submitted_time, None);
+    }
+
+    #[test]
+    fn status_text_is_not_taken_as_alerts() {
+        assert_eq!(alerts_value("Status: job-printing failed, check printer"), None);
+        assert_eq!(alerts_value("状态：正在打印"), None);
+        assert_eq!(alerts_value("Alerts: job-printing"), Some("job-printing"));
+        assert_eq!(alerts_value("警告：job-stopped,printer-stopped"), Some("job-stopped,printer-stopped"));
+    }
+
+    #[test]
+    fn printer_names_are_preserved() {
+        let output = "Front_Desk\n前台-打印机\r\nBüro_Drucker\n事務所プリンタ\n\n";
+        assert_eq!(parse_printer_names(output), vec!["Front_Desk", "前台-打印机", "Büro_Drucker", "事務所プリンタ"]);
+    }
+
+    #[test]
+    fn request_id_in_several_languages() {
+        for (output, printer) in [
+            ("request id is Front_Desk-12 (1 file(s))\n", "Front_Desk"),
+            ("请求 ID 为 前台-打印机-12（1 个文件）\n", "前台-打印机"),
+            ("Anfrage-ID ist Büro_Drucker-12 (1 Datei(en))\n", "Büro_Drucker"),
+            ("リクエスト ID は 事務所プリンタ-12 です (1 個のファイル)\n", "\"事務所プリンタ\""),
+        ] {
+            assert_eq!(parse_request_id(output, printer), Some(format!("{}-12", queue_name(printer))), "{}", output);
+        }
+        assert_eq!(parse_request_id("lp: error - no default destination available.", "Front_Desk"), None);
+    }
+
+    #[test]
+    fn job_arg_accepts_number_or_full_id() {
+        assert_eq!(job_arg("\"前台-打印机\"", "12"), Ok(("前台-打印机-12".to_string(), "12".to_string())));
+        assert_eq!(job_arg("前台-打印机", "前台-打印机-12"), Ok(("前台-打印机-12".to_string(), "12".to_string())));
+        assert!(job_arg("前台-打印机", "其他-12").is_err());
+        assert!(job_arg("Front_Desk", "-a").is_err());
+        assert!(job_arg("Front_Desk", "12 -a").is_err());
+        assert!(job_arg("Front_Desk", "").is_err());
+    }
+
+    #[test]
+    fn lpoptions_keep_localized_values() {
+        let options = parse_lpoptions("device-uri=ipp://10.0.0.5/ipp/print printer-info='前台 打印机' printer-location=二楼\\ 前台 printer-state=5 printer-state-reasons=media-empty-error,paused");
+        assert_eq!(options["printer-info"], "前台 打印机");
+        assert_eq!(options["printer-location"], "二楼 前台");
+        let printer = printer_from_options("前台-打印机", &options);
+        assert_eq!(printer.name, "前台-打印机");
+        assert_eq!(printer.computer_name, "二楼 前台");
+        assert_eq!(printer.printer_status, 6);
+        assert_eq!(printer.state.reasons, vec![crate::declare::PrinterReason::PaperOut, crate::declare::PrinterReason::Paused]);
+    }
+}
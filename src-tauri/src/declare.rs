@@ -1,6 +1,424 @@
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Serialize, Deserialize};
+use crate::settings::PrintSettings;
+use crate::timestamp::Timestamp;
+
 pub struct PrintOptions {
     pub id: String,
     pub path: String,
-    pub print_setting: String,
+    pub print_setting: PrintSettings,
     pub remove_after_print: bool
 }
+
+// 打印目标：系统打印队列，或不经过系统队列的网络打印机
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Destination {
+    // 系统打印队列，id 与 print_pdf 的 id 相同
+    Queue { id: String },
+    // RAW 端口 (AppSocket/JetDirect)，数据原样发送给打印机
+    Raw {
+        host: String,
+        #[serde(default = "crate::raw::default_port")]
+        port: u16,
+    },
+    // LPD 队列 (RFC 1179)
+    Lpd {
+        host: String,
+        #[serde(default = "crate::lpd::default_port")]
+        port: u16,
+        queue: String,
+        #[serde(default = "default_copies")]
+        copies: u32,
+    },
+}
+
+impl Destination {
+    // 目标的唯一标识，用于保存按打印机区分的配置
+    pub fn key(&self) -> String {
+        match self {
+            Destination::Queue { id } => id.trim().trim_matches('"').to_string(),
+            Destination::Raw { host, port } => format!("raw://{}:{}", host, port),
+            Destination::Lpd { host, port, queue, .. } => format!("lpd://{}:{}/{}", host, port, queue),
+        }
+    }
+}
+
+fn default_copies() -> u32 {
+    1
+}
+
+// 打印机信息，字段与前端 types.ts 中的 Printer 保持一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Printer {
+    pub id: String,
+    pub name: String,
+    pub driver_name: String,
+    pub job_count: u32,
+    pub print_processor: String,
+    pub port_name: String,
+    pub share_name: String,
+    pub computer_name: String,
+    pub printer_status: u32,
+    pub shared: bool,
+    #[serde(rename = "type")]
+    pub printer_type: u32, // 0: local; 1: connection
+    pub priority: u32,
+    // 各平台统一的打印机状态，printer_status 保留各平台的原始取值
+    #[serde(default)]
+    pub state: PrinterState,
+}
+
+// 打印机当前的整体状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrinterStatus {
+    Idle,
+    Printing,
+    Paused,
+    Offline,
+    Error,
+    Unknown,
+}
+
+// 枚举上的 #[default] 需要 Rust 1.62，这里手写
+#[allow(clippy::derivable_impls)]
+impl Default for PrinterStatus {
+    fn default() -> Self {
+        PrinterStatus::Unknown
+    }
+}
+
+// 打印机处于当前状态的原因
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrinterReason {
+    Offline,
+    PaperOut,
+    PaperJam,
+    DoorOpen,
+    TonerLow,
+    TonerEmpty,
+    OutputBinFull,
+    Paused,
+    UserIntervention,
+    Error,
+}
+
+impl PrinterReason {
+    // 这些原因会导致作业无法打印
+    fn is_error(&self) -> bool {
+        !matches!(self, PrinterReason::TonerLow | PrinterReason::Paused | PrinterReason::Offline)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PrinterState {
+    pub status: PrinterStatus,
+    pub reasons: Vec<PrinterReason>,
+}
+
+// IPP / CUPS 的 printer-state-reasons 关键字对应的原因
+const IPP_STATE_REASONS: [(&str, PrinterReason); 15] = [
+    ("offline", PrinterReason::Offline),
+    ("shutdown", PrinterReason::Offline),
+    ("media-empty", PrinterReason::PaperOut),
+    ("media-needed", PrinterReason::PaperOut),
+    ("media-jam", PrinterReason::PaperJam),
+    ("door-open", PrinterReason::DoorOpen),
+    ("cover-open", PrinterReason::DoorOpen),
+    ("toner-low", PrinterReason::TonerLow),
+    ("marker-supply-low", PrinterReason::TonerLow),
+    ("toner-empty", PrinterReason::TonerEmpty),
+    ("marker-supply-empty", PrinterReason::TonerEmpty),
+    ("output-area-full", PrinterReason::OutputBinFull),
+    ("paused", PrinterReason::Paused),
+    ("moving-to-paused", PrinterReason::Paused),
+    ("other", PrinterReason::Error),
+];
+
+impl PrinterState {
+    // 按原因推断整体状态：错误优先，其次是脱机和暂停，最后才是是否正在打印
+    pub fn new(printing: bool, all_reasons: Vec<PrinterReason>) -> Self {
+        let mut reasons = Vec::new();
+        for reason in all_reasons {
+            if !reasons.contains(&reason) {
+                reasons.push(reason);
+            }
+        }
+        let status = if reasons.iter().any(PrinterReason::is_error) {
+            PrinterStatus::Error
+        } else if reasons.contains(&PrinterReason::Offline) {
+            PrinterStatus::Offline
+        } else if reasons.contains(&PrinterReason::Paused) {
+            PrinterStatus::Paused
+        } else if printing {
+            PrinterStatus::Printing
+        } else {
+            PrinterStatus::Idle
+        };
+        Self { status, reasons }
+    }
+
+    // Get-Printer 的 PrinterStatus，是 MSFT_Printer 的枚举值而不是位标志
+    // 0 正常 / 1 暂停 / 2 错误 / 4 卡纸 / 5 缺纸 / 8 脱机 / 11 打印中 / 18 墨粉不足 / 23 门盖打开 ...
+    pub fn from_windows_status(status: u32) -> Self {
+        let (printing, reason) = match status {
+            // 正常、等待删除、等待、初始化、预热、节能
+            0 | 3 | 14 | 16 | 17 | 25 => (false, None),
+            1 => (false, Some(PrinterReason::Paused)),
+            2 | 7 | 20 | 22 => (false, Some(PrinterReason::Error)),
+            4 => (false, Some(PrinterReason::PaperJam)),
+            5 => (false, Some(PrinterReason::PaperOut)),
+            6 | 21 => (false, Some(PrinterReason::UserIntervention)),
+            8 | 13 => (false, Some(PrinterReason::Offline)),
+            // 正在传输、忙、打印中、处理中
+            9 | 10 | 11 | 15 => (true, None),
+            12 => (false, Some(PrinterReason::OutputBinFull)),
+            18 => (false, Some(PrinterReason::TonerLow)),
+            19 => (false, Some(PrinterReason::TonerEmpty)),
+            23 => (false, Some(PrinterReason::DoorOpen)),
+            // 24 服务器状态未知，以及未定义的取值
+            _ => return Self::default(),
+        };
+        Self::new(printing, reason.into_iter().collect())
+    }
+
+    // Win32_Printer 的 PrinterStatus (3 空闲 / 4 打印中 / 7 脱机 ...) 和 DetectedErrorState
+    pub fn from_wmi(printer_status: u32, detected_error_state: u32, work_offline: bool) -> Self {
+        let mut reasons = Vec::new();
+        if work_offline || printer_status == 7 {
+            reasons.push(PrinterReason::Offline);
+        }
+        match detected_error_state {
+            5 => reasons.push(PrinterReason::TonerLow),
+            4 => reasons.push(PrinterReason::PaperOut),
+            6 => reasons.push(PrinterReason::TonerEmpty),
+            7 => reasons.push(PrinterReason::DoorOpen),
+            8 => reasons.push(PrinterReason::PaperJam),
+            9 => reasons.push(PrinterReason::Offline),
+            10 => reasons.push(PrinterReason::UserIntervention),
+            11 => reasons.push(PrinterReason::OutputBinFull),
+            _ => {}
+        }
+        let mut state = Self::new(printer_status == 4, reasons);
+        // 1 其他 / 2 未知
+        if printer_status <= 2 && state.reasons.is_empty() {
+            state.status = PrinterStatus::Unknown;
+        }
+        state
+    }
+
+    // IPP printer-state (3 空闲 / 4 打印中 / 5 已停止) 和 printer-state-reasons 关键字
+    // 关键字可能带 -error / -warning / -report 后缀，none 表示没有异常
+    pub fn from_ipp<'a, I: IntoIterator<Item = &'a str>>(printer_state: i32, state_reasons: I) -> Self {
+        let mut reasons = Vec::new();
+        for keyword in state_reasons {
+            let keyword = keyword.trim();
+            let base = keyword
+                .strip_suffix("-error")
+                .or_else(|| keyword.strip_suffix("-warning"))
+                .or_else(|| keyword.strip_suffix("-report"))
+                .unwrap_or(keyword);
+            if let Some((_, reason)) = IPP_STATE_REASONS.iter().find(|(name, _)| *name == base) {
+                reasons.push(*reason);
+            } else if keyword.ends_with("-error") {
+                reasons.push(PrinterReason::Error);
+            }
+        }
+        // 已停止但没有给出原因时按暂停处理 (cupsdisable)
+        if printer_state == 5 && reasons.is_empty() {
+            reasons.push(PrinterReason::Paused);
+        }
+        let mut state = Self::new(printer_state == 4, reasons);
+        if !(3..=5).contains(&printer_state) && state.reasons.is_empty() {
+            state.status = PrinterStatus::Unknown;
+        }
+        state
+    }
+}
+
+impl Printer {
+    // 前端以 base64 编码后的打印机名称作为 ID
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: general_purpose::STANDARD.encode(name),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// 打印作业状态，字段与前端 types.ts 中的 JobsStatus 保持一致
+// flags 为 code 中所有置位的状态名称，name 为其中最主要的一个
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub code: u32,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+// Windows JobStatus 的各个标志位，对应前端 constants.ts 中的 jobStatus
+// 多个标志位同时置位时 (例如 Error | PaperOut | Printing)，排在前面的作为主要状态
+const JOB_STATUS_FLAGS: [(u32, &str, &str); 14] = [
+    (64, "PaperOut", "The printer is out of the required paper size."),
+    (32, "Offline", "The printer is offline."),
+    (1024, "UserIntervention", "The printer requires user action to fix an error condition."),
+    (2, "Error", "The print job is in an error state."),
+    (512, "Blocked", "An error condition, possibly on a print job that precedes this one in the queue, blocked the print job."),
+    (4, "Deleting", "The print job is in the process of being deleted."),
+    (256, "Deleted", "The print job was deleted from the queue, typically after printing."),
+    (4096, "Completed", "The print job is complete, including any post-printing processing."),
+    (128, "Printed", "The print job printed."),
+    (8192, "Retained", "The print job is retained in the print queue after printing."),
+    (1, "Paused", "The print job is paused."),
+    (16, "Printing", "The print job is now printing."),
+    (8, "Spooling", "The print job is spooling."),
+    (2048, "Restarted", "The print job was blocked but has restarted."),
+];
+
+impl JobStatus {
+    // 按 Windows JobStatus 位标志解析状态，对应前端 constants.ts 中的 jobStatus
+    pub fn from_code(code: u32) -> Self {
+        if code == 0 {
+            return Self {
+                code,
+                name: "None".to_string(),
+                description: "The print job has no specified state.".to_string(),
+                flags: Vec::new(),
+            };
+        }
+
+        let mut set: Vec<(u32, &str, &str)> = JOB_STATUS_FLAGS.iter().copied().filter(|(bit, _, _)| code & bit != 0).collect();
+        let (name, description) = match set.first() {
+            Some((_, name, description)) => (*name, *description),
+            None => ("Unknown", "Unknown Job Status"),
+        };
+        set.sort_by_key(|(bit, _, _)| *bit);
+        Self {
+            code,
+            name: name.to_string(),
+            description: description.to_string(),
+            flags: set.into_iter().map(|(_, name, _)| name.to_string()).collect(),
+        }
+    }
+
+    pub fn has(&self, bit: u32) -> bool {
+        self.code & bit != 0
+    }
+}
+
+// 打印作业信息，字段与前端 types.ts 中的 Jobs 保持一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PrintJob {
+    pub id: String,
+    pub job_id: String,
+    pub printer_name: String,
+    pub job_status: Option<JobStatus>,
+    pub computer_name: String,
+    pub data_type: String,
+    pub document_name: String,
+    pub size: u64,
+    pub job_time: u64,
+    pub pages_printed: u32,
+    pub total_pages: u32,
+    pub position: u32,
+    pub priority: u32,
+    // 提交时间，UTC 毫秒时间戳
+    pub submitted_time: Option<i64>,
+    pub create_time: Option<i64>,
+    // 提交时间，带时区的 ISO 8601 字符串
+    #[serde(default)]
+    pub submitted_at: Option<String>,
+    pub username: String,
+}
+
+impl PrintJob {
+    // 前端以 base64 编码后的 "打印机名称_@_作业ID" 作为作业 ID
+    pub fn new(printer_name: &str, job_id: &str) -> Self {
+        Self {
+            id: general_purpose::STANDARD.encode(format!("{}_@_{}", printer_name, job_id)),
+            job_id: job_id.to_string(),
+            printer_name: printer_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // 各平台的作业只有一个提交时间，submitted_time 与 create_time 相同
+    pub fn with_submitted(self, time: Option<Timestamp>) -> Self {
+        Self {
+            submitted_time: time.map(|time| time.millis()),
+            create_time: time.map(|time| time.millis()),
+            submitted_at: time.map(|time| time.iso()),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PrinterReason::*;
+
+    fn state(status: PrinterStatus, reasons: Vec<PrinterReason>) -> PrinterState {
+        PrinterState { status, reasons }
+    }
+
+    #[test]
+    fn windows_printer_status_enum() {
+        assert_eq!(PrinterState::from_windows_status(0), state(PrinterStatus::Idle, vec![]));
+        assert_eq!(PrinterState::from_windows_status(25), state(PrinterStatus::Idle, vec![]));
+        assert_eq!(PrinterState::from_windows_status(11), state(PrinterStatus::Printing, vec![]));
+        assert_eq!(PrinterState::from_windows_status(9), state(PrinterStatus::Printing, vec![]));
+        assert_eq!(PrinterState::from_windows_status(1), state(PrinterStatus::Paused, vec![Paused]));
+        assert_eq!(PrinterState::from_windows_status(8), state(PrinterStatus::Offline, vec![Offline]));
+        assert_eq!(PrinterState::from_windows_status(2), state(PrinterStatus::Error, vec![Error]));
+        assert_eq!(PrinterState::from_windows_status(4), state(PrinterStatus::Error, vec![PaperJam]));
+        assert_eq!(PrinterState::from_windows_status(5), state(PrinterStatus::Error, vec![PaperOut]));
+        assert_eq!(PrinterState::from_windows_status(12), state(PrinterStatus::Error, vec![OutputBinFull]));
+        assert_eq!(PrinterState::from_windows_status(19), state(PrinterStatus::Error, vec![TonerEmpty]));
+        assert_eq!(PrinterState::from_windows_status(21), state(PrinterStatus::Error, vec![UserIntervention]));
+        assert_eq!(PrinterState::from_windows_status(23), state(PrinterStatus::Error, vec![DoorOpen]));
+        // 墨粉不足不影响打印
+        assert_eq!(PrinterState::from_windows_status(18), state(PrinterStatus::Idle, vec![TonerLow]));
+        // 服务器状态未知和未定义的取值，例如按位标志理解的 0x80
+        assert_eq!(PrinterState::from_windows_status(24), PrinterState::default());
+        assert_eq!(PrinterState::from_windows_status(0x80), PrinterState::default());
+    }
+
+    #[test]
+    fn wmi_status_and_error_state() {
+        assert_eq!(PrinterState::from_wmi(3, 0, false), state(PrinterStatus::Idle, vec![]));
+        assert_eq!(PrinterState::from_wmi(4, 2, false), state(PrinterStatus::Printing, vec![]));
+        assert_eq!(PrinterState::from_wmi(7, 0, false), state(PrinterStatus::Offline, vec![Offline]));
+        assert_eq!(PrinterState::from_wmi(3, 0, true), state(PrinterStatus::Offline, vec![Offline]));
+        assert_eq!(PrinterState::from_wmi(7, 9, true), state(PrinterStatus::Offline, vec![Offline]));
+        assert_eq!(PrinterState::from_wmi(3, 4, false), state(PrinterStatus::Error, vec![PaperOut]));
+        assert_eq!(PrinterState::from_wmi(1, 8, false), state(PrinterStatus::Error, vec![PaperJam]));
+        assert_eq!(PrinterState::from_wmi(3, 5, false), state(PrinterStatus::Idle, vec![TonerLow]));
+        // 其他 / 未知且没有原因时无法判断
+        assert_eq!(PrinterState::from_wmi(2, 0, false), state(PrinterStatus::Unknown, vec![]));
+        assert_eq!(PrinterState::from_wmi(1, 0, false), state(PrinterStatus::Unknown, vec![]));
+    }
+
+    #[test]
+    fn ipp_state_and_reasons() {
+        assert_eq!(PrinterState::from_ipp(3, ["none"]), state(PrinterStatus::Idle, vec![]));
+        assert_eq!(PrinterState::from_ipp(4, Vec::new()), state(PrinterStatus::Printing, vec![]));
+        // 已停止但没有原因按暂停处理
+        assert_eq!(PrinterState::from_ipp(5, ["none"]), state(PrinterStatus::Paused, vec![Paused]));
+        assert_eq!(PrinterState::from_ipp(5, ["paused"]), state(PrinterStatus::Paused, vec![Paused]));
+        // 后缀去掉后再匹配，重复的原因只记一次
+        assert_eq!(
+            PrinterState::from_ipp(4, ["media-empty-error", "media-needed-warning", " toner-low-report "]),
+            state(PrinterStatus::Error, vec![PaperOut, TonerLow])
+        );
+        assert_eq!(PrinterState::from_ipp(3, ["offline-report"]), state(PrinterStatus::Offline, vec![Offline]));
+        // 不认识的 -error 关键字按一般错误处理，其他不认识的关键字忽略
+        assert_eq!(PrinterState::from_ipp(3, ["fuser-over-temp-error"]), state(PrinterStatus::Error, vec![Error]));
+        assert_eq!(PrinterState::from_ipp(3, ["cups-insecure-filter-warning"]), state(PrinterStatus::Idle, vec![]));
+        assert_eq!(PrinterState::from_ipp(0, Vec::new()), state(PrinterStatus::Unknown, vec![]));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+use crate::backend::PrinterBackend;
+use crate::declare::{Destination, PrintOptions};
+use crate::fsys::remove_file;
+use crate::journal::DocumentFormat;
+use crate::lpd::{total_copies, LpdJob, LpdPrinter};
+use crate::raw::RawPrinter;
+use crate::retry::{self, PrintError};
+use crate::settings::PrintSettings;
+
+// 按打印目标发送文件：系统队列交给打印后端，网络打印机直接发送文件内容
+// 返回打印队列中的作业 ID，RAW 端口没有作业的概念，后端无法确定作业 ID 时也返回 None
+pub fn print_to(
+    backend: &dyn PrinterBackend,
+    destination: Destination,
+    path: String,
+    format: &DocumentFormat,
+    print_setting: PrintSettings,
+    remove_after_print: bool,
+) -> Result<Option<String>, PrintError> {
+    match destination {
+        // 打印机指令绕过驱动直接写入系统队列
+        Destination::Queue { id } if matches!(format, DocumentFormat::Raw { .. }) => {
+            let document_name = document_name(format, &path);
+            let job_id = backend
+                .print_raw(id.clone(), path.clone(), document_name)
+                .map_err(|error| queue_error(backend, id, error))?;
+            if remove_after_print {
+                remove_document(&path);
+            }
+            Ok(Some(job_id).filter(|job_id| !job_id.is_empty()))
+        }
+        Destination::Queue { id } => {
+            let options = PrintOptions {
+                id: id.clone(),
+                path,
+                print_setting,
+                remove_after_print,
+            };
+            let job_id = backend.print_pdf(options).map_err(|error| queue_error(backend, id, error))?;
+            Ok(Some(job_id).filter(|job_id| !job_id.is_empty()))
+        }
+        Destination::Raw { host, port } => {
+            let data = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+            let written = RawPrinter::new(&host, port).send(&data)?;
+            println!("RAW 打印成功: {}:{} 共 {} 字节", host, port, written);
+
+            if remove_after_print {
+                remove_document(&path);
+            }
+            Ok(None)
+        }
+        Destination::Lpd { host, port, queue, copies } => {
+            let data = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+            let job_name = document_name(format, &path);
+            // LPD 控制文件可以指定份数，其余打印设置无法传给打印机
+            let copies = total_copies(copies, print_setting.copies()?)?;
+            let job_number = LpdPrinter::new(&host, port, &queue).send_job(&LpdJob::new(&job_name, copies), &data)?;
+
+            if remove_after_print {
+                remove_document(&path);
+            }
+            Ok(Some(job_number.to_string()))
+        }
+    }
+}
+
+// 系统队列提交失败时，按打印机当前的状态判断能否重试
+fn queue_error(backend: &dyn PrinterBackend, printer_name: String, message: String) -> PrintError {
+    let class = retry::classify_printer(&backend.get_printer(printer_name));
+    PrintError { class, message }
+}
+
+// 已经发送给打印机后再删除文件，删除失败只记录日志，不能当作打印失败而重试
+fn remove_document(path: &str) {
+    if let Err(e) = remove_file(path) {
+        println!("删除文件失败: {}", e);
+    }
+}
+
+// 打印队列中显示的作业名称：打印机指令使用提交时给定的名称，其余使用文件名
+fn document_name(format: &DocumentFormat, path: &str) -> String {
+    match format {
+        DocumentFormat::Raw { document_name } => document_name.clone(),
+        DocumentFormat::Pdf => Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string()),
+    }
+}
+
+// 打印设置中在该目标上不会生效的选项
+// RAW 端口原样发送文件，所有设置都不生效；LPD 只支持份数
+pub fn unsupported_settings(backend: &dyn PrinterBackend, destination: &Destination, settings: &PrintSettings) -> Vec<&'static str> {
+    match destination {
+        Destination::Queue { .. } => backend.unsupported_settings(settings),
+        Destination::Raw { .. } => settings.specified(),
+        Destination::Lpd { .. } => settings.specified().into_iter().filter(|name| *name != "repeat").collect(),
+    }
+}
+
+// 目标名称，用于拼接返回给前端的提示信息
+pub fn destination_name(backend: &dyn PrinterBackend, destination: &Destination) -> &'static str {
+    match destination {
+        Destination::Queue { .. } => backend.name(),
+        Destination::Raw { .. } => "RAW",
+        Destination::Lpd { .. } => "LPD",
+    }
+}
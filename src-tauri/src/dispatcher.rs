@@ -0,0 +1,669 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use parking_lot::{Condvar, Mutex};
+use serde::{Serialize, Deserialize};
+use tauri::State;
+use crate::backend::{PrinterBackend, SharedBackend};
+use crate::declare::Destination;
+use crate::fsys::remove_file;
+use crate::journal::{self, now_millis, JobJournal, JobRecord, JobState, SharedJournal};
+use crate::retry;
+use crate::settings::PrintSettings;
+
+// 默认同时向几台打印机提交作业
+const DEFAULT_CONCURRENCY: usize = 2;
+// 等待重试期间多久检查一次作业状态，被取消的作业不必等到重试时间
+const RETRY_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DispatcherConfig {
+    pub concurrency: usize,
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+// 提交后立即返回给调用方的作业句柄，之后通过 get_queued_job 查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct JobHandle {
+    pub job_id: String,
+    // 提交时同一打印机前面还有几个作业
+    pub queue_position: usize,
+    pub message: String,
+}
+
+impl JobHandle {
+    pub fn with_message(self, message: String) -> Self {
+        Self { message, ..self }
+    }
+}
+
+// 全局并发限制：同时处于提交中的作业数，上限可以在运行时调整
+struct Slots {
+    // (正在提交的作业数, 上限)
+    state: Mutex<(usize, usize)>,
+    available: Condvar,
+}
+
+impl Slots {
+    fn acquire(&self) {
+        let mut state = self.state.lock();
+        while state.0 >= state.1 {
+            self.available.wait(&mut state);
+        }
+        state.0 += 1;
+    }
+
+    fn release(&self) {
+        self.state.lock().0 -= 1;
+        self.available.notify_one();
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.state.lock().1 = limit;
+        self.available.notify_all();
+    }
+}
+
+// 一个打印目标的作业队列
+struct PrinterQueue {
+    sender: mpsc::Sender<JobRecord>,
+    // 尚未结束的作业数，包括正在打印的作业
+    pending: Arc<Mutex<usize>>,
+}
+
+pub type SharedDispatcher = Arc<Dispatcher>;
+
+// 打印作业调度：每个打印目标一个 worker 线程，按提交顺序逐个打印，避免同一台打印机的作业交错
+// 不同打印机之间并行，但同时提交中的作业数不超过配置的上限
+pub struct Dispatcher {
+    journal: SharedJournal,
+    backend: SharedBackend,
+    config_path: PathBuf,
+    slots: Arc<Slots>,
+    // 以 Destination::key() 区分打印目标
+    queues: Mutex<HashMap<String, PrinterQueue>>,
+}
+
+impl Dispatcher {
+    pub fn new(journal: SharedJournal, backend: SharedBackend, config_path: PathBuf) -> Self {
+        let config: DispatcherConfig = fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        println!("打印并发上限: {}", config.concurrency);
+
+        Self {
+            journal,
+            backend,
+            config_path,
+            slots: Arc::new(Slots {
+                state: Mutex::new((0, config.concurrency.max(1))),
+                available: Condvar::new(),
+            }),
+            queues: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 记录作业并加入对应打印机的队列
+    pub fn submit(
+        &self,
+        destination: Destination,
+        document: String,
+        print_setting: PrintSettings,
+        remove_after_print: bool,
+    ) -> Result<JobHandle, String> {
+        let job = self.journal.submit(destination, document, print_setting, remove_after_print)?;
+        self.dispatch(job)
+    }
+
+    // 记录打印机指令作业并加入队列，document 为写有指令的临时文件
+    pub fn submit_raw(&self, destination: Destination, document: String, document_name: &str) -> Result<JobHandle, String> {
+        let job = self.journal.submit_raw(destination, document, document_name)?;
+        self.dispatch(job)
+    }
+
+    // 把已经记录在日志中的作业加入对应打印机的队列，用于先记录、生成文件后再打印的作业
+    pub fn dispatch(&self, job: JobRecord) -> Result<JobHandle, String> {
+        let job_id = job.id.clone();
+        let queue_position = self.enqueue(job)?;
+        Ok(JobHandle {
+            job_id,
+            queue_position,
+            message: String::new(),
+        })
+    }
+
+    // 继续打印上次退出前尚未提交的作业
+    pub fn resume(&self) {
+        let jobs = self.journal.recover();
+        if !jobs.is_empty() {
+            println!("继续打印上次未完成的作业: {} 个", jobs.len());
+        }
+        for job in jobs {
+            if let Err(e) = self.enqueue(job) {
+                println!("作业加入队列失败: {}", e);
+            }
+        }
+    }
+
+    fn enqueue(&self, job: JobRecord) -> Result<usize, String> {
+        let key = job.destination.key();
+        let mut queues = self.queues.lock();
+        let queue = queues.entry(key.clone()).or_insert_with(|| self.spawn_worker(&key));
+
+        let mut count = queue.pending.lock();
+        let position = *count;
+        queue.sender.send(job).map_err(|_| format!("打印队列已关闭: {}", key))?;
+        *count += 1;
+        Ok(position)
+    }
+
+    fn spawn_worker(&self, key: &str) -> PrinterQueue {
+        let (sender, receiver) = mpsc::channel::<JobRecord>();
+        let pending = Arc::new(Mutex::new(0));
+
+        let journal = self.journal.clone();
+        let backend = self.backend.clone();
+        let slots = self.slots.clone();
+        let worker_pending = pending.clone();
+        let name = key.to_string();
+        thread::spawn(move || {
+            println!("打印队列已启动: {}", name);
+            for job in receiver {
+                run_job(&journal, backend.as_ref(), &slots, &job.id);
+                *worker_pending.lock() -= 1;
+            }
+        });
+
+        PrinterQueue { sender, pending }
+    }
+
+    // 死信列表：重试次数用完的作业，以及应用退出时中断、无法确认是否已打印的作业
+    pub fn dead_letters(&self) -> Vec<JobRecord> {
+        self.journal.list().into_iter().filter(|job| job.state.needs_attention()).collect()
+    }
+
+    // 把死信或中断的作业重新加入打印队列，提交次数从头计算
+    pub fn retry_dead_letter(&self, id: &str) -> Result<JobHandle, String> {
+        let job = self.dead_letter(id)?;
+        if !Path::new(&job.document).exists() {
+            return Err(format!("文件不存在: {}", job.document));
+        }
+        let job = self.journal.requeue(id)?;
+        self.dispatch(job)
+    }
+
+    // 丢弃死信或中断的作业，打印后需要删除的临时文件一并删除
+    pub fn discard_dead_letter(&self, id: &str) -> Result<(), String> {
+        let job = self.dead_letter(id)?;
+        self.journal.transition(id, JobState::Cancelled, "从死信列表丢弃")?;
+        if job.remove_after_print {
+            if let Err(e) = remove_file(&job.document) {
+                println!("删除文件失败: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    // 取消尚未提交到打印机的作业，正在提交中的作业无法取消
+    pub fn cancel(&self, id: &str) -> Result<(), String> {
+        match self.journal.get(id) {
+            Some(job) if matches!(job.state, JobState::Received | JobState::Retrying) => {
+                self.journal.transition(id, JobState::Cancelled, "已取消")?;
+                Ok(())
+            }
+            Some(job) => Err(format!("作业当前状态无法取消: {:?}", job.state)),
+            None => Err(format!("打印作业不存在: {}", id)),
+        }
+    }
+
+    fn dead_letter(&self, id: &str) -> Result<JobRecord, String> {
+        match self.journal.get(id) {
+            Some(job) if job.state.needs_attention() => Ok(job),
+            Some(_) => Err(format!("作业不在死信列表中: {}", id)),
+            None => Err(format!("打印作业不存在: {}", id)),
+        }
+    }
+
+    pub fn config(&self) -> DispatcherConfig {
+        DispatcherConfig {
+            concurrency: self.slots.state.lock().1,
+        }
+    }
+
+    pub fn set_concurrency(&self, concurrency: usize) -> Result<(), String> {
+        if concurrency == 0 {
+            return Err("并发上限必须大于 0".to_string());
+        }
+        self.slots.set_limit(concurrency);
+
+        if let Some(dir) = self.config_path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(&self.config()).map_err(|e| e.to_string())?;
+        fs::write(&self.config_path, content).map_err(|e| format!("保存调度配置失败: {}", e))
+    }
+}
+
+// 按重试策略提交一个作业，直到成功、失败、取消或进入死信列表
+// 等待重试时不占用全局并发名额，但同一打印机后面的作业继续排队，保证顺序
+// 等待期间定时重新读取作业，被取消的作业立即让出队列，不再提交
+fn run_job(journal: &JobJournal, backend: &dyn PrinterBackend, slots: &Slots, id: &str) {
+    loop {
+        let job = match journal.get(id) {
+            Some(job) if job.state.is_active() => job,
+            _ => return,
+        };
+        if let Some(next_attempt_at) = job.next_attempt_at {
+            let now = now_millis();
+            if next_attempt_at > now {
+                thread::sleep(Duration::from_millis(next_attempt_at - now).min(RETRY_CHECK_INTERVAL));
+                continue;
+            }
+        }
+
+        slots.acquire();
+        if !matches!(journal.get(id), Some(job) if job.state.is_active()) {
+            slots.release();
+            return;
+        }
+        let result = journal::execute(journal, backend, &job);
+        slots.release();
+
+        match result {
+            Ok(_) => return,
+            Err(error) => {
+                if retry::after_failure(journal, id, &error).is_none() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_dead_letters(dispatcher: State<'_, SharedDispatcher>) -> Vec<JobRecord> {
+    dispatcher.dead_letters()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn retry_dead_letter(job_id: String, dispatcher: State<'_, SharedDispatcher>) -> Result<JobHandle, String> {
+    println!("重试死信作业: {}", job_id);
+    dispatcher.retry_dead_letter(&job_id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn discard_dead_letter(job_id: String, dispatcher: State<'_, SharedDispatcher>) -> Result<(), String> {
+    println!("丢弃死信作业: {}", job_id);
+    dispatcher.discard_dead_letter(&job_id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn cancel_queued_job(job_id: String, dispatcher: State<'_, SharedDispatcher>) -> Result<(), String> {
+    println!("取消排队中的作业: {}", job_id);
+    dispatcher.cancel(&job_id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_print_concurrency(dispatcher: State<'_, SharedDispatcher>) -> usize {
+    dispatcher.config().concurrency
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_print_concurrency(concurrency: usize, dispatcher: State<'_, SharedDispatcher>) -> Result<(), String> {
+    println!("设置打印并发上限: {}", concurrency);
+    dispatcher.set_concurrency(concurrency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+    use crate::declare::{PrintJob, PrintOptions, Printer};
+    use crate::fsys::unique_temp_path;
+
+    // 记录提交顺序的打印后端，闸门关闭时提交会阻塞，用于观察并发数
+    #[derive(Default)]
+    struct GatedBackend {
+        printed: Mutex<Vec<(String, String)>>,
+        active: AtomicUsize,
+        peak: AtomicUsize,
+        // 还能放行几次提交，None 表示不限
+        permits: Mutex<Option<usize>>,
+        released: Condvar,
+        // 接下来几次提交以打印机脱机失败
+        offline: AtomicUsize,
+    }
+
+    impl GatedBackend {
+        fn closed() -> Arc<Self> {
+            let backend = Self::default();
+            *backend.permits.lock() = Some(0);
+            Arc::new(backend)
+        }
+
+        fn open(&self) {
+            *self.permits.lock() = None;
+            self.released.notify_all();
+        }
+
+        fn release(&self, count: usize) {
+            if let Some(permits) = self.permits.lock().as_mut() {
+                *permits += count;
+            }
+            self.released.notify_all();
+        }
+
+        fn printed_to(&self, printer: &str) -> Vec<String> {
+            self.printed
+                .lock()
+                .iter()
+                .filter(|(id, _)| id == printer)
+                .map(|(_, path)| path.clone())
+                .collect()
+        }
+    }
+
+    impl PrinterBackend for GatedBackend {
+        fn name(&self) -> &'static str {
+            "Test"
+        }
+
+        fn get_printers(&self) -> Result<Vec<Printer>, String> {
+            Ok(Vec::new())
+        }
+
+        // 只在提交失败后用来判断错误类型，查询失败按暂时性错误重试
+        fn get_printer(&self, _printer_name: String) -> Result<Option<Printer>, String> {
+            Err("打印机无响应".to_string())
+        }
+
+        fn print_pdf(&self, options: PrintOptions) -> Result<String, String> {
+            if self.offline.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| count.checked_sub(1)).is_ok() {
+                return Err("打印机脱机".to_string());
+            }
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            let mut permits = self.permits.lock();
+            while *permits == Some(0) {
+                self.released.wait(&mut permits);
+            }
+            if let Some(permits) = permits.as_mut() {
+                *permits -= 1;
+            }
+            drop(permits);
+            self.printed.lock().push((options.id, options.path));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok("1".to_string())
+        }
+
+        fn get_jobs(&self, _printer_name: String) -> Result<Vec<PrintJob>, String> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn dispatcher(backend: Arc<GatedBackend>, config: Option<DispatcherConfig>) -> Dispatcher {
+        let dir = unique_temp_path("dispatcher_test", "d");
+        let config_path = dir.join("dispatcher.json");
+        if let Some(config) = config {
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+        }
+        let journal = Arc::new(JobJournal::open(dir.join("print_jobs.jsonl")));
+        Dispatcher::new(journal, backend, config_path)
+    }
+
+    fn queue(id: &str) -> Destination {
+        Destination::Queue { id: id.to_string() }
+    }
+
+    fn submit(dispatcher: &Dispatcher, printer: &str, document: &str) -> JobHandle {
+        dispatcher
+            .submit(queue(printer), document.to_string(), PrintSettings::default(), false)
+            .unwrap()
+    }
+
+    fn wait_until<F: Fn() -> bool>(condition: F) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "等待超时");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn state(dispatcher: &Dispatcher, id: &str) -> JobState {
+        dispatcher.journal.get(id).unwrap().state
+    }
+
+    #[test]
+    fn jobs_for_one_printer_run_in_order() {
+        let backend = Arc::new(GatedBackend::default());
+        let dispatcher = dispatcher(backend.clone(), None);
+
+        let mut handles = Vec::new();
+        for index in 0..6 {
+            handles.push(submit(&dispatcher, "A", &format!("a{}.pdf", index)));
+            handles.push(submit(&dispatcher, "B", &format!("b{}.pdf", index)));
+        }
+        wait_until(|| handles.iter().all(|handle| state(&dispatcher, &handle.job_id) == JobState::Spooled));
+
+        let expected = |prefix: &str| (0..6).map(|index| format!("{}{}.pdf", prefix, index)).collect::<Vec<_>>();
+        assert_eq!(backend.printed_to("A"), expected("a"));
+        assert_eq!(backend.printed_to("B"), expected("b"));
+        // 同一打印机同时只提交一个作业
+        assert!(backend.peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn queue_position_counts_pending_jobs() {
+        let backend = GatedBackend::closed();
+        let dispatcher = dispatcher(backend.clone(), None);
+
+        let positions: Vec<usize> = (0..3).map(|index| submit(&dispatcher, "A", &format!("{}.pdf", index)).queue_position).collect();
+        assert_eq!(positions, vec![0, 1, 2]);
+        assert_eq!(submit(&dispatcher, "B", "b.pdf").queue_position, 0);
+
+        // 作业结束后不再计入排队数
+        backend.open();
+        wait_until(|| *dispatcher.queues.lock()["A"].pending.lock() == 0);
+        assert_eq!(submit(&dispatcher, "A", "next.pdf").queue_position, 0);
+    }
+
+    #[test]
+    fn concurrency_is_capped_across_printers() {
+        let backend = GatedBackend::closed();
+        let dispatcher = dispatcher(backend.clone(), Some(DispatcherConfig { concurrency: 2 }));
+        assert_eq!(dispatcher.config().concurrency, 2);
+
+        let handles: Vec<JobHandle> = ["A", "B", "C", "D"].iter().map(|printer| submit(&dispatcher, printer, "x.pdf")).collect();
+        wait_until(|| backend.active.load(Ordering::SeqCst) == 2);
+        // 其余两台打印机在等待名额，不会进入提交
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(backend.active.load(Ordering::SeqCst), 2);
+        let waiting = handles.iter().filter(|handle| state(&dispatcher, &handle.job_id) == JobState::Received).count();
+        assert_eq!(waiting, 2);
+
+        backend.open();
+        wait_until(|| handles.iter().all(|handle| state(&dispatcher, &handle.job_id) == JobState::Spooled));
+        assert_eq!(backend.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn raising_concurrency_wakes_waiting_workers() {
+        let backend = GatedBackend::closed();
+        let dispatcher = dispatcher(backend.clone(), Some(DispatcherConfig { concurrency: 1 }));
+
+        for printer in ["A", "B", "C"] {
+            submit(&dispatcher, printer, "x.pdf");
+        }
+        wait_until(|| backend.active.load(Ordering::SeqCst) == 1);
+
+        // 调整上限时已有作业阻塞在提交中，等待名额的作业立即开始
+        dispatcher.set_concurrency(3).unwrap();
+        wait_until(|| backend.active.load(Ordering::SeqCst) == 3);
+        backend.open();
+        wait_until(|| backend.printed.lock().len() == 3);
+
+        // 上限写入配置文件，下次启动时沿用
+        let saved: DispatcherConfig = serde_json::from_str(&fs::read_to_string(&dispatcher.config_path).unwrap()).unwrap();
+        assert_eq!(saved.concurrency, 3);
+        assert!(dispatcher.set_concurrency(0).is_err());
+        assert_eq!(dispatcher.config().concurrency, 3);
+    }
+
+    #[test]
+    fn lowering_concurrency_takes_effect_as_slots_free() {
+        let backend = GatedBackend::closed();
+        let dispatcher = dispatcher(backend.clone(), Some(DispatcherConfig { concurrency: 3 }));
+
+        for printer in ["A", "B", "C"] {
+            submit(&dispatcher, printer, "1.pdf");
+        }
+        wait_until(|| backend.active.load(Ordering::SeqCst) == 3);
+        dispatcher.set_concurrency(1).unwrap();
+        for printer in ["A", "B", "C"] {
+            submit(&dispatcher, printer, "2.pdf");
+        }
+
+        // 正在提交的作业不受影响，名额释放后同时提交的作业不超过新的上限
+        backend.release(3);
+        for printed in 3..6 {
+            wait_until(|| backend.printed.lock().len() == printed && backend.active.load(Ordering::SeqCst) == 1);
+            thread::sleep(Duration::from_millis(30));
+            assert_eq!(backend.active.load(Ordering::SeqCst), 1);
+            backend.release(1);
+        }
+        wait_until(|| backend.printed.lock().len() == 6);
+    }
+
+    #[test]
+    fn cancelled_job_is_skipped() {
+        let backend = GatedBackend::closed();
+        let dispatcher = dispatcher(backend.clone(), None);
+
+        let first = submit(&dispatcher, "A", "1.pdf");
+        let second = submit(&dispatcher, "A", "2.pdf");
+        wait_until(|| backend.active.load(Ordering::SeqCst) == 1);
+        assert!(dispatcher.cancel(&first.job_id).is_err());
+        dispatcher.cancel(&second.job_id).unwrap();
+
+        backend.open();
+        wait_until(|| state(&dispatcher, &first.job_id) == JobState::Spooled);
+        let third = submit(&dispatcher, "A", "3.pdf");
+        wait_until(|| state(&dispatcher, &third.job_id) == JobState::Spooled);
+        assert_eq!(backend.printed_to("A"), vec!["1.pdf", "3.pdf"]);
+        assert_eq!(state(&dispatcher, &second.job_id), JobState::Cancelled);
+    }
+
+    #[test]
+    fn cancelling_retry_frees_the_queue() {
+        let backend = Arc::new(GatedBackend::default());
+        backend.offline.store(1, Ordering::SeqCst);
+        let dispatcher = dispatcher(backend.clone(), None);
+
+        let first = submit(&dispatcher, "A", "1.pdf");
+        let second = submit(&dispatcher, "A", "2.pdf");
+        wait_until(|| state(&dispatcher, &first.job_id) == JobState::Retrying);
+
+        // 第一次重试要等 5 秒，取消后排在后面的作业不必等到那时
+        let cancelled_at = Instant::now();
+        dispatcher.cancel(&first.job_id).unwrap();
+        wait_until(|| state(&dispatcher, &second.job_id) == JobState::Spooled);
+        assert!(cancelled_at.elapsed() < Duration::from_secs(2));
+        assert_eq!(backend.printed_to("A"), vec!["2.pdf"]);
+        assert_eq!(state(&dispatcher, &first.job_id), JobState::Cancelled);
+    }
+
+    // 直接在日志中制造一个死信作业，document 写在临时目录中
+    fn dead_letter(dispatcher: &Dispatcher, printer: &str) -> (String, PathBuf) {
+        let dir = unique_temp_path("dispatcher_test", "d");
+        fs::create_dir_all(&dir).unwrap();
+        let document = dir.join("dead.pdf");
+        fs::write(&document, b"%PDF").unwrap();
+
+        let journal = &dispatcher.journal;
+        let job = journal
+            .submit(queue(printer), document.display().to_string(), PrintSettings::default(), true)
+            .unwrap();
+        journal.start_attempt(&job.id).unwrap();
+        journal.fail(&job.id, JobState::DeadLetter, "打印机脱机").unwrap();
+        (job.id, document)
+    }
+
+    #[test]
+    fn dead_letter_can_be_retried() {
+        let backend = Arc::new(GatedBackend::default());
+        let dispatcher = dispatcher(backend.clone(), None);
+        let (id, document) = dead_letter(&dispatcher, "A");
+        assert_eq!(dispatcher.dead_letters().len(), 1);
+        assert!(dispatcher.cancel(&id).is_err());
+
+        let handle = dispatcher.retry_dead_letter(&id).unwrap();
+        assert_eq!(handle.job_id, id);
+        wait_until(|| state(&dispatcher, &id) == JobState::Spooled);
+        assert_eq!(backend.printed_to("A"), vec![document.display().to_string()]);
+
+        // 提交次数从头计算，之前的错误不再保留
+        let job = dispatcher.journal.get(&id).unwrap();
+        assert_eq!(job.attempts, 1);
+        assert_eq!(job.error, None);
+        assert!(dispatcher.dead_letters().is_empty());
+        assert!(dispatcher.retry_dead_letter(&id).is_err());
+    }
+
+    #[test]
+    fn dead_letter_without_document_is_not_retried() {
+        let dispatcher = dispatcher(Arc::new(GatedBackend::default()), None);
+        let (id, document) = dead_letter(&dispatcher, "A");
+        fs::remove_file(&document).unwrap();
+
+        assert!(dispatcher.retry_dead_letter(&id).unwrap_err().contains("文件不存在"));
+        assert_eq!(state(&dispatcher, &id), JobState::DeadLetter);
+    }
+
+    #[test]
+    fn discarded_dead_letter_removes_document() {
+        let dispatcher = dispatcher(Arc::new(GatedBackend::default()), None);
+        let (id, document) = dead_letter(&dispatcher, "A");
+
+        dispatcher.discard_dead_letter(&id).unwrap();
+        assert_eq!(state(&dispatcher, &id), JobState::Cancelled);
+        assert!(!document.exists());
+        assert!(dispatcher.dead_letters().is_empty());
+        assert!(dispatcher.discard_dead_letter(&id).is_err());
+    }
+
+    #[test]
+    fn interrupted_job_can_be_retried_or_discarded() {
+        let backend = Arc::new(GatedBackend::default());
+        let dispatcher = dispatcher(backend.clone(), None);
+        let (retried, document) = dead_letter(&dispatcher, "A");
+        let (discarded, _) = dead_letter(&dispatcher, "A");
+        // 应用在提交时退出，重启后恢复为 Interrupted
+        for id in [&retried, &discarded] {
+            dispatcher.journal.requeue(id).unwrap();
+            dispatcher.journal.start_attempt(id).unwrap();
+            dispatcher.journal.fail(id, JobState::Interrupted, "应用在提交打印时退出").unwrap();
+        }
+        assert_eq!(dispatcher.dead_letters().len(), 2);
+
+        dispatcher.discard_dead_letter(&discarded).unwrap();
+        assert_eq!(state(&dispatcher, &discarded), JobState::Cancelled);
+
+        dispatcher.retry_dead_letter(&retried).unwrap();
+        wait_until(|| state(&dispatcher, &retried) == JobState::Spooled);
+        assert_eq!(backend.printed_to("A"), vec![document.display().to_string()]);
+        assert!(dispatcher.dead_letters().is_empty());
+    }
+}
@@ -0,0 +1,485 @@
+use serde::{Serialize, Deserialize};
+use crate::barcode::{self, Code128Code, Code128Set, Symbology};
+use crate::bitmap::MonoBitmap;
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const FS: u8 = 0x1C;
+const LF: u8 = 0x0A;
+
+// 80mm 纸宽对应的可打印点数 (203dpi)
+pub const PAPER_WIDTH_80MM: u32 = 576;
+// 一次 GS v 0 最多发送的行数，避免小缓冲区的打印机丢数据
+const RASTER_BAND_HEIGHT: u32 = 256;
+// 以位图打印的二维码制每个模块的点数
+const MATRIX_MODULE: u32 = 4;
+
+// 文本编码，GB18030 与 Big5 需要打开打印机的汉字模式 (FS &)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodePage {
+    Pc437,
+    Windows1252,
+    Cp866,
+    Gb18030,
+    Big5,
+}
+
+// 枚举上的 #[default] 需要 Rust 1.62，这里手写
+#[allow(clippy::derivable_impls)]
+impl Default for CodePage {
+    fn default() -> Self {
+        CodePage::Gb18030
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// 枚举上的 #[default] 需要 Rust 1.62，这里手写
+#[allow(clippy::derivable_impls)]
+impl Default for Align {
+    fn default() -> Self {
+        Align::Left
+    }
+}
+
+// ESC/POS 指令构造器
+pub struct EscPos {
+    buffer: Vec<u8>,
+    code_page: CodePage,
+}
+
+impl EscPos {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            code_page: CodePage::default(),
+        }
+    }
+
+    // ESC @ 初始化打印机
+    pub fn init(&mut self) -> &mut Self {
+        self.buffer.extend_from_slice(&[ESC, b'@']);
+        self
+    }
+
+    // 选择字符编码
+    pub fn code_page(&mut self, code_page: CodePage) -> &mut Self {
+        match code_page {
+            CodePage::Gb18030 | CodePage::Big5 => {
+                // FS & 进入汉字模式
+                self.buffer.extend_from_slice(&[FS, b'&']);
+            }
+            single_byte => {
+                // FS . 退出汉字模式，ESC t n 选择单字节代码页
+                let table = match single_byte {
+                    CodePage::Windows1252 => 16,
+                    CodePage::Cp866 => 17,
+                    _ => 0,
+                };
+                self.buffer.extend_from_slice(&[FS, b'.', ESC, b't', table]);
+            }
+        }
+        self.code_page = code_page;
+        self
+    }
+
+    // ESC a n 对齐方式
+    pub fn align(&mut self, align: Align) -> &mut Self {
+        let n = match align {
+            Align::Left => 0,
+            Align::Center => 1,
+            Align::Right => 2,
+        };
+        self.buffer.extend_from_slice(&[ESC, b'a', n]);
+        self
+    }
+
+    // ESC E n 加粗
+    pub fn bold(&mut self, bold: bool) -> &mut Self {
+        self.buffer.extend_from_slice(&[ESC, b'E', bold as u8]);
+        self
+    }
+
+    // ESC - n 下划线
+    pub fn underline(&mut self, underline: bool) -> &mut Self {
+        self.buffer.extend_from_slice(&[ESC, b'-', underline as u8]);
+        self
+    }
+
+    // GS ! n 字符放大倍数，宽高各 1-8 倍
+    pub fn size(&mut self, width: u8, height: u8) -> &mut Self {
+        let width = width.clamp(1, 8) - 1;
+        let height = height.clamp(1, 8) - 1;
+        self.buffer.extend_from_slice(&[GS, b'!', (width << 4) | height]);
+        self
+    }
+
+    // 按当前编码写入文本
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        let encoded = encode_text(text, self.code_page);
+        self.buffer.extend_from_slice(&encoded);
+        self
+    }
+
+    pub fn line(&mut self, text: &str) -> &mut Self {
+        self.text(text);
+        self.buffer.push(LF);
+        self
+    }
+
+    // ESC d n 走纸 n 行
+    pub fn feed(&mut self, lines: u8) -> &mut Self {
+        self.buffer.extend_from_slice(&[ESC, b'd', lines]);
+        self
+    }
+
+    // GS k 73 打印 Code128，字符集切换与 FNC1 由 barcode 模块编排，用 {A {B {C {1 表示
+    // 数据长度只有一个字节，超过 255 字节时截断会得到错误的条码，直接报错
+    pub fn code128(&mut self, codes: &[Code128Code], height: u8) -> Result<&mut Self, String> {
+        let mut payload = Vec::new();
+        for code in codes {
+            match code {
+                Code128Code::Start(set) | Code128Code::Switch(set) => {
+                    payload.push(b'{');
+                    payload.push(match set {
+                        Code128Set::A => b'A',
+                        Code128Set::B => b'B',
+                        Code128Set::C => b'C',
+                    });
+                }
+                Code128Code::Fnc1 => payload.extend_from_slice(b"{1"),
+                Code128Code::Char(b'{') => payload.extend_from_slice(b"{{"),
+                Code128Code::Char(c) | Code128Code::Pair(c) => payload.push(*c),
+            }
+        }
+        if payload.len() > 255 {
+            return Err(format!("条码数据过长: {} 字节，最多 255 字节", payload.len()));
+        }
+
+        // GS h 高度、GS w 模块宽度、GS H 条码下方显示文字
+        self.buffer.extend_from_slice(&[GS, b'h', height.max(1), GS, b'w', 2, GS, b'H', 2]);
+        self.buffer.extend_from_slice(&[GS, b'k', 73, payload.len() as u8]);
+        self.buffer.extend_from_slice(&payload);
+        Ok(self)
+    }
+
+    // GS ( k 打印二维码，module_size 为 1-16 点，纠错等级 M
+    pub fn qr(&mut self, data: &str, module_size: u8) -> &mut Self {
+        let bytes = data.as_bytes();
+        let store_len = bytes.len() + 3;
+
+        // 模型 2
+        self.buffer.extend_from_slice(&[GS, b'(', b'k', 4, 0, 49, 65, 50, 0]);
+        // 模块大小
+        self.buffer.extend_from_slice(&[GS, b'(', b'k', 3, 0, 49, 67, module_size.clamp(1, 16)]);
+        // 纠错等级 M
+        self.buffer.extend_from_slice(&[GS, b'(', b'k', 3, 0, 49, 69, 49]);
+        // 存储数据
+        self.buffer.extend_from_slice(&[GS, b'(', b'k', (store_len % 256) as u8, (store_len / 256) as u8, 49, 80, 48]);
+        self.buffer.extend_from_slice(bytes);
+        // 打印
+        self.buffer.extend_from_slice(&[GS, b'(', b'k', 3, 0, 49, 81, 48]);
+        self
+    }
+
+    // GS v 0 打印光栅位图，按行分段发送
+    pub fn raster(&mut self, bitmap: &MonoBitmap) -> &mut Self {
+        let bytes_per_row = bitmap.bytes_per_row();
+        let mut y = 0;
+        while y < bitmap.height {
+            let band = RASTER_BAND_HEIGHT.min(bitmap.height - y);
+            self.buffer.extend_from_slice(&[
+                GS,
+                b'v',
+                b'0',
+                0,
+                (bytes_per_row % 256) as u8,
+                (bytes_per_row / 256) as u8,
+                (band % 256) as u8,
+                (band / 256) as u8,
+            ]);
+            for row in y..y + band {
+                self.buffer.extend_from_slice(bitmap.row(row));
+            }
+            y += band;
+        }
+        self
+    }
+
+    // 走纸后切纸，partial 为 true 时半切
+    pub fn cut(&mut self, partial: bool) -> &mut Self {
+        self.feed(3);
+        self.buffer.extend_from_slice(&[GS, b'V', if partial { 1 } else { 0 }]);
+        self
+    }
+
+    // ESC p 弹出钱箱，pin 为 2 或 5
+    pub fn kick_drawer(&mut self, pin: u8) -> &mut Self {
+        let m = if pin == 5 { 1 } else { 0 };
+        self.buffer.extend_from_slice(&[ESC, b'p', m, 25, 250]);
+        self
+    }
+
+    pub fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buffer.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        self.buffer.clone()
+    }
+}
+
+impl Default for EscPos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 按代码页编码文本，无法编码的字符替换为 '?'
+fn encode_text(text: &str, code_page: CodePage) -> Vec<u8> {
+    let encoding = match code_page {
+        CodePage::Gb18030 => encoding_rs::GB18030,
+        CodePage::Big5 => encoding_rs::BIG5,
+        CodePage::Windows1252 => encoding_rs::WINDOWS_1252,
+        CodePage::Cp866 => encoding_rs::IBM866,
+        CodePage::Pc437 => {
+            return text.chars().map(|c| if c.is_ascii() { c as u8 } else { b'?' }).collect();
+        }
+    };
+
+    let mut out = Vec::new();
+    let mut buffer = [0u8; 4];
+    for c in text.chars() {
+        let (encoded, _, unmappable) = encoding.encode(c.encode_utf8(&mut buffer));
+        if unmappable {
+            out.push(b'?');
+        } else {
+            out.extend_from_slice(&encoded);
+        }
+    }
+    out
+}
+
+fn default_one() -> u8 {
+    1
+}
+
+// 前端下发的小票内容，按顺序转换为 ESC/POS 指令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReceiptItem {
+    Text {
+        content: String,
+        #[serde(default)]
+        align: Align,
+        #[serde(default)]
+        bold: bool,
+        #[serde(default)]
+        underline: bool,
+        #[serde(default = "default_one")]
+        width: u8,
+        #[serde(default = "default_one")]
+        height: u8,
+    },
+    Feed {
+        #[serde(default = "default_one")]
+        lines: u8,
+    },
+    Barcode {
+        data: String,
+        #[serde(default)]
+        symbology: Symbology,
+        #[serde(default)]
+        align: Align,
+        height: Option<u8>,
+    },
+    Qrcode {
+        data: String,
+        #[serde(default)]
+        align: Align,
+        size: Option<u8>,
+    },
+    Image {
+        data: String,
+        #[serde(default)]
+        align: Align,
+    },
+    Cut {
+        #[serde(default)]
+        partial: bool,
+    },
+    Drawer {
+        pin: Option<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    #[serde(default)]
+    pub code_page: CodePage,
+    // 可打印宽度 (点)，图片超出时等比缩小
+    pub paper_width: Option<u32>,
+    pub items: Vec<ReceiptItem>,
+}
+
+// 生成整张小票的 ESC/POS 数据
+pub fn render_receipt(receipt: &Receipt) -> Result<Vec<u8>, String> {
+    let mut pos = EscPos::new();
+    pos.init().code_page(receipt.code_page);
+    let paper_width = receipt.paper_width.unwrap_or(PAPER_WIDTH_80MM);
+
+    for item in &receipt.items {
+        match item {
+            ReceiptItem::Text { content, align, bold, underline, width, height } => {
+                pos.align(*align).bold(*bold).underline(*underline).size(*width, *height);
+                for line in content.lines() {
+                    pos.line(line);
+                }
+                // 恢复默认样式，避免影响后续内容
+                pos.bold(false).underline(false).size(1, 1);
+            }
+            ReceiptItem::Feed { lines } => {
+                pos.feed(*lines);
+            }
+            ReceiptItem::Barcode { data, symbology, align, height } => {
+                pos.align(*align);
+                match symbology {
+                    Symbology::Code128 => pos.code128(&barcode::code128_codes(data)?, height.unwrap_or(80))?,
+                    Symbology::Gs1128 => pos.code128(&barcode::gs1_128_codes(data)?, height.unwrap_or(80))?,
+                    Symbology::Qrcode => pos.qr(data, 6),
+                    // DataMatrix、PDF417 并非所有机型都支持，按位图打印
+                    _ => pos.raster(&barcode::encode(*symbology, data)?.to_bitmap(MATRIX_MODULE, MATRIX_MODULE)),
+                };
+                pos.raw(&[LF]);
+            }
+            ReceiptItem::Qrcode { data, align, size } => {
+                pos.align(*align).qr(data, size.unwrap_or(6)).raw(&[LF]);
+            }
+            ReceiptItem::Image { data, align } => {
+                let bitmap = MonoBitmap::from_base64(data, Some(paper_width))?;
+                pos.align(*align).raster(&bitmap);
+            }
+            ReceiptItem::Cut { partial } => {
+                pos.cut(*partial);
+            }
+            ReceiptItem::Drawer { pin } => {
+                pos.kick_drawer(pin.unwrap_or(2));
+            }
+        }
+    }
+    pos.align(Align::Left);
+
+    Ok(pos.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipt_bytes() {
+        let receipt: Receipt = serde_json::from_value(serde_json::json!({
+            "items": [
+                { "type": "text", "content": "中A", "align": "center", "bold": true },
+                { "type": "feed", "lines": 2 },
+                { "type": "barcode", "data": "12" },
+                { "type": "cut", "partial": true },
+                { "type": "drawer", "pin": 5 }
+            ]
+        }))
+        .unwrap();
+
+        let expected: Vec<u8> = [
+            // 初始化，GB18030 汉字模式
+            &[0x1B, 0x40, 0x1C, 0x26][..],
+            // 居中、加粗、无下划线、原始大小，"中A" 换行
+            &[0x1B, 0x61, 0x01, 0x1B, 0x45, 0x01, 0x1B, 0x2D, 0x00, 0x1D, 0x21, 0x00, 0xD6, 0xD0, 0x41, 0x0A],
+            // 恢复默认样式
+            &[0x1B, 0x45, 0x00, 0x1B, 0x2D, 0x00, 0x1D, 0x21, 0x00],
+            &[0x1B, 0x64, 0x02],
+            // 左对齐，条高 80，"12" 用 C 字符集编为一个符号
+            &[0x1B, 0x61, 0x00, 0x1D, 0x68, 80, 0x1D, 0x77, 0x02, 0x1D, 0x48, 0x02, 0x1D, 0x6B, 73, 3, b'{', b'C', 12, 0x0A],
+            // 走纸 3 行后半切
+            &[0x1B, 0x64, 0x03, 0x1D, 0x56, 0x01],
+            // 钱箱引脚 5
+            &[0x1B, 0x70, 0x01, 25, 250],
+            &[0x1B, 0x61, 0x00],
+        ]
+        .concat();
+        assert_eq!(render_receipt(&receipt).unwrap(), expected);
+    }
+
+    #[test]
+    fn single_byte_code_page() {
+        let mut pos = EscPos::new();
+        pos.code_page(CodePage::Cp866).text("Да€");
+        assert_eq!(pos.build(), vec![0x1C, 0x2E, 0x1B, 0x74, 17, 0x84, 0xA0, b'?']);
+    }
+
+    #[test]
+    fn code128_payload() {
+        let mut pos = EscPos::new();
+        pos.code128(&barcode::code128_codes("{a").unwrap(), 0).unwrap();
+        assert_eq!(
+            pos.build(),
+            vec![0x1D, 0x68, 1, 0x1D, 0x77, 2, 0x1D, 0x48, 2, 0x1D, 0x6B, 73, 5, b'{', b'B', b'{', b'{', b'a']
+        );
+
+        // 超过 255 字节时报错，不输出任何内容
+        let mut pos = EscPos::new();
+        let codes = barcode::code128_codes(&"a".repeat(300)).unwrap();
+        assert!(pos.code128(&codes, 80).is_err());
+        assert!(pos.build().is_empty());
+
+        let receipt = Receipt {
+            code_page: CodePage::Gb18030,
+            paper_width: None,
+            items: vec![ReceiptItem::Barcode {
+                data: "a".repeat(300),
+                symbology: Symbology::Code128,
+                align: Align::Left,
+                height: None,
+            }],
+        };
+        assert!(render_receipt(&receipt).is_err());
+    }
+
+    #[test]
+    fn qr_bytes() {
+        let mut pos = EscPos::new();
+        pos.qr("hi", 20);
+        assert_eq!(
+            pos.build(),
+            vec![
+                0x1D, 0x28, 0x6B, 4, 0, 49, 65, 50, 0,
+                0x1D, 0x28, 0x6B, 3, 0, 49, 67, 16,
+                0x1D, 0x28, 0x6B, 3, 0, 49, 69, 49,
+                0x1D, 0x28, 0x6B, 5, 0, 49, 80, 48, b'h', b'i',
+                0x1D, 0x28, 0x6B, 3, 0, 49, 81, 48,
+            ]
+        );
+    }
+
+    #[test]
+    fn raster_bands() {
+        let mut bitmap = MonoBitmap::new(9, RASTER_BAND_HEIGHT + 1);
+        bitmap.set(0, 0, true);
+        bitmap.set(8, RASTER_BAND_HEIGHT, true);
+        let mut pos = EscPos::new();
+        pos.raster(&bitmap);
+        let bytes = pos.build();
+
+        let first = 8 + 2 * RASTER_BAND_HEIGHT as usize;
+        assert_eq!(bytes.len(), first + 8 + 2);
+        assert_eq!(&bytes[..10], &[0x1D, 0x76, 0x30, 0, 2, 0, 0, 1, 0x80, 0x00]);
+        assert_eq!(&bytes[first..], &[0x1D, 0x76, 0x30, 0, 2, 0, 1, 0, 0x00, 0x80]);
+    }
+}
@@ -0,0 +1,33 @@
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+use tokio::runtime::Handle;
+use crate::websocket::{self, SharedWebSocket};
+
+// 后台状态变化同时推送给前端 (Tauri 事件) 和 WebSocket 客户端
+// 推送可能发生在打印线程中，WebSocket 发送交给 Tokio 运行时异步完成
+#[derive(Clone)]
+pub struct EventSink {
+    app_handle: AppHandle,
+    ws_conn: SharedWebSocket,
+    runtime: Handle,
+}
+
+impl EventSink {
+    pub fn new(app_handle: AppHandle, ws_conn: SharedWebSocket, runtime: Handle) -> Self {
+        Self {
+            app_handle,
+            ws_conn,
+            runtime,
+        }
+    }
+
+    // WebSocket 消息格式为 {"event": "...", "payload": {...}}
+    pub fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let message = json!({ "event": event, "payload": &payload }).to_string();
+        if let Err(e) = self.app_handle.emit_all(event, payload) {
+            println!("发送事件失败: {} {}", event, e);
+        }
+        self.runtime.spawn(websocket::notify(self.ws_conn.clone(), message));
+    }
+}
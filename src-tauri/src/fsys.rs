@@ -1,8 +1,12 @@
 
 use std::fs::{File, remove_file as rmf};
 
+use std::env;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 use base64::{Engine as _, engine::{general_purpose}};
 
 
@@ -15,7 +19,7 @@ pub fn create_file_from_base64(base64_string: &str, file_path: &str) -> Result<(
 
     // Create a file at the specified path
     let path = Path::new(file_path);
-    let mut file = File::create(&path)?;
+    let mut file = File::create(path)?;
 
     // Write the decoded bytes to the file
     file.write_all(&buffer)?;
@@ -26,4 +30,16 @@ pub fn create_file_from_base64(base64_string: &str, file_path: &str) -> Result<(
 pub fn remove_file (file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
     rmf(file_path)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+static TEMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// 临时目录下不重复的文件路径，同一毫秒内生成的文件用序号区分，进程号区分同时运行的多个实例
+pub fn unique_temp_path(prefix: &str, extension: &str) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let sequence = TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("{}_{}_{}_{}.{}", prefix, process::id(), millis, sequence, extension))
+}
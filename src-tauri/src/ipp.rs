@@ -0,0 +1,916 @@
+use reqwest::Client;
+use std::fs;
+use crate::declare::{JobStatus, PrintJob, Printer, PrinterState};
+use crate::fsys::remove_file;
+use crate::timestamp::Timestamp;
+
+// IPP 操作码 (RFC 8011 / CUPS)
+pub const PRINT_JOB: u16 = 0x0002;
+pub const VALIDATE_JOB: u16 = 0x0004;
+pub const CANCEL_JOB: u16 = 0x0008;
+pub const GET_JOB_ATTRIBUTES: u16 = 0x0009;
+pub const GET_JOBS: u16 = 0x000A;
+pub const GET_PRINTER_ATTRIBUTES: u16 = 0x000B;
+pub const HOLD_JOB: u16 = 0x000C;
+pub const RELEASE_JOB: u16 = 0x000D;
+
+// 属性分组标签
+pub const OPERATION_ATTRIBUTES: u8 = 0x01;
+pub const JOB_ATTRIBUTES: u8 = 0x02;
+pub const END_OF_ATTRIBUTES: u8 = 0x03;
+pub const PRINTER_ATTRIBUTES: u8 = 0x04;
+pub const UNSUPPORTED_ATTRIBUTES: u8 = 0x05;
+
+// 属性值标签
+const TAG_UNSUPPORTED: u8 = 0x10;
+const TAG_UNKNOWN: u8 = 0x12;
+const TAG_NO_VALUE: u8 = 0x13;
+const TAG_INTEGER: u8 = 0x21;
+const TAG_BOOLEAN: u8 = 0x22;
+const TAG_ENUM: u8 = 0x23;
+const TAG_OCTET_STRING: u8 = 0x30;
+const TAG_DATE_TIME: u8 = 0x31;
+const TAG_RESOLUTION: u8 = 0x32;
+const TAG_RANGE: u8 = 0x33;
+const TAG_BEGIN_COLLECTION: u8 = 0x34;
+const TAG_TEXT_WITH_LANGUAGE: u8 = 0x35;
+const TAG_NAME_WITH_LANGUAGE: u8 = 0x36;
+const TAG_END_COLLECTION: u8 = 0x37;
+const TAG_TEXT: u8 = 0x41;
+const TAG_NAME: u8 = 0x42;
+const TAG_KEYWORD: u8 = 0x44;
+const TAG_URI: u8 = 0x45;
+const TAG_URI_SCHEME: u8 = 0x46;
+const TAG_CHARSET: u8 = 0x47;
+const TAG_NATURAL_LANGUAGE: u8 = 0x48;
+const TAG_MIME_MEDIA_TYPE: u8 = 0x49;
+const TAG_MEMBER_NAME: u8 = 0x4A;
+
+// 查询作业时请求的属性
+const JOB_REQUESTED_ATTRIBUTES: [&str; 9] = [
+    "job-id",
+    "job-name",
+    "job-originating-user-name",
+    "job-state",
+    "job-k-octets",
+    "job-impressions",
+    "job-impressions-completed",
+    "time-at-creation",
+    "job-printer-uri",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IppValue {
+    Integer(i32),
+    Boolean(bool),
+    Enum(i32),
+    OctetString(Vec<u8>),
+    DateTime(Vec<u8>),
+    Resolution { x: i32, y: i32, units: u8 },
+    Range { lower: i32, upper: i32 },
+    TextWithLanguage { language: String, text: String },
+    NameWithLanguage { language: String, name: String },
+    Text(String),
+    Name(String),
+    Keyword(String),
+    Uri(String),
+    UriScheme(String),
+    Charset(String),
+    NaturalLanguage(String),
+    MimeMediaType(String),
+    Collection(Vec<IppAttribute>),
+    NoValue,
+    Unknown,
+    Unsupported,
+    Other(u8, Vec<u8>),
+}
+
+impl IppValue {
+    fn tag(&self) -> u8 {
+        match self {
+            IppValue::Integer(_) => TAG_INTEGER,
+            IppValue::Boolean(_) => TAG_BOOLEAN,
+            IppValue::Enum(_) => TAG_ENUM,
+            IppValue::OctetString(_) => TAG_OCTET_STRING,
+            IppValue::DateTime(_) => TAG_DATE_TIME,
+            IppValue::Resolution { .. } => TAG_RESOLUTION,
+            IppValue::Range { .. } => TAG_RANGE,
+            IppValue::TextWithLanguage { .. } => TAG_TEXT_WITH_LANGUAGE,
+            IppValue::NameWithLanguage { .. } => TAG_NAME_WITH_LANGUAGE,
+            IppValue::Text(_) => TAG_TEXT,
+            IppValue::Name(_) => TAG_NAME,
+            IppValue::Keyword(_) => TAG_KEYWORD,
+            IppValue::Uri(_) => TAG_URI,
+            IppValue::UriScheme(_) => TAG_URI_SCHEME,
+            IppValue::Charset(_) => TAG_CHARSET,
+            IppValue::NaturalLanguage(_) => TAG_NATURAL_LANGUAGE,
+            IppValue::MimeMediaType(_) => TAG_MIME_MEDIA_TYPE,
+            IppValue::Collection(_) => TAG_BEGIN_COLLECTION,
+            IppValue::NoValue => TAG_NO_VALUE,
+            IppValue::Unknown => TAG_UNKNOWN,
+            IppValue::Unsupported => TAG_UNSUPPORTED,
+            IppValue::Other(tag, _) => *tag,
+        }
+    }
+
+    // 文本类的值统一取字符串
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            IppValue::Text(s)
+            | IppValue::Name(s)
+            | IppValue::Keyword(s)
+            | IppValue::Uri(s)
+            | IppValue::UriScheme(s)
+            | IppValue::Charset(s)
+            | IppValue::NaturalLanguage(s)
+            | IppValue::MimeMediaType(s) => Some(s),
+            IppValue::TextWithLanguage { text, .. } => Some(text),
+            IppValue::NameWithLanguage { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            IppValue::Integer(v) | IppValue::Enum(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            IppValue::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn encode_value(&self) -> Vec<u8> {
+        match self {
+            IppValue::Integer(v) | IppValue::Enum(v) => v.to_be_bytes().to_vec(),
+            IppValue::Boolean(v) => vec![*v as u8],
+            IppValue::OctetString(v) | IppValue::DateTime(v) | IppValue::Other(_, v) => v.clone(),
+            IppValue::Resolution { x, y, units } => {
+                let mut out = x.to_be_bytes().to_vec();
+                out.extend_from_slice(&y.to_be_bytes());
+                out.push(*units);
+                out
+            }
+            IppValue::Range { lower, upper } => {
+                let mut out = lower.to_be_bytes().to_vec();
+                out.extend_from_slice(&upper.to_be_bytes());
+                out
+            }
+            IppValue::TextWithLanguage { language, text: value }
+            | IppValue::NameWithLanguage { language, name: value } => {
+                let mut out = Vec::new();
+                out.extend_from_slice(&(language.len() as u16).to_be_bytes());
+                out.extend_from_slice(language.as_bytes());
+                out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+                out.extend_from_slice(value.as_bytes());
+                out
+            }
+            IppValue::Collection(_) | IppValue::NoValue | IppValue::Unknown | IppValue::Unsupported => Vec::new(),
+            _ => self.as_str().unwrap_or_default().as_bytes().to_vec(),
+        }
+    }
+
+    fn decode_value(tag: u8, bytes: &[u8]) -> Result<IppValue, String> {
+        let string = || String::from_utf8_lossy(bytes).to_string();
+        let int_at = |offset: usize| -> Result<i32, String> {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| format!("IPP 属性值长度错误 (tag 0x{:02x})", tag))
+        };
+
+        let value = match tag {
+            TAG_INTEGER => IppValue::Integer(int_at(0)?),
+            TAG_ENUM => IppValue::Enum(int_at(0)?),
+            TAG_BOOLEAN => IppValue::Boolean(matches!(bytes.first(), Some(b) if *b != 0)),
+            TAG_OCTET_STRING => IppValue::OctetString(bytes.to_vec()),
+            TAG_DATE_TIME => IppValue::DateTime(bytes.to_vec()),
+            TAG_RESOLUTION => IppValue::Resolution {
+                x: int_at(0)?,
+                y: int_at(4)?,
+                units: bytes.get(8).copied().unwrap_or_default(),
+            },
+            TAG_RANGE => IppValue::Range { lower: int_at(0)?, upper: int_at(4)? },
+            TAG_TEXT_WITH_LANGUAGE | TAG_NAME_WITH_LANGUAGE => {
+                let mut reader = Reader::new(bytes);
+                let language = String::from_utf8_lossy(reader.take_prefixed()?).to_string();
+                let value = String::from_utf8_lossy(reader.take_prefixed()?).to_string();
+                if tag == TAG_TEXT_WITH_LANGUAGE {
+                    IppValue::TextWithLanguage { language, text: value }
+                } else {
+                    IppValue::NameWithLanguage { language, name: value }
+                }
+            }
+            TAG_TEXT => IppValue::Text(string()),
+            TAG_NAME => IppValue::Name(string()),
+            TAG_KEYWORD => IppValue::Keyword(string()),
+            TAG_URI => IppValue::Uri(string()),
+            TAG_URI_SCHEME => IppValue::UriScheme(string()),
+            TAG_CHARSET => IppValue::Charset(string()),
+            TAG_NATURAL_LANGUAGE => IppValue::NaturalLanguage(string()),
+            TAG_MIME_MEDIA_TYPE => IppValue::MimeMediaType(string()),
+            TAG_NO_VALUE => IppValue::NoValue,
+            TAG_UNKNOWN => IppValue::Unknown,
+            TAG_UNSUPPORTED => IppValue::Unsupported,
+            _ => IppValue::Other(tag, bytes.to_vec()),
+        };
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IppAttribute {
+    pub name: String,
+    pub values: Vec<IppValue>,
+}
+
+impl IppAttribute {
+    pub fn new(name: &str, value: IppValue) -> Self {
+        Self { name: name.to_string(), values: vec![value] }
+    }
+
+    pub fn first(&self) -> Option<&IppValue> {
+        self.values.first()
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        for (index, value) in self.values.iter().enumerate() {
+            // 多值属性的后续值名称长度为 0
+            let name = if index == 0 { self.name.as_str() } else { "" };
+            out.push(value.tag());
+            out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            out.extend_from_slice(name.as_bytes());
+
+            if let IppValue::Collection(members) = value {
+                out.extend_from_slice(&0u16.to_be_bytes());
+                for member in members {
+                    out.push(TAG_MEMBER_NAME);
+                    out.extend_from_slice(&0u16.to_be_bytes());
+                    out.extend_from_slice(&(member.name.len() as u16).to_be_bytes());
+                    out.extend_from_slice(member.name.as_bytes());
+                    IppAttribute { name: String::new(), values: member.values.clone() }.encode(out);
+                }
+                out.push(TAG_END_COLLECTION);
+                out.extend_from_slice(&0u16.to_be_bytes());
+                out.extend_from_slice(&0u16.to_be_bytes());
+            } else {
+                let bytes = value.encode_value();
+                out.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+                out.extend_from_slice(&bytes);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IppGroup {
+    pub tag: u8,
+    pub attributes: Vec<IppAttribute>,
+}
+
+impl IppGroup {
+    pub fn get(&self, name: &str) -> Option<&IppValue> {
+        self.attributes.iter().find(|attr| attr.name == name).and_then(IppAttribute::first)
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(IppValue::as_str)
+    }
+
+    pub fn get_i32(&self, name: &str) -> Option<i32> {
+        self.get(name).and_then(IppValue::as_i32)
+    }
+
+    // 多值属性的全部字符串值，例如 printer-state-reasons
+    pub fn get_strs(&self, name: &str) -> Vec<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.values.iter().filter_map(IppValue::as_str).collect())
+            .unwrap_or_default()
+    }
+}
+
+// IPP 请求与响应的结构一致，code 在请求中是操作码，在响应中是状态码
+#[derive(Debug, Clone, PartialEq)]
+pub struct IppMessage {
+    pub version: (u8, u8),
+    pub code: u16,
+    pub request_id: u32,
+    pub groups: Vec<IppGroup>,
+    pub data: Vec<u8>,
+}
+
+impl IppMessage {
+    // 创建请求，并写入必需的 charset 与 natural-language 操作属性
+    pub fn request(operation: u16, request_id: u32) -> Self {
+        Self {
+            version: (2, 0),
+            code: operation,
+            request_id,
+            groups: vec![IppGroup {
+                tag: OPERATION_ATTRIBUTES,
+                attributes: vec![
+                    IppAttribute::new("attributes-charset", IppValue::Charset("utf-8".to_string())),
+                    IppAttribute::new("attributes-natural-language", IppValue::NaturalLanguage("en".to_string())),
+                ],
+            }],
+            data: Vec::new(),
+        }
+    }
+
+    // 向指定分组追加属性，分组不存在时新建
+    pub fn push(&mut self, group_tag: u8, attribute: IppAttribute) {
+        match self.groups.iter_mut().find(|group| group.tag == group_tag) {
+            Some(group) => group.attributes.push(attribute),
+            None => self.groups.push(IppGroup { tag: group_tag, attributes: vec![attribute] }),
+        }
+    }
+
+    pub fn group(&self, tag: u8) -> Option<&IppGroup> {
+        self.groups.iter().find(|group| group.tag == tag)
+    }
+
+    pub fn groups(&self, tag: u8) -> impl Iterator<Item = &IppGroup> {
+        self.groups.iter().filter(move |group| group.tag == tag)
+    }
+
+    // 0x0000 - 0x00FF 为成功状态
+    pub fn is_success(&self) -> bool {
+        self.code <= 0x00FF
+    }
+
+    pub fn status_message(&self) -> Option<&str> {
+        self.group(OPERATION_ATTRIBUTES).and_then(|group| group.get_str("status-message"))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![self.version.0, self.version.1];
+        out.extend_from_slice(&self.code.to_be_bytes());
+        out.extend_from_slice(&self.request_id.to_be_bytes());
+        for group in &self.groups {
+            out.push(group.tag);
+            for attribute in &group.attributes {
+                attribute.encode(&mut out);
+            }
+        }
+        out.push(END_OF_ATTRIBUTES);
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+        let version = (reader.u8()?, reader.u8()?);
+        let code = reader.u16()?;
+        let request_id = reader.u32()?;
+
+        let mut groups: Vec<IppGroup> = Vec::new();
+        loop {
+            let tag = reader.u8()?;
+            if tag == END_OF_ATTRIBUTES {
+                break;
+            }
+            if tag <= 0x0F {
+                groups.push(IppGroup { tag, attributes: Vec::new() });
+                continue;
+            }
+
+            let group = groups.last_mut().ok_or("IPP 属性缺少分组标签")?;
+            let name = String::from_utf8_lossy(reader.take_prefixed()?).to_string();
+            let value = reader.value(tag)?;
+            match group.attributes.last_mut() {
+                // 名称为空表示上一个属性的附加值
+                Some(last) if name.is_empty() => last.values.push(value),
+                _ => group.attributes.push(IppAttribute { name, values: vec![value] }),
+            }
+        }
+
+        Ok(Self {
+            version,
+            code,
+            request_id,
+            groups,
+            data: reader.rest().to_vec(),
+        })
+    }
+}
+
+// 按字节读取 IPP 报文
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.offset + len;
+        let slice = self.bytes.get(self.offset..end).ok_or("IPP 报文不完整")?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    // 两字节长度前缀的数据
+    fn take_prefixed(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    fn value(&mut self, tag: u8) -> Result<IppValue, String> {
+        let bytes = self.take_prefixed()?;
+        if tag == TAG_BEGIN_COLLECTION {
+            return self.collection();
+        }
+        IppValue::decode_value(tag, bytes)
+    }
+
+    // 解析 begCollection 到 endCollection 之间的成员属性
+    fn collection(&mut self) -> Result<IppValue, String> {
+        let mut members: Vec<IppAttribute> = Vec::new();
+        loop {
+            let tag = self.u8()?;
+            self.take_prefixed()?;
+            if tag == TAG_END_COLLECTION {
+                self.take_prefixed()?;
+                return Ok(IppValue::Collection(members));
+            }
+            if tag == TAG_MEMBER_NAME {
+                let name = String::from_utf8_lossy(self.take_prefixed()?).to_string();
+                members.push(IppAttribute { name, values: Vec::new() });
+                continue;
+            }
+            let value = self.value(tag)?;
+            let member = members.last_mut().ok_or("IPP 集合缺少成员名称")?;
+            member.values.push(value);
+        }
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.offset..]
+    }
+}
+
+// ipp:// 与 ipps:// 转换为实际请求的 http:// 与 https:// 地址，默认端口 631
+pub fn http_url(printer_uri: &str) -> Result<String, String> {
+    let (scheme, rest) = printer_uri
+        .split_once("://")
+        .ok_or_else(|| format!("无效的打印机地址: {}", printer_uri))?;
+    let scheme = match scheme.to_ascii_lowercase().as_str() {
+        "ipp" | "http" => "http",
+        "ipps" | "https" => "https",
+        other => return Err(format!("不支持的协议: {}", other)),
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let has_port = matches!(
+        authority.rsplit_once(':'),
+        Some((_, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit())
+    );
+
+    if has_port {
+        Ok(format!("{}://{}{}", scheme, authority, path))
+    } else {
+        Ok(format!("{}://{}:631{}", scheme, authority, path))
+    }
+}
+
+// 当前系统用户名，作为 requesting-user-name
+fn requesting_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "electronic-print".to_string())
+}
+
+// IPP 客户端，一个实例对应一台打印机
+pub struct IppClient {
+    printer_uri: String,
+    url: String,
+    http: Client,
+}
+
+impl IppClient {
+    pub fn new(printer_uri: &str) -> Result<Self, String> {
+        Ok(Self {
+            printer_uri: printer_uri.to_string(),
+            url: http_url(printer_uri)?,
+            http: Client::new(),
+        })
+    }
+
+    // 创建带 printer-uri 与 requesting-user-name 的请求
+    fn request(&self, operation: u16) -> IppMessage {
+        let mut request = IppMessage::request(operation, rand_request_id());
+        request.push(OPERATION_ATTRIBUTES, IppAttribute::new("printer-uri", IppValue::Uri(self.printer_uri.clone())));
+        request.push(OPERATION_ATTRIBUTES, IppAttribute::new("requesting-user-name", IppValue::Name(requesting_user())));
+        request
+    }
+
+    fn job_request(&self, operation: u16, job_id: i32) -> IppMessage {
+        let mut request = self.request(operation);
+        request.push(OPERATION_ATTRIBUTES, IppAttribute::new("job-id", IppValue::Integer(job_id)));
+        request
+    }
+
+    // 发送请求，非成功状态转换为错误
+    pub async fn send(&self, request: IppMessage) -> Result<IppMessage, String> {
+        let response = self
+            .http
+            .post(&self.url)
+            .header("Content-Type", "application/ipp")
+            .body(request.encode())
+            .send()
+            .await
+            .map_err(|e| format!("IPP 请求失败: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("IPP 请求失败: HTTP {}", response.status()));
+        }
+
+        let body = response.bytes().await.map_err(|e| format!("读取 IPP 响应失败: {}", e))?;
+        let message = IppMessage::decode(&body)?;
+        if message.is_success() {
+            Ok(message)
+        } else {
+            Err(format!(
+                "IPP 错误 0x{:04x}: {}",
+                message.code,
+                message.status_message().unwrap_or_default()
+            ))
+        }
+    }
+
+    pub async fn get_printer_attributes(&self) -> Result<IppGroup, String> {
+        let response = self.send(self.request(GET_PRINTER_ATTRIBUTES)).await?;
+        response
+            .group(PRINTER_ATTRIBUTES)
+            .cloned()
+            .ok_or_else(|| "IPP 响应缺少打印机属性".to_string())
+    }
+
+    fn document_request(&self, operation: u16, job_name: &str, document_format: &str) -> IppMessage {
+        let mut request = self.request(operation);
+        request.push(OPERATION_ATTRIBUTES, IppAttribute::new("job-name", IppValue::Name(job_name.to_string())));
+        request.push(OPERATION_ATTRIBUTES, IppAttribute::new("document-format", IppValue::MimeMediaType(document_format.to_string())));
+        request
+    }
+
+    // 校验打印机是否接受该格式的作业
+    pub async fn validate_job(&self, job_name: &str, document_format: &str) -> Result<(), String> {
+        self.send(self.document_request(VALIDATE_JOB, job_name, document_format)).await.map(|_| ())
+    }
+
+    // 提交作业，返回 job-id
+    pub async fn print_job(&self, job_name: &str, document_format: &str, data: Vec<u8>) -> Result<i32, String> {
+        let mut request = self.document_request(PRINT_JOB, job_name, document_format);
+        request.data = data;
+        let response = self.send(request).await?;
+        response
+            .group(JOB_ATTRIBUTES)
+            .and_then(|group| group.get_i32("job-id"))
+            .ok_or_else(|| "IPP 响应缺少 job-id".to_string())
+    }
+
+    pub async fn get_jobs(&self) -> Result<Vec<IppGroup>, String> {
+        let mut request = self.request(GET_JOBS);
+        request.push(OPERATION_ATTRIBUTES, IppAttribute::new("which-jobs", IppValue::Keyword("not-completed".to_string())));
+        request.push(OPERATION_ATTRIBUTES, IppAttribute {
+            name: "requested-attributes".to_string(),
+            values: JOB_REQUESTED_ATTRIBUTES.iter().map(|name| IppValue::Keyword(name.to_string())).collect(),
+        });
+        let response = self.send(request).await?;
+        Ok(response.groups(JOB_ATTRIBUTES).cloned().collect())
+    }
+
+    pub async fn get_job_attributes(&self, job_id: i32) -> Result<IppGroup, String> {
+        let response = self.send(self.job_request(GET_JOB_ATTRIBUTES, job_id)).await?;
+        response
+            .group(JOB_ATTRIBUTES)
+            .cloned()
+            .ok_or_else(|| "IPP 响应缺少作业属性".to_string())
+    }
+
+    pub async fn cancel_job(&self, job_id: i32) -> Result<(), String> {
+        self.send(self.job_request(CANCEL_JOB, job_id)).await.map(|_| ())
+    }
+
+    pub async fn hold_job(&self, job_id: i32) -> Result<(), String> {
+        self.send(self.job_request(HOLD_JOB, job_id)).await.map(|_| ())
+    }
+
+    pub async fn release_job(&self, job_id: i32) -> Result<(), String> {
+        self.send(self.job_request(RELEASE_JOB, job_id)).await.map(|_| ())
+    }
+}
+
+// request-id 只需在同一连接内区分请求，取当前时间即可
+fn rand_request_id() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() | 1)
+        .unwrap_or(1)
+}
+
+// 根据打印机属性构造打印机信息
+pub fn printer_from_attributes(printer_uri: &str, attributes: &IppGroup) -> Printer {
+    let name = attributes.get_str("printer-name").unwrap_or(printer_uri);
+    // printer-state: 3 空闲 / 4 打印中 / 5 已停止，转换为 Win32_Printer 的 PrinterStatus
+    let printer_status = match attributes.get_i32("printer-state") {
+        Some(3) => 3,
+        Some(4) => 4,
+        Some(5) => 6,
+        _ => 2,
+    };
+    let shared = attributes.get("printer-is-shared").and_then(IppValue::as_bool).unwrap_or_default();
+
+    Printer {
+        driver_name: attributes.get_str("printer-make-and-model").unwrap_or_default().to_string(),
+        job_count: attributes.get_i32("queued-job-count").unwrap_or_default().max(0) as u32,
+        port_name: printer_uri.to_string(),
+        computer_name: attributes.get_str("printer-location").unwrap_or_default().to_string(),
+        printer_status,
+        shared,
+        printer_type: 1,
+        state: PrinterState::from_ipp(attributes.get_i32("printer-state").unwrap_or_default(), attributes.get_strs("printer-state-reasons")),
+        ..Printer::new(name)
+    }
+}
+
+// 根据作业属性构造作业信息
+pub fn job_from_attributes(printer_name: &str, attributes: &IppGroup) -> PrintJob {
+    let job_id = attributes.get_i32("job-id").unwrap_or_default().to_string();
+    // job-state: 3 pending / 4 held / 5 processing / 6 stopped / 7 canceled / 8 aborted / 9 completed
+    let status = match attributes.get_i32("job-state") {
+        Some(4) => 1,
+        Some(5) => 16,
+        Some(6) | Some(8) => 2,
+        Some(7) => 256,
+        Some(9) => 4096,
+        _ => 0,
+    };
+    let created = attributes.get_i32("time-at-creation").and_then(|secs| Timestamp::from_millis(secs as i64 * 1000));
+
+    PrintJob {
+        document_name: attributes.get_str("job-name").unwrap_or_default().to_string(),
+        username: attributes.get_str("job-originating-user-name").unwrap_or_default().to_string(),
+        size: attributes.get_i32("job-k-octets").unwrap_or_default().max(0) as u64 * 1024,
+        total_pages: attributes.get_i32("job-impressions").unwrap_or_default().max(0) as u32,
+        pages_printed: attributes.get_i32("job-impressions-completed").unwrap_or_default().max(0) as u32,
+        job_status: Some(JobStatus::from_code(status)),
+        ..PrintJob::new(printer_name, &job_id).with_submitted(created)
+    }
+}
+
+fn parse_job_id(job_id: &str) -> Result<i32, String> {
+    job_id.trim().parse().map_err(|_| format!("无效的作业 ID: {}", job_id))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_get_printer(printer_uri: String) -> Result<Printer, String> {
+    let client = IppClient::new(&printer_uri)?;
+    let attributes = client.get_printer_attributes().await?;
+    Ok(printer_from_attributes(&printer_uri, &attributes))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_validate_job(printer_uri: String, document_format: String) -> Result<(), String> {
+    IppClient::new(&printer_uri)?.validate_job("validate", &document_format).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_print(
+    printer_uri: String,
+    path: String,
+    document_format: Option<String>,
+    remove_after_print: bool,
+) -> Result<String, String> {
+    let client = IppClient::new(&printer_uri)?;
+    let data = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let job_name = std::path::Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let document_format = document_format.unwrap_or_else(|| "application/pdf".to_string());
+
+    let job_id = client.print_job(&job_name, &document_format, data).await?;
+    println!("IPP 打印成功，作业 ID: {}", job_id);
+
+    if remove_after_print {
+        remove_file(&path).map_err(|e| format!("删除文件失败: {}", e))?;
+    }
+    Ok(job_id.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_get_jobs(printer_uri: String) -> Result<Vec<PrintJob>, String> {
+    let client = IppClient::new(&printer_uri)?;
+    let jobs = client.get_jobs().await?;
+    Ok(jobs.iter().map(|job| job_from_attributes(&printer_uri, job)).collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_get_job(printer_uri: String, job_id: String) -> Result<PrintJob, String> {
+    let client = IppClient::new(&printer_uri)?;
+    let job = client.get_job_attributes(parse_job_id(&job_id)?).await?;
+    Ok(job_from_attributes(&printer_uri, &job))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_cancel_job(printer_uri: String, job_id: String) -> Result<(), String> {
+    IppClient::new(&printer_uri)?.cancel_job(parse_job_id(&job_id)?).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_hold_job(printer_uri: String, job_id: String) -> Result<(), String> {
+    IppClient::new(&printer_uri)?.hold_job(parse_job_id(&job_id)?).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ipp_release_job(printer_uri: String, job_id: String) -> Result<(), String> {
+    IppClient::new(&printer_uri)?.release_job(parse_job_id(&job_id)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // 本地 IPP 替身服务：接收一个请求，交给 handler 生成响应
+    async fn serve_once<F>(handler: F) -> String
+    where
+        F: FnOnce(IppMessage) -> IppMessage + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let (header_end, content_length) = loop {
+                let n = stream.read(&mut chunk).await.unwrap();
+                buffer.extend_from_slice(&chunk[..n]);
+                if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                    let headers = String::from_utf8_lossy(&buffer[..pos]).to_lowercase();
+                    let length = headers
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .map(|v| v.trim().parse::<usize>().unwrap())
+                        .unwrap();
+                    break (pos + 4, length);
+                }
+            };
+            while buffer.len() < header_end + content_length {
+                let n = stream.read(&mut chunk).await.unwrap();
+                buffer.extend_from_slice(&chunk[..n]);
+            }
+
+            let request = IppMessage::decode(&buffer[header_end..]).unwrap();
+            let body = handler(request).encode();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/ipp\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+        });
+
+        format!("ipp://{}/printers/test", addr)
+    }
+
+    fn response(request: &IppMessage, status: u16) -> IppMessage {
+        let mut response = IppMessage::request(status, request.request_id);
+        response.code = status;
+        response
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let mut message = IppMessage::request(GET_JOBS, 7);
+        message.push(OPERATION_ATTRIBUTES, IppAttribute {
+            name: "requested-attributes".to_string(),
+            values: vec![IppValue::Keyword("job-id".to_string()), IppValue::Keyword("job-state".to_string())],
+        });
+        message.push(JOB_ATTRIBUTES, IppAttribute::new("copies", IppValue::Integer(2)));
+        message.push(JOB_ATTRIBUTES, IppAttribute::new("page-ranges", IppValue::Range { lower: 1, upper: 3 }));
+        message.push(JOB_ATTRIBUTES, IppAttribute::new("job-name", IppValue::NameWithLanguage {
+            language: "zh-cn".to_string(),
+            name: "电子面单".to_string(),
+        }));
+        message.push(JOB_ATTRIBUTES, IppAttribute::new("media-col", IppValue::Collection(vec![
+            IppAttribute::new("media-type", IppValue::Keyword("labels".to_string())),
+            IppAttribute::new("media-size", IppValue::Collection(vec![
+                IppAttribute::new("x-dimension", IppValue::Integer(7600)),
+                IppAttribute::new("y-dimension", IppValue::Integer(13000)),
+            ])),
+        ])));
+        message.data = b"%PDF-1.4".to_vec();
+
+        let decoded = IppMessage::decode(&message.encode()).unwrap();
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn ipp_uri_maps_to_http_url() {
+        assert_eq!(http_url("ipp://printer.local/ipp/print").unwrap(), "http://printer.local:631/ipp/print");
+        assert_eq!(http_url("ipps://10.0.0.5:8631/ipp").unwrap(), "https://10.0.0.5:8631/ipp");
+        assert_eq!(http_url("ipp://[fe80::1]/ipp").unwrap(), "http://[fe80::1]:631/ipp");
+        assert!(http_url("lpd://host/queue").is_err());
+    }
+
+    #[tokio::test]
+    async fn print_job_returns_job_id() {
+        let uri = serve_once(|request| {
+            assert_eq!(request.code, PRINT_JOB);
+            let operation = request.group(OPERATION_ATTRIBUTES).unwrap();
+            assert_eq!(operation.get_str("document-format"), Some("application/pdf"));
+            assert_eq!(operation.get_str("job-name"), Some("waybill.pdf"));
+            assert_eq!(request.data, b"%PDF-1.4 test");
+
+            let mut response = response(&request, 0x0000);
+            response.push(JOB_ATTRIBUTES, IppAttribute::new("job-id", IppValue::Integer(42)));
+            response.push(JOB_ATTRIBUTES, IppAttribute::new("job-state", IppValue::Enum(3)));
+            response
+        })
+        .await;
+
+        let client = IppClient::new(&uri).unwrap();
+        let job_id = client.print_job("waybill.pdf", "application/pdf", b"%PDF-1.4 test".to_vec()).await.unwrap();
+        assert_eq!(job_id, 42);
+    }
+
+    #[tokio::test]
+    async fn get_jobs_decodes_each_job_group() {
+        let uri = serve_once(|request| {
+            assert_eq!(request.code, GET_JOBS);
+            let mut response = response(&request, 0x0000);
+            for (id, state) in [(1, 5), (2, 4)] {
+                response.groups.push(IppGroup {
+                    tag: JOB_ATTRIBUTES,
+                    attributes: vec![
+                        IppAttribute::new("job-id", IppValue::Integer(id)),
+                        IppAttribute::new("job-state", IppValue::Enum(state)),
+                        IppAttribute::new("job-name", IppValue::Name(format!("job-{}", id))),
+                    ],
+                });
+            }
+            response
+        })
+        .await;
+
+        let client = IppClient::new(&uri).unwrap();
+        let jobs: Vec<PrintJob> = client
+            .get_jobs()
+            .await
+            .unwrap()
+            .iter()
+            .map(|job| job_from_attributes("test", job))
+            .collect();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].job_id, "1");
+        assert_eq!(jobs[0].job_status.as_ref().unwrap().name, "Printing");
+        assert_eq!(jobs[1].document_name, "job-2");
+        assert_eq!(jobs[1].job_status.as_ref().unwrap().name, "Paused");
+    }
+
+    #[tokio::test]
+    async fn error_status_is_reported() {
+        let uri = serve_once(|request| {
+            assert_eq!(request.code, CANCEL_JOB);
+            assert_eq!(request.group(OPERATION_ATTRIBUTES).unwrap().get_i32("job-id"), Some(9));
+            let mut response = response(&request, 0x0406);
+            response.push(OPERATION_ATTRIBUTES, IppAttribute::new("status-message", IppValue::Text("Job #9 does not exist.".to_string())));
+            response
+        })
+        .await;
+
+        let err = IppClient::new(&uri).unwrap().cancel_job(9).await.unwrap_err();
+        assert!(err.contains("0x0406"));
+        assert!(err.contains("Job #9 does not exist."));
+    }
+}
@@ -0,0 +1,730 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use tauri::State;
+use crate::backend::PrinterBackend;
+use crate::declare::Destination;
+use crate::destination;
+use crate::retry::PrintError;
+use crate::settings::PrintSettings;
+
+// 已离开本程序的作业在日志中保留的时间
+const RETENTION_MILLIS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+// 作业生命周期：Received → Rendering → Submitting → Spooled → Printing → Completed / Failed / Cancelled
+// 不需要生成 PDF 的作业跳过 Rendering
+// Retrying、DeadLetter、Interrupted 是提交失败或应用退出时的旁路状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    // 已记录，在打印机队列中等待提交
+    #[serde(alias = "queued")]
+    Received,
+    // 正在生成 PDF，此时还没有任何数据发给打印机
+    Rendering,
+    // 正在提交给打印机
+    Submitting,
+    // 已进入系统打印队列或发送到网络打印机
+    Spooled,
+    // 打印机正在打印
+    Printing,
+    Completed,
+    Failed,
+    #[serde(alias = "discarded")]
+    Cancelled,
+    // 临时错误，等待下一次重试
+    Retrying,
+    // 重试次数用完，等待人工重试或丢弃
+    DeadLetter,
+    // 提交过程中应用退出，无法确认是否已经打印，需要人工确认
+    Interrupted,
+}
+
+impl JobState {
+    // 仍在本程序中处理、需要调度提交的作业
+    pub fn is_active(&self) -> bool {
+        matches!(self, JobState::Received | JobState::Rendering | JobState::Submitting | JobState::Retrying)
+    }
+
+    // 等待人工处理的作业：重试次数用完，或应用退出时正在提交、无法确认是否已打印
+    // 只能重新加入队列或丢弃
+    pub fn needs_attention(&self) -> bool {
+        matches!(self, JobState::DeadLetter | JobState::Interrupted)
+    }
+
+    // 不会再发生变化的状态
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed | JobState::Cancelled)
+    }
+
+    // 生命周期中允许的状态变化
+    pub fn can_transition(&self, next: JobState) -> bool {
+        use JobState::*;
+        match self {
+            Received => matches!(next, Rendering | Submitting | Failed | Cancelled),
+            Rendering => matches!(next, Submitting | Failed),
+            Submitting => matches!(next, Spooled | Failed | Retrying | DeadLetter | Interrupted),
+            Retrying => matches!(next, Submitting | Failed | Cancelled),
+            Spooled => matches!(next, Printing | Completed | Failed | Cancelled),
+            Printing => matches!(next, Completed | Failed | Cancelled),
+            DeadLetter | Interrupted => matches!(next, Received | Cancelled),
+            Completed | Failed | Cancelled => false,
+        }
+    }
+}
+
+// 一次状态变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transition {
+    pub state: JobState,
+    // 毫秒时间戳
+    pub at: u64,
+    pub reason: String,
+}
+
+// 待打印文件的格式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocumentFormat {
+    // 交给系统打印队列按打印设置打印
+    Pdf,
+    // 已经生成好的打印机指令 (ESC/POS、ZPL、TSPL 等)，原样发送；document_name 为队列中显示的作业名称
+    Raw { document_name: String },
+}
+
+// 枚举上的 #[default] 需要 Rust 1.62，这里手写
+#[allow(clippy::derivable_impls)]
+impl Default for DocumentFormat {
+    fn default() -> Self {
+        DocumentFormat::Pdf
+    }
+}
+
+// 日志中的一条作业记录，也直接返回给前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: String,
+    // 待打印的文件路径
+    pub document: String,
+    // 旧版本的日志没有这个字段，都是 PDF
+    #[serde(default)]
+    pub format: DocumentFormat,
+    pub destination: Destination,
+    pub print_setting: PrintSettings,
+    pub remove_after_print: bool,
+    pub state: JobState,
+    // 毫秒时间戳
+    pub created_at: u64,
+    pub updated_at: u64,
+    // 进入打印队列后的系统作业 ID (CUPS、Windows 打印队列或 LPD 作业号)
+    #[serde(default)]
+    pub spooler_job_id: Option<String>,
+    // 最近一次失败的原因
+    #[serde(default)]
+    pub error: Option<String>,
+    // 已经提交过的次数
+    #[serde(default)]
+    pub attempts: u32,
+    // 等待重试时下一次提交的时间
+    #[serde(default)]
+    pub next_attempt_at: Option<u64>,
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+}
+
+// 推送给前端的 job-state-changed 事件
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStateChanged {
+    pub job_id: String,
+    pub destination: String,
+    pub previous: Option<JobState>,
+    pub state: JobState,
+    pub at: u64,
+    pub reason: String,
+    pub spooler_job_id: Option<String>,
+}
+
+type Listener = Box<dyn Fn(JobStateChanged) + Send + Sync>;
+
+pub type SharedJournal = Arc<JobJournal>;
+
+// 打印作业日志 (JSON Lines)：每次状态变化追加一行完整的作业记录，只追加不修改
+// 启动时按 id 取最后一行还原作业，再把仍需保留的记录重写为新文件
+pub struct JobJournal {
+    path: PathBuf,
+    jobs: Mutex<Vec<JobRecord>>,
+    sequence: AtomicU64,
+    listener: Mutex<Option<Listener>>,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl JobJournal {
+    pub fn open(path: PathBuf) -> Self {
+        let jobs = replay(&path);
+        let now = now_millis();
+        let jobs: Vec<JobRecord> = jobs
+            .into_iter()
+            .filter(|job| job.state.is_active() || now.saturating_sub(job.updated_at) < RETENTION_MILLIS)
+            .collect();
+        if let Err(e) = compact(&path, &jobs) {
+            println!("整理打印作业日志失败: {}", e);
+        }
+        println!("已加载打印作业日志: {} 条记录", jobs.len());
+
+        Self {
+            path,
+            jobs: Mutex::new(jobs),
+            sequence: AtomicU64::new(0),
+            listener: Mutex::new(None),
+        }
+    }
+
+    // 每次状态变化后调用，用于推送 job-state-changed 事件
+    pub fn set_listener<F: Fn(JobStateChanged) + Send + Sync + 'static>(&self, listener: F) {
+        *self.listener.lock() = Some(Box::new(listener));
+    }
+
+    // 记录新提交的作业
+    pub fn submit(
+        &self,
+        destination: Destination,
+        document: String,
+        print_setting: PrintSettings,
+        remove_after_print: bool,
+    ) -> Result<JobRecord, String> {
+        self.insert(destination, document, DocumentFormat::Pdf, print_setting, remove_after_print)
+    }
+
+    // 记录打印机指令作业，document 为写有指令的临时文件，发送后删除
+    pub fn submit_raw(&self, destination: Destination, document: String, document_name: &str) -> Result<JobRecord, String> {
+        let format = DocumentFormat::Raw {
+            document_name: document_name.to_string(),
+        };
+        self.insert(destination, document, format, PrintSettings::default(), true)
+    }
+
+    fn insert(
+        &self,
+        destination: Destination,
+        document: String,
+        format: DocumentFormat,
+        print_setting: PrintSettings,
+        remove_after_print: bool,
+    ) -> Result<JobRecord, String> {
+        let now = now_millis();
+        let reason = "已加入打印队列".to_string();
+        let mut jobs = self.jobs.lock();
+        // 序号每次启动从 0 开始，同一毫秒内重启时跳过日志中已有的 ID
+        let id = loop {
+            let id = format!("{}-{}", now, self.sequence.fetch_add(1, Ordering::Relaxed));
+            if !jobs.iter().any(|job| job.id == id) {
+                break id;
+            }
+        };
+        let job = JobRecord {
+            id,
+            document,
+            format,
+            destination,
+            print_setting,
+            remove_after_print,
+            state: JobState::Received,
+            created_at: now,
+            updated_at: now,
+            spooler_job_id: None,
+            error: None,
+            attempts: 0,
+            next_attempt_at: None,
+            transitions: vec![Transition {
+                state: JobState::Received,
+                at: now,
+                reason: reason.clone(),
+            }],
+        };
+
+        self.append(&job)?;
+        jobs.push(job.clone());
+        drop(jobs);
+        self.notify(&job, None, reason);
+        Ok(job)
+    }
+
+    // 按生命周期切换状态
+    pub fn transition(&self, id: &str, state: JobState, reason: &str) -> Result<JobRecord, String> {
+        self.apply(id, state, reason, |_| {})
+    }
+
+    // 开始一次提交，累计提交次数
+    pub fn start_attempt(&self, id: &str) -> Result<JobRecord, String> {
+        self.apply(id, JobState::Submitting, "正在提交到打印机", |job| {
+            job.attempts += 1;
+            job.next_attempt_at = None;
+        })
+    }
+
+    // 已进入打印队列，记录系统作业 ID
+    pub fn spooled(&self, id: &str, spooler_job_id: Option<String>) -> Result<JobRecord, String> {
+        let reason = match &spooler_job_id {
+            Some(spooler_job_id) => format!("已进入打印队列，作业 ID: {}", spooler_job_id),
+            None => "已进入打印队列".to_string(),
+        };
+        self.apply(id, JobState::Spooled, &reason, |job| {
+            job.spooler_job_id = spooler_job_id;
+            job.error = None;
+        })
+    }
+
+    // 失败、进入死信列表或中断，同时记录错误
+    pub fn fail(&self, id: &str, state: JobState, error: &str) -> Result<JobRecord, String> {
+        self.apply(id, state, error, |job| {
+            job.error = Some(error.to_string());
+            job.next_attempt_at = None;
+        })
+    }
+
+    // 临时错误，记录错误和下一次重试的时间
+    pub fn schedule_retry(&self, id: &str, error: &str, next_attempt_at: u64) -> Result<JobRecord, String> {
+        self.apply(id, JobState::Retrying, error, |job| {
+            job.error = Some(error.to_string());
+            job.next_attempt_at = Some(next_attempt_at);
+        })
+    }
+
+    // 人工重试：清空提交次数，重新排队
+    pub fn requeue(&self, id: &str) -> Result<JobRecord, String> {
+        self.apply(id, JobState::Received, "重新加入打印队列", |job| {
+            job.attempts = 0;
+            job.error = None;
+            job.next_attempt_at = None;
+        })
+    }
+
+    fn apply<F: FnOnce(&mut JobRecord)>(&self, id: &str, state: JobState, reason: &str, change: F) -> Result<JobRecord, String> {
+        let (updated, previous) = {
+            let mut jobs = self.jobs.lock();
+            let job = jobs
+                .iter_mut()
+                .find(|job| job.id == id)
+                .ok_or_else(|| format!("打印作业不存在: {}", id))?;
+            let previous = job.state;
+            if !previous.can_transition(state) {
+                return Err(format!("打印作业 {} 不能从 {:?} 变为 {:?}", id, previous, state));
+            }
+
+            let mut updated = job.clone();
+            change(&mut updated);
+            let now = now_millis();
+            updated.state = state;
+            updated.updated_at = now;
+            updated.transitions.push(Transition {
+                state,
+                at: now,
+                reason: reason.to_string(),
+            });
+
+            self.append(&updated)?;
+            *job = updated.clone();
+            (updated, previous)
+        };
+        self.notify(&updated, Some(previous), reason.to_string());
+        Ok(updated)
+    }
+
+    fn notify(&self, job: &JobRecord, previous: Option<JobState>, reason: String) {
+        if let Some(listener) = self.listener.lock().as_ref() {
+            listener(JobStateChanged {
+                job_id: job.id.clone(),
+                destination: job.destination.key(),
+                previous,
+                state: job.state,
+                at: job.updated_at,
+                reason,
+                spooler_job_id: job.spooler_job_id.clone(),
+            });
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobRecord> {
+        self.jobs.lock().iter().find(|job| job.id == id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<JobRecord> {
+        self.jobs.lock().clone()
+    }
+
+    // 启动时检查上次未提交完的作业：提交中的标记为中断，等待中和等待重试的返回给调用方继续打印
+    pub fn recover(&self) -> Vec<JobRecord> {
+        let active: Vec<JobRecord> = self.list().into_iter().filter(|job| job.state.is_active()).collect();
+        let mut resumable = Vec::new();
+
+        for job in active {
+            let update = match job.state {
+                // 生成 PDF 时退出，还没有发给打印机，PDF 也不完整
+                JobState::Rendering => self.fail(&job.id, JobState::Failed, "应用在生成 PDF 时退出"),
+                JobState::Submitting => self.fail(&job.id, JobState::Interrupted, "应用在提交打印时退出，无法确认是否已打印"),
+                _ if !Path::new(&job.document).exists() => {
+                    self.fail(&job.id, JobState::Failed, &format!("文件不存在: {}", job.document))
+                }
+                _ => {
+                    resumable.push(job);
+                    continue;
+                }
+            };
+            if let Err(e) = update {
+                println!("更新打印作业状态失败: {}", e);
+            }
+        }
+        resumable
+    }
+
+    fn append(&self, job: &JobRecord) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建数据目录失败: {}", e))?;
+        }
+        let mut line = serde_json::to_string(job).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("打开打印作业日志失败: {}", e))?;
+        file.write_all(line.as_bytes()).map_err(|e| format!("写入打印作业日志失败: {}", e))?;
+        file.sync_data().map_err(|e| format!("写入打印作业日志失败: {}", e))
+    }
+}
+
+// 逐行读取日志，同一作业以最后一行为准；崩溃时写了一半的行直接跳过
+fn replay(path: &Path) -> Vec<JobRecord> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut jobs: Vec<JobRecord> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<JobRecord>(&line) {
+            Ok(job) => match jobs.iter_mut().find(|existing| existing.id == job.id) {
+                Some(existing) => *existing = job,
+                None => jobs.push(job),
+            },
+            Err(e) => println!("跳过无法解析的打印作业记录: {}", e),
+        }
+    }
+    jobs
+}
+
+// 先写临时文件再替换，避免整理过程中崩溃丢失日志
+fn compact(path: &Path, jobs: &[JobRecord]) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let temp_path = path.with_extension("jsonl.tmp");
+    let mut content = String::new();
+    for job in jobs {
+        content.push_str(&serde_json::to_string(job).map_err(|e| e.to_string())?);
+        content.push('\n');
+    }
+    fs::write(&temp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&temp_path, path).map_err(|e| e.to_string())
+}
+
+// 提交日志中的作业，成功时记录系统作业 ID；失败后的状态由调用方按重试策略决定
+// 网络打印机没有本地打印队列可以跟踪，发送完成即视为打印完成
+pub fn execute(journal: &JobJournal, backend: &dyn PrinterBackend, job: &JobRecord) -> Result<Option<String>, PrintError> {
+    journal.start_attempt(&job.id)?;
+    let result = destination::print_to(
+        backend,
+        job.destination.clone(),
+        job.document.clone(),
+        &job.format,
+        job.print_setting.clone(),
+        job.remove_after_print,
+    );
+
+    if let Ok(spooler_job_id) = &result {
+        let mut update = journal.spooled(&job.id, spooler_job_id.clone());
+        if !matches!(job.destination, Destination::Queue { .. }) {
+            update = update.and_then(|_| journal.transition(&job.id, JobState::Completed, "已发送到网络打印机"));
+        }
+        if let Err(e) = update {
+            println!("更新打印作业状态失败: {}", e);
+        }
+    }
+    result
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_print_queue(journal: State<'_, SharedJournal>) -> Vec<JobRecord> {
+    journal.list()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_queued_job(job_id: String, journal: State<'_, SharedJournal>) -> Option<JobRecord> {
+    journal.get(&job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use crate::fsys::unique_temp_path;
+
+    fn journal_path() -> PathBuf {
+        unique_temp_path("journal_test", "d").join("print_jobs.jsonl")
+    }
+
+    fn raw_destination() -> Destination {
+        Destination::Raw {
+            host: "127.0.0.1".to_string(),
+            port: 9100,
+        }
+    }
+
+    fn submit(journal: &JobJournal, document: &Path) -> JobRecord {
+        journal
+            .submit(raw_destination(), document.display().to_string(), PrintSettings::default(), false)
+            .unwrap()
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap().lines().map(str::to_string).collect()
+    }
+
+    fn states(job: &JobRecord) -> Vec<JobState> {
+        job.transitions.iter().map(|transition| transition.state).collect()
+    }
+
+    #[test]
+    fn replay_restores_latest_record() {
+        let path = journal_path();
+        let journal = JobJournal::open(path.clone());
+        let a = submit(&journal, Path::new("a.pdf"));
+        let b = submit(&journal, Path::new("b.pdf"));
+        assert_ne!(a.id, b.id);
+        journal.start_attempt(&a.id).unwrap();
+        journal.spooled(&a.id, Some("12".to_string())).unwrap();
+        // 尚未提交过的作业不能进入重试，失败的变化不写日志
+        journal.schedule_retry(&b.id, "打印机脱机", 0).unwrap_err();
+        drop(journal);
+        // 每次变化追加一行
+        assert_eq!(lines(&path).len(), 4);
+
+        let journal = JobJournal::open(path.clone());
+        let jobs = journal.list();
+        assert_eq!(jobs.iter().map(|job| job.id.clone()).collect::<Vec<_>>(), vec![a.id.clone(), b.id.clone()]);
+        let a = journal.get(&a.id).unwrap();
+        assert_eq!(a.state, JobState::Spooled);
+        assert_eq!(a.spooler_job_id.as_deref(), Some("12"));
+        assert_eq!(a.attempts, 1);
+        assert_eq!(states(&a), vec![JobState::Received, JobState::Submitting, JobState::Spooled]);
+        assert_eq!(journal.get(&b.id).unwrap().state, JobState::Received);
+    }
+
+    #[test]
+    fn open_compacts_to_one_line_per_job() {
+        let path = journal_path();
+        let journal = JobJournal::open(path.clone());
+        let kept = submit(&journal, Path::new("a.pdf"));
+        journal.start_attempt(&kept.id).unwrap();
+        journal.spooled(&kept.id, None).unwrap();
+        journal.transition(&kept.id, JobState::Completed, "已完成").unwrap();
+
+        // 超过保留期的已结束作业被丢弃，仍在处理中的作业无论多久都保留
+        let mut expired = submit(&journal, Path::new("b.pdf"));
+        let mut waiting = submit(&journal, Path::new("c.pdf"));
+        drop(journal);
+        expired.id = "expired".to_string();
+        expired.state = JobState::Completed;
+        expired.updated_at = 0;
+        waiting.id = "waiting".to_string();
+        waiting.updated_at = 0;
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        for job in [&expired, &waiting] {
+            writeln!(file, "{}", serde_json::to_string(job).unwrap()).unwrap();
+        }
+        drop(file);
+
+        let journal = JobJournal::open(path.clone());
+        let ids: Vec<String> = journal.list().into_iter().map(|job| job.id).collect();
+        assert_eq!(ids.len(), 4);
+        assert!(!ids.contains(&"expired".to_string()));
+        assert!(ids.contains(&"waiting".to_string()));
+        assert_eq!(lines(&path).len(), 4);
+        assert!(!path.with_extension("jsonl.tmp").exists());
+
+        // 整理后的文件可以原样读回
+        let reopened = JobJournal::open(path.clone());
+        assert_eq!(reopened.get(&kept.id).unwrap().state, JobState::Completed);
+        assert_eq!(states(&reopened.get(&kept.id).unwrap()).len(), 4);
+    }
+
+    #[test]
+    fn torn_final_line_is_dropped() {
+        let path = journal_path();
+        let journal = JobJournal::open(path.clone());
+        let job = submit(&journal, Path::new("a.pdf"));
+        journal.start_attempt(&job.id).unwrap();
+        drop(journal);
+
+        // 崩溃时写了一半的最后一行
+        let full = lines(&path).pop().unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&full.as_bytes()[..full.len() / 2]).unwrap();
+        drop(file);
+
+        let journal = JobJournal::open(path.clone());
+        assert_eq!(journal.list().len(), 1);
+        assert_eq!(journal.get(&job.id).unwrap().state, JobState::Submitting);
+        // 残行在打开时被整理掉，后续追加的记录不会接在残行后面
+        let next = submit(&journal, Path::new("b.pdf"));
+        drop(journal);
+        let journal = JobJournal::open(path);
+        assert_eq!(journal.list().len(), 2);
+        assert_eq!(journal.get(&next.id).unwrap().state, JobState::Received);
+    }
+
+    #[test]
+    fn recover_interrupts_jobs_being_submitted() {
+        let path = journal_path();
+        let document = path.with_file_name("present.pdf");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&document, b"%PDF").unwrap();
+        let journal = JobJournal::open(path.clone());
+
+        let submitting = submit(&journal, &document);
+        journal.start_attempt(&submitting.id).unwrap();
+        let rendering = submit(&journal, &document);
+        journal.transition(&rendering.id, JobState::Rendering, "正在生成 PDF").unwrap();
+        let waiting = submit(&journal, &document);
+        let retrying = submit(&journal, &document);
+        journal.start_attempt(&retrying.id).unwrap();
+        journal.schedule_retry(&retrying.id, "打印机脱机", 0).unwrap();
+        let missing = submit(&journal, Path::new("missing.pdf"));
+        let done = submit(&journal, &document);
+        journal.transition(&done.id, JobState::Cancelled, "已取消").unwrap();
+        drop(journal);
+
+        let journal = JobJournal::open(path);
+        let resumable: Vec<String> = journal.recover().into_iter().map(|job| job.id).collect();
+        assert_eq!(resumable, vec![waiting.id.clone(), retrying.id.clone()]);
+
+        let interrupted = journal.get(&submitting.id).unwrap();
+        assert_eq!(interrupted.state, JobState::Interrupted);
+        assert!(interrupted.error.is_some());
+        // 生成 PDF 时退出的作业没有发给打印机，直接失败
+        let rendering = journal.get(&rendering.id).unwrap();
+        assert_eq!(rendering.state, JobState::Failed);
+        assert!(rendering.error.unwrap().contains("生成 PDF"));
+        let missing = journal.get(&missing.id).unwrap();
+        assert_eq!(missing.state, JobState::Failed);
+        assert!(missing.error.unwrap().contains("文件不存在"));
+        assert_eq!(journal.get(&done.id).unwrap().state, JobState::Cancelled);
+
+        // 中断的作业可以人工重新排队
+        let requeued = journal.requeue(&submitting.id).unwrap();
+        assert_eq!((requeued.state, requeued.attempts), (JobState::Received, 0));
+    }
+
+    #[test]
+    fn old_records_are_readable() {
+        let path = journal_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let now = now_millis();
+        let old = serde_json::json!({
+            "id": "1-0",
+            "document": "a.pdf",
+            "destination": { "type": "queue", "id": "HP" },
+            "print_setting": {},
+            "remove_after_print": false,
+            "state": "queued",
+            "created_at": now,
+            "updated_at": now
+        });
+        let mut discarded = old.clone();
+        discarded["id"] = "1-1".into();
+        discarded["state"] = "discarded".into();
+        fs::write(&path, format!("{}\n\n{}\n", old, discarded)).unwrap();
+
+        let journal = JobJournal::open(path);
+        let job = journal.get("1-0").unwrap();
+        assert_eq!(job.state, JobState::Received);
+        assert_eq!(job.format, DocumentFormat::Pdf);
+        assert_eq!(job.attempts, 0);
+        assert!(job.transitions.is_empty());
+        assert_eq!(journal.get("1-1").unwrap().state, JobState::Cancelled);
+    }
+
+    #[test]
+    fn listener_sees_every_change() {
+        let journal = JobJournal::open(journal_path());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        journal.set_listener(move |event| sink.lock().push(event));
+
+        let job = submit(&journal, Path::new("a.pdf"));
+        journal.start_attempt(&job.id).unwrap();
+        journal.fail(&job.id, JobState::Failed, "文件不存在").unwrap();
+        // 不允许的状态变化不写日志也不通知
+        assert!(journal.transition(&job.id, JobState::Printing, "").is_err());
+
+        let events = events.lock();
+        let changes: Vec<(Option<JobState>, JobState)> = events.iter().map(|event| (event.previous, event.state)).collect();
+        assert_eq!(
+            changes,
+            vec![
+                (None, JobState::Received),
+                (Some(JobState::Received), JobState::Submitting),
+                (Some(JobState::Submitting), JobState::Failed),
+            ]
+        );
+        assert_eq!(events[0].destination, raw_destination().key());
+        assert_eq!(events[2].reason, "文件不存在");
+    }
+
+    #[test]
+    fn lifecycle_transitions() {
+        use JobState::*;
+        let allowed = [
+            (Received, vec![Rendering, Submitting, Failed, Cancelled]),
+            (Rendering, vec![Submitting, Failed]),
+            (Submitting, vec![Spooled, Failed, Retrying, DeadLetter, Interrupted]),
+            (Retrying, vec![Submitting, Failed, Cancelled]),
+            (Spooled, vec![Printing, Completed, Failed, Cancelled]),
+            (Printing, vec![Completed, Failed, Cancelled]),
+            (DeadLetter, vec![Received, Cancelled]),
+            (Interrupted, vec![Received, Cancelled]),
+            (Completed, vec![]),
+            (Failed, vec![]),
+            (Cancelled, vec![]),
+        ];
+        let all: Vec<JobState> = allowed.iter().map(|(state, _)| *state).collect();
+        for (state, next_states) in &allowed {
+            for next in &all {
+                assert_eq!(state.can_transition(*next), next_states.contains(next), "{:?} -> {:?}", state, next);
+            }
+            assert_eq!(state.is_terminal(), next_states.is_empty(), "{:?}", state);
+        }
+
+        let active: Vec<JobState> = all.iter().copied().filter(JobState::is_active).collect();
+        assert_eq!(active, vec![Received, Rendering, Submitting, Retrying]);
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Serialize, Deserialize};
+use crate::barcode::Symbology;
+
+// 面单版式，坐标与尺寸单位均为毫米，由各打印语言按 dpi 换算为点
+// ZPL、TSPL 等标签打印语言共用这一份版式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelLayout {
+    pub width: f32,
+    pub height: f32,
+    #[serde(default = "default_dpi")]
+    pub dpi: u32,
+    #[serde(default = "default_copies")]
+    pub copies: u32,
+    // 标签之间的间隙 (毫米)，TSPL 的 GAP 指令使用
+    #[serde(default = "default_gap")]
+    pub gap: f32,
+    // 打印中文时使用的打印机内置字体，不同打印语言取值不同
+    pub font: Option<String>,
+    pub elements: Vec<LabelElement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LabelElement {
+    Text {
+        x: f32,
+        y: f32,
+        content: String,
+        // 字高 (毫米)
+        #[serde(default = "default_font_size")]
+        font_size: f32,
+        #[serde(default)]
+        bold: bool,
+    },
+    // 条码，默认 Code128；二维码制的 module_width 为模块边长，不使用 height
+    Barcode {
+        x: f32,
+        y: f32,
+        data: String,
+        #[serde(default)]
+        symbology: Symbology,
+        #[serde(default)]
+        height: f32,
+        // 窄条宽度 (点)
+        module_width: Option<u32>,
+        #[serde(default)]
+        human_readable: bool,
+    },
+    Qrcode {
+        x: f32,
+        y: f32,
+        data: String,
+        // 模块大小 (点)
+        module_size: Option<u32>,
+    },
+    Box {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        #[serde(default = "default_thickness")]
+        thickness: f32,
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        #[serde(default = "default_thickness")]
+        thickness: f32,
+    },
+    // base64 编码的 PNG / JPEG 图片，width 为空时按原始像素打印
+    Image {
+        x: f32,
+        y: f32,
+        data: String,
+        width: Option<f32>,
+    },
+}
+
+fn default_dpi() -> u32 {
+    203
+}
+
+fn default_copies() -> u32 {
+    1
+}
+
+fn default_gap() -> f32 {
+    2.0
+}
+
+fn default_font_size() -> f32 {
+    3.0
+}
+
+fn default_thickness() -> f32 {
+    0.25
+}
+
+impl LabelLayout {
+    // 毫米换算为点
+    pub fn dots(&self, mm: f32) -> u32 {
+        (mm.max(0.0) * self.dpi as f32 / 25.4).round() as u32
+    }
+}
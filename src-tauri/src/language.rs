@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use tauri::State;
+use crate::label::LabelLayout;
+use crate::{tspl, zpl};
+
+// 打印机的面单输出语言，默认沿用 PDF
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputLanguage {
+    Pdf,
+    Zpl,
+    Tspl,
+}
+
+// 枚举上的 #[default] 需要 Rust 1.62，这里手写
+#[allow(clippy::derivable_impls)]
+impl Default for OutputLanguage {
+    fn default() -> Self {
+        OutputLanguage::Pdf
+    }
+}
+
+// 按面单版式生成指定语言的打印数据
+pub fn render(language: OutputLanguage, layout: &LabelLayout) -> Result<Vec<u8>, String> {
+    match language {
+        OutputLanguage::Zpl => zpl::render_zpl(layout).map(String::into_bytes),
+        OutputLanguage::Tspl => tspl::render_tspl(layout),
+        OutputLanguage::Pdf => Err("该打印机使用 PDF 输出，请通过 print_pdf 打印".to_string()),
+    }
+}
+
+// 每台打印机选择的输出语言，保存在应用数据目录
+pub struct OutputLanguages {
+    path: PathBuf,
+    languages: Mutex<HashMap<String, OutputLanguage>>,
+}
+
+impl OutputLanguages {
+    pub fn load(path: PathBuf) -> Self {
+        let languages = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            languages: Mutex::new(languages),
+        }
+    }
+
+    pub fn get(&self, printer: &str) -> OutputLanguage {
+        self.languages.lock().get(printer).copied().unwrap_or_default()
+    }
+
+    pub fn set(&self, printer: &str, language: OutputLanguage) -> Result<(), String> {
+        let mut languages = self.languages.lock();
+        languages.insert(printer.to_string(), language);
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(&*languages).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| format!("保存输出语言失败: {}", e))
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_output_language(printer: String, languages: State<'_, OutputLanguages>) -> OutputLanguage {
+    languages.get(&printer)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_output_language(printer: String, language: OutputLanguage, languages: State<'_, OutputLanguages>) -> Result<(), String> {
+    println!("设置打印机 {} 的输出语言: {:?}", printer, language);
+    languages.set(&printer, language)
+}
@@ -0,0 +1,419 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::declare::{JobStatus, PrintJob, Printer, PrinterState};
+use crate::raw::connect;
+use crate::retry::PrintError;
+
+// LPD 默认端口 (RFC 1179)
+pub const DEFAULT_PORT: u16 = 515;
+
+// 控制文件中每份副本占一行，份数上限避免生成过大的控制文件
+pub const MAX_COPIES: u32 = 999;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+// 一个 LPD 打印作业的控制信息
+pub struct LpdJob {
+    pub job_name: String,
+    pub user: String,
+    pub copies: u32,
+}
+
+impl LpdJob {
+    pub fn new(job_name: &str, copies: u32) -> Self {
+        Self {
+            job_name: job_name.to_string(),
+            user: local_user(),
+            copies: copies.max(1),
+        }
+    }
+}
+
+// 目标上配置的份数乘以打印设置中的份数，两者都来自前端，相乘可能溢出
+pub fn total_copies(copies: u32, repeat: u32) -> Result<u32, String> {
+    copies
+        .max(1)
+        .checked_mul(repeat.max(1))
+        .filter(|total| *total <= MAX_COPIES)
+        .ok_or_else(|| format!("无效的打印份数: 最多 {} 份", MAX_COPIES))
+}
+
+// LPD 网络打印机，一个实例对应一个远端队列
+pub struct LpdPrinter {
+    pub host: String,
+    pub port: u16,
+    pub queue: String,
+}
+
+impl LpdPrinter {
+    pub fn new(host: &str, port: u16, queue: &str) -> Self {
+        Self {
+            host: host.trim().to_string(),
+            port,
+            queue: queue.trim().to_string(),
+        }
+    }
+
+    fn open(&self) -> Result<TcpStream, String> {
+        let stream = connect(&self.host, self.port, CONNECT_TIMEOUT)?;
+        stream
+            .set_write_timeout(Some(IO_TIMEOUT))
+            .and_then(|_| stream.set_read_timeout(Some(IO_TIMEOUT)))
+            .map_err(|e| format!("设置超时失败: {}", e))?;
+        Ok(stream)
+    }
+
+    // 提交作业：先发送控制文件，再发送数据文件，返回作业编号
+    // 数据文件开始发送前的失败可以重试，之后打印机可能已经收到部分数据，按致命错误处理
+    pub fn send_job(&self, job: &LpdJob, data: &[u8]) -> Result<u32, PrintError> {
+        if data.is_empty() {
+            return Err(PrintError::fatal("打印数据为空".to_string()));
+        }
+        if job.copies > MAX_COPIES {
+            return Err(PrintError::fatal(format!("无效的打印份数: 最多 {} 份", MAX_COPIES)));
+        }
+
+        let job_number = next_job_number();
+        let host = local_host();
+        let data_file = format!("dfA{:03}{}", job_number, host);
+        let control = control_file(&host, job, &data_file);
+
+        let mut stream = self.open().map_err(PrintError::transient)?;
+        // 02 queue LF: Receive a printer job
+        send_command(&mut stream, &format!("\x02{}\n", sanitize(&self.queue)), "接收作业").map_err(PrintError::transient)?;
+        // 02 count SP name LF: Receive control file
+        send_command(&mut stream, &format!("\x02{} cfA{:03}{}\n", control.len(), job_number, host), "接收控制文件")
+            .map_err(PrintError::transient)?;
+        send_file(&mut stream, control.as_bytes(), "控制文件").map_err(PrintError::transient)?;
+        // 03 count SP name LF: Receive data file
+        send_command(&mut stream, &format!("\x03{} {}\n", data.len(), data_file), "接收数据文件").map_err(PrintError::transient)?;
+        send_file(&mut stream, data, "数据文件").map_err(PrintError::fatal)?;
+
+        println!("LPD 打印成功: {}:{}/{} 作业 {}", self.host, self.port, self.queue, job_number);
+        Ok(job_number)
+    }
+
+    // 查询队列状态，long 为 true 时使用长格式 (04)，否则使用短格式 (03)
+    pub fn queue_state(&self, long: bool) -> Result<String, String> {
+        let mut stream = self.open()?;
+        let command = if long { '\x04' } else { '\x03' };
+        stream
+            .write_all(format!("{}{} \n", command, sanitize(&self.queue)).as_bytes())
+            .map_err(|e| format!("查询队列状态失败: {}", e))?;
+
+        let mut output = Vec::new();
+        stream
+            .read_to_end(&mut output)
+            .map_err(|e| format!("读取队列状态失败: {}", e))?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+}
+
+// 控制文件，行顺序参照 BSD lpr：H 主机、P 用户、J 作业名、N 文件名，每份副本一行 l (原样打印)
+fn control_file(host: &str, job: &LpdJob, data_file: &str) -> String {
+    let job_name = sanitize(&job.job_name);
+    let mut control = format!(
+        "H{}\nP{}\nJ{}\nN{}\n",
+        host,
+        sanitize(&job.user),
+        job_name,
+        job_name
+    );
+    for _ in 0..job.copies.max(1) {
+        control.push_str(&format!("l{}\n", data_file));
+    }
+    control.push_str(&format!("U{}\n", data_file));
+    control
+}
+
+// 发送命令并等待打印机返回 0 确认
+fn send_command(stream: &mut TcpStream, command: &str, step: &str) -> Result<(), String> {
+    stream
+        .write_all(command.as_bytes())
+        .map_err(|e| format!("LPD {}失败: {}", step, e))?;
+    read_ack(stream, step)
+}
+
+// 文件内容后跟一个 0 字节，同样需要确认
+fn send_file(stream: &mut TcpStream, content: &[u8], step: &str) -> Result<(), String> {
+    stream
+        .write_all(content)
+        .and_then(|_| stream.write_all(&[0]))
+        .map_err(|e| format!("LPD 发送{}失败: {}", step, e))?;
+    read_ack(stream, step)
+}
+
+fn read_ack(stream: &mut TcpStream, step: &str) -> Result<(), String> {
+    let mut ack = [0u8; 1];
+    stream
+        .read_exact(&mut ack)
+        .map_err(|e| format!("LPD {}未收到确认: {}", step, e))?;
+    if ack[0] != 0 {
+        return Err(format!("LPD {}被打印机拒绝 (代码 {})", step, ack[0]));
+    }
+    Ok(())
+}
+
+// 控制文件按行解析，去掉换行等控制字符，并按 RFC 1179 限制长度
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(99)
+        .collect()
+}
+
+// 作业编号为 000-999
+fn next_job_number() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_millis() % 1000) as u32)
+        .unwrap_or_default()
+}
+
+// 本机名称，只保留 ASCII 字母数字与连字符，最长 31 个字符
+fn local_host() -> String {
+    let host = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_default();
+    let host: String = host
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .take(31)
+        .collect();
+    if host.is_empty() {
+        "localhost".to_string()
+    } else {
+        host
+    }
+}
+
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "electronic-print".to_string())
+}
+
+// 解析短格式队列状态 (BSD lpd 格式)
+// Rank   Owner      Job  Files                                 Total Size
+// active root       12   label.zpl                             1024 bytes
+// 1st    root       13   waybill.pdf                           20480 bytes
+pub fn parse_queue_state(queue: &str, output: &str) -> Vec<PrintJob> {
+    let mut jobs = Vec::new();
+
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 4 || !is_rank(parts[0]) {
+            continue;
+        }
+
+        let job_id = parts[2];
+        if !job_id.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        // 末尾为 "<大小> bytes"，中间为文件名
+        let (files, size) = match parts.iter().rposition(|part| *part == "bytes") {
+            Some(index) if index > 3 => (parts[3..index - 1].join(" "), parts[index - 1].parse().unwrap_or_default()),
+            _ => (parts[3..].join(" "), 0),
+        };
+
+        let status = if parts[0] == "active" { 16 } else { 0 };
+        jobs.push(PrintJob {
+            username: parts[1].to_string(),
+            document_name: files,
+            size,
+            position: jobs.len() as u32 + 1,
+            job_status: Some(JobStatus::from_code(status)),
+            ..PrintJob::new(queue, job_id)
+        });
+    }
+
+    jobs
+}
+
+// 排队序号：active、1st、2nd、3rd、4th ...
+fn is_rank(rank: &str) -> bool {
+    if rank == "active" {
+        return true;
+    }
+    let digits = rank.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    !digits.is_empty() && digits.len() < rank.len() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+// 根据短格式队列状态构造打印机信息，以便和系统队列一起展示
+pub fn printer_from_queue_state(printer: &LpdPrinter, output: &str) -> Printer {
+    let jobs = parse_queue_state(&printer.queue, output);
+    let printing = jobs.iter().any(|job| matches!(&job.job_status, Some(status) if status.code == 16));
+
+    Printer {
+        job_count: jobs.len() as u32,
+        port_name: format!("lpd://{}:{}/{}", printer.host, printer.port, printer.queue),
+        computer_name: printer.host.clone(),
+        printer_status: if printing { 4 } else { 3 },
+        printer_type: 1,
+        state: PrinterState::new(printing, Vec::new()),
+        ..Printer::new(&printer.queue)
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn lpd_get_printer(host: String, port: Option<u16>, queue: String) -> Result<Printer, String> {
+    let printer = LpdPrinter::new(&host, port.unwrap_or(DEFAULT_PORT), &queue);
+    let output = printer.queue_state(false)?;
+    Ok(printer_from_queue_state(&printer, &output))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn lpd_get_jobs(host: String, port: Option<u16>, queue: String) -> Result<Vec<PrintJob>, String> {
+    let printer = LpdPrinter::new(&host, port.unwrap_or(DEFAULT_PORT), &queue);
+    let output = printer.queue_state(false)?;
+    Ok(parse_queue_state(&printer.queue, &output))
+}
+
+// 长格式没有统一的结构，原样返回给前端展示
+#[tauri::command(rename_all = "snake_case")]
+pub fn lpd_queue_state(host: String, port: Option<u16>, queue: String, long: bool) -> Result<String, String> {
+    LpdPrinter::new(&host, port.unwrap_or(DEFAULT_PORT), &queue).queue_state(long)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::ErrorClass;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // 本地 LPD 替身：按 RFC 1179 接收一个作业，返回 (命令行, 控制文件, 数据文件)
+    fn receive_job(listener: TcpListener) -> (String, String, Vec<u8>) {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+
+        let read_line = |reader: &mut BufReader<TcpStream>| {
+            let mut line = Vec::new();
+            reader.read_until(b'\n', &mut line).unwrap();
+            String::from_utf8(line).unwrap()
+        };
+
+        let command = read_line(&mut reader);
+        writer.write_all(&[0]).unwrap();
+
+        let mut files: Vec<Vec<u8>> = Vec::new();
+        for _ in 0..2 {
+            let subcommand = read_line(&mut reader);
+            let length: usize = subcommand[1..].split(' ').next().unwrap().parse().unwrap();
+            writer.write_all(&[0]).unwrap();
+
+            let mut content = vec![0u8; length + 1];
+            reader.read_exact(&mut content).unwrap();
+            assert_eq!(content.pop(), Some(0));
+            files.push(content);
+            writer.write_all(&[0]).unwrap();
+        }
+
+        let data = files.pop().unwrap();
+        let control = String::from_utf8(files.pop().unwrap()).unwrap();
+        (command, control, data)
+    }
+
+    #[test]
+    fn send_job_transfers_control_and_data_files() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || receive_job(listener));
+
+        let job = LpdJob {
+            job_name: "label\n.zpl".to_string(),
+            user: "warehouse".to_string(),
+            copies: 2,
+        };
+        let payload = b"^XA^FO50,50^FDHello^FS^XZ".to_vec();
+        LpdPrinter::new("127.0.0.1", port, "zebra").send_job(&job, &payload).unwrap();
+
+        let (command, control, data) = server.join().unwrap();
+        assert_eq!(command, "\x02zebra\n");
+        assert_eq!(data, payload);
+        assert!(control.contains("Pwarehouse\n"));
+        assert!(control.contains("Jlabel.zpl\n"));
+        assert_eq!(control.lines().filter(|line| line.starts_with("ldfA")).count(), 2);
+    }
+
+    #[test]
+    fn rejected_job_is_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut line = Vec::new();
+            BufReader::new(stream.try_clone().unwrap()).read_until(b'\n', &mut line).unwrap();
+            stream.write_all(&[1]).unwrap();
+        });
+
+        let err = LpdPrinter::new("127.0.0.1", port, "missing")
+            .send_job(&LpdJob::new("test", 1), b"data")
+            .unwrap_err();
+        server.join().unwrap();
+        assert!(err.message.contains("代码 1"));
+        // 数据文件还没有发送，稍后可以重试
+        assert_eq!(err.class, ErrorClass::Transient);
+    }
+
+    #[test]
+    fn queue_state_is_parsed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut line = Vec::new();
+            BufReader::new(stream.try_clone().unwrap()).read_until(b'\n', &mut line).unwrap();
+            stream
+                .write_all(
+                    b"lp is ready and printing\n\
+                      Rank   Owner      Job  Files                                 Total Size\n\
+                      active root       12   label.zpl                             1024 bytes\n\
+                      1st    alice      13   waybill 01.pdf                        20480 bytes\n",
+                )
+                .unwrap();
+            line
+        });
+
+        let printer = LpdPrinter::new("127.0.0.1", port, "lp");
+        let output = printer.queue_state(false).unwrap();
+        assert_eq!(server.join().unwrap(), b"\x03lp \n");
+
+        let jobs = parse_queue_state("lp", &output);
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].job_id, "12");
+        assert_eq!(jobs[0].job_status.as_ref().unwrap().name, "Printing");
+        assert_eq!(jobs[1].document_name, "waybill 01.pdf");
+        assert_eq!(jobs[1].size, 20480);
+        assert_eq!(jobs[1].username, "alice");
+
+        let info = printer_from_queue_state(&printer, &output);
+        assert_eq!(info.job_count, 2);
+        assert_eq!(info.printer_status, 4);
+    }
+
+    #[test]
+    fn empty_queue_has_no_jobs() {
+        assert!(parse_queue_state("lp", "lp is ready\nno entries\n").is_empty());
+    }
+
+    #[test]
+    fn copies_are_bounded() {
+        assert_eq!(total_copies(2, 3), Ok(6));
+        assert_eq!(total_copies(0, 1), Ok(1));
+        assert_eq!(total_copies(MAX_COPIES, 1), Ok(MAX_COPIES));
+        assert!(total_copies(MAX_COPIES, 2).is_err());
+        assert!(total_copies(u32::MAX, u32::MAX).is_err());
+        assert!(total_copies(65536, 65536).is_err());
+    }
+}
@@ -19,13 +19,39 @@ use std::collections::HashMap;
 mod windows;
 mod windows7;
 mod macos;
+mod cups;
+mod ipp;
+mod raw;
+mod lpd;
+mod bitmap;
+mod escpos;
+mod label;
+mod zpl;
+mod tspl;
+mod language;
+mod barcode;
+mod pdf417;
+mod pdf;
+mod render;
+mod destination;
+mod journal;
+mod dispatcher;
+mod retry;
+mod events;
+mod monitor;
 mod declare;
+mod settings;
 mod fsys;
 mod utils;
+mod command;
+mod timestamp;
 mod apikit;
 mod websocket;
 mod apm;
-use apm::is_windows_7_or_newer;
+mod backend;
+use backend::SharedBackend;
+use journal::SharedJournal;
+use dispatcher::{JobHandle, SharedDispatcher};
 
 // Define AppState 主要是要要给apm.rs 共享使用
 #[derive(Clone)]
@@ -34,15 +60,6 @@ pub struct AppState {
     pub start_time: u128, // Store the timestamp in milliseconds
 }
 
-#[tauri::command]
-fn remove_job(printername: String, jobid: String) -> String {
-    println!("main  remove_job");
-    if cfg!(windows) {
-        return windows::remove_job(printername, jobid);
-    }
-    "Unsupported OS".to_string()
-}
-
 #[tauri::command(rename_all = "snake_case")]
 fn create_temp_file(buffer_data: String, filename: String) -> String {
     println!("main create_temp_file");
@@ -65,152 +82,186 @@ fn remove_temp_file(filename: String) -> bool {
 
 
 #[tauri::command(rename_all = "snake_case")]
-fn get_printers() -> String {
-    println!("Initializing get_printers function.");
-
-    if cfg!(windows) {
-        println!("Running on Windows.");
-        unsafe {
-            if is_windows_7_or_newer() {
-                println!("Detected Windows 7 or newer.");
-                let result = windows::get_printers();
-                println!("Result from get_printers: {}", result); // Print result
-                return result;
-            } else {
-                println!("检测到 Win7 系统。");
-                let result = windows7::get_printers_win7();
-                println!("Result from get_printers_win7: {}", result); // Print result
-                return result;
-            }
-        }
-    } else {
-        println!("Running on macOS.");
-        let result = macos::get_printers_macos();
-        println!("Result from get_printers_macos: {}", result); // Print result
-        return result;
-    }
+fn get_printers(backend: State<'_, SharedBackend>) -> Result<Vec<declare::Printer>, String> {
+    println!("main get_printers");
+    let result = backend.get_printers();
+    println!("Result from get_printers: {:?}", result);
+    result
 }
 
-
 #[tauri::command]
-fn get_printers_by_name(printername: String) -> String {
+fn get_printers_by_name(printername: String, backend: State<'_, SharedBackend>) -> Result<Option<declare::Printer>, String> {
     println!("main get_printers_by_name");
-    if cfg!(windows) {
-        unsafe {
-            if is_windows_7_or_newer() {
-                println!("Detected Windows 7 or newer. get_printers_by_name ");
-                let result = windows::get_printers_by_name(printername);
-                println!("Result from get_printers_by_name above win7: {}", result); // Print result
-                return result;
-            } else {
-                println!("检测到 Win7 系统。 get_printers_by_name");
-                let result = windows7::get_printers_by_name_win7(printername);
-                println!("Result from get_printers_by_name win7: {}", result); // Print result
-                return result;
-            }
-        }
+    let result = backend.get_printer(printername);
+    println!("Result from get_printers_by_name: {:?}", result);
+    result
+}
+
+#[tauri::command]
+fn print_pdf(id: String, path: String, printer_setting: settings::PrintSettingInput, remove_after_print: bool, dispatcher: State<'_, SharedDispatcher>, backend: State<'_, SharedBackend>) -> Result<JobHandle, String> {
+    println!("main print_pdf");
+
+    let print_setting = printer_setting.into_settings().map_err(|err| format!("{}-打印失败: {}", backend.name(), err))?;
+    let unsupported = backend.unsupported_settings(&print_setting);
+    if !unsupported.is_empty() {
+        println!("{} 不支持的打印设置: {:?}", backend.name(), unsupported);
+    }
+
+    // 加入打印机队列后立即返回，打印结果通过 get_queued_job 查询
+    match dispatcher.submit(declare::Destination::Queue { id }, path, print_setting, remove_after_print) {
+        Ok(handle) => Ok(handle.with_message(format!("{}-已加入打印队列{}", backend.name(), settings::unsupported_note(&unsupported)))),
+        Err(err) => Err(format!("{}-打印失败: {}", backend.name(), err)),
     }
-    "Unsupported OS".to_string()
 }
 
+// 打印到指定目标：系统打印队列，或 RAW / LPD 网络打印机
+#[tauri::command(rename_all = "snake_case")]
+fn print_to_destination(destination: declare::Destination, path: String, printer_setting: settings::PrintSettingInput, remove_after_print: bool, dispatcher: State<'_, SharedDispatcher>, backend: State<'_, SharedBackend>) -> Result<JobHandle, String> {
+    println!("main print_to_destination: {:?}", destination);
+    let name = destination::destination_name(backend.inner().as_ref(), &destination);
+    let print_setting = printer_setting.into_settings().map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    let unsupported = destination::unsupported_settings(backend.inner().as_ref(), &destination, &print_setting);
+
+    match dispatcher.submit(destination, path, print_setting, remove_after_print) {
+        Ok(handle) => Ok(handle.with_message(format!("{}-已加入打印队列{}", name, settings::unsupported_note(&unsupported)))),
+        Err(err) => Err(format!("{}-打印失败: {}", name, err)),
+    }
+}
 
+// 生成 ESC/POS 小票指令并加入指定目标的打印队列
+#[tauri::command(rename_all = "snake_case")]
+fn print_escpos(destination: declare::Destination, receipt: escpos::Receipt, dispatcher: State<'_, SharedDispatcher>, backend: State<'_, SharedBackend>) -> Result<JobHandle, String> {
+    println!("main print_escpos: {:?}", destination);
+    let name = destination::destination_name(backend.inner().as_ref(), &destination);
+
+    let data = escpos::render_receipt(&receipt).map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    match submit_raw(&dispatcher, destination, &data, "receipt") {
+        Ok(handle) => Ok(handle.with_message(format!("{}-已加入打印队列", name))),
+        Err(err) => Err(format!("{}-打印失败: {}", name, err)),
+    }
+}
 
+// 按面单版式生成 ZPL 并加入指定目标的打印队列
+#[tauri::command(rename_all = "snake_case")]
+fn print_zpl(destination: declare::Destination, layout: label::LabelLayout, dispatcher: State<'_, SharedDispatcher>, backend: State<'_, SharedBackend>) -> Result<JobHandle, String> {
+    println!("main print_zpl: {:?}", destination);
+    let name = destination::destination_name(backend.inner().as_ref(), &destination);
+
+    let data = zpl::render_zpl(&layout).map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    match submit_raw(&dispatcher, destination, data.as_bytes(), "waybill.zpl") {
+        Ok(handle) => Ok(handle.with_message(format!("{}-已加入打印队列", name))),
+        Err(err) => Err(format!("{}-打印失败: {}", name, err)),
+    }
+}
 
-#[tauri::command]
-fn print_pdf(id: String, path: String, printer_setting: String, remove_after_print: bool) -> String {
-    println!("main print_pdf");
-    
-    let options = declare::PrintOptions {
-        id: id.clone(),
-        path: path.clone(),
-        print_setting: printer_setting.clone(),
-        remove_after_print,
-    };
-    
-    if cfg!(windows) {
-        unsafe {
-            if is_windows_7_or_newer() {
-            match windows::print_pdf(options) {
-                Ok(_) => "Windows-打印成功".to_string(),
-                Err(err) => format!("Windows-打印失败: {}", err),
-            }} else {
-            match windows7::print_pdf_win7(options) {
-                Ok(_) => "Windows7-打印成功".to_string(),
-                Err(err) => format!("Windows7-打印失败: {}", err),
-            }
-            } 
-        }
+// 按打印机选择的输出语言 (ZPL / TSPL) 生成面单并加入指定目标的打印队列
+#[tauri::command(rename_all = "snake_case")]
+fn print_label(destination: declare::Destination, layout: label::LabelLayout, languages: State<'_, language::OutputLanguages>, dispatcher: State<'_, SharedDispatcher>, backend: State<'_, SharedBackend>) -> Result<JobHandle, String> {
+    let output_language = languages.get(&destination.key());
+    println!("main print_label: {:?} {:?}", destination, output_language);
+    let name = destination::destination_name(backend.inner().as_ref(), &destination);
+
+    let data = language::render(output_language, &layout).map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    match submit_raw(&dispatcher, destination, &data, "waybill") {
+        Ok(handle) => Ok(handle.with_message(format!("{}-已加入打印队列", name))),
+        Err(err) => Err(format!("{}-打印失败: {}", name, err)),
+    }
+}
 
-        // return windows::print_pdf(options);
-    } else if cfg!(target_os = "macos") {
-        // macOS 处理逻辑
-        match macos::print_pdf_macos(id.clone(), path.clone(), printer_setting.clone(), remove_after_print) {
-            Ok(_) => "MacOS-打印成功".to_string(),
-            Err(err) => format!("MacOS-打印失败: {}", err),
+// 打印机指令先写入临时文件再加入打印队列，与 PDF 作业一样记录在作业日志中，发送后删除
+fn submit_raw(dispatcher: &dispatcher::Dispatcher, destination: declare::Destination, data: &[u8], document_name: &str) -> Result<JobHandle, String> {
+    let path = fsys::unique_temp_path("raw", "bin").display().to_string();
+    std::fs::write(&path, data).map_err(|err| format!("写入临时文件失败: {}", err))?;
+    let result = dispatcher.submit_raw(destination, path.clone(), document_name);
+    if result.is_err() {
+        if let Err(e) = fsys::remove_file(&path) {
+            println!("删除临时文件失败: {}", e);
         }
-    } else {
-        "Unsupported OS".to_string()
     }
+    result
 }
 
+// 把前端的 PrintData 渲染为矢量 PDF 并打印到指定目标
 #[tauri::command(rename_all = "snake_case")]
-fn get_jobs(printer_name: String) -> String {
-    println!("main get_jobs");
-    if cfg!(windows) {
-        unsafe {
-            if is_windows_7_or_newer() {
-                println!("Detected Windows 7 or newer. get_printers_by_name ");
-                let result = windows::get_jobs(printer_name);
-                println!("Result from get_jobs above win7: {}", result); // Print result
-                return result;
-            } else {
-                println!("检测到 Win7 系统。 get_printers_by_name");
-                let result = windows7::get_jobs_win7(printer_name);
-                println!("Result from get_jobs win7: {}", result); // Print result
-                return result;
-            }
+async fn print_data(destination: declare::Destination, data: Vec<render::PrintData>, page_size: render::PageSize, printer_setting: settings::PrintSettingInput, journal: State<'_, SharedJournal>, dispatcher: State<'_, SharedDispatcher>, backend: State<'_, SharedBackend>) -> Result<JobHandle, String> {
+    println!("main print_data: {:?}", destination);
+    let backend = backend.inner().clone();
+    let name = destination::destination_name(backend.as_ref(), &destination);
+    let print_setting = printer_setting.into_settings().map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    let unsupported = destination::unsupported_settings(backend.as_ref(), &destination, &print_setting);
+
+    // 先记录作业再生成 PDF，生成过程也能在作业状态中看到
+    let path = temp_pdf_path();
+    let job = journal.submit(destination, path.clone(), print_setting, true).map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    journal.transition(&job.id, journal::JobState::Rendering, "正在生成 PDF").map_err(|err| format!("{}-打印失败: {}", name, err))?;
+    if let Err(err) = render_to_file(&data, &page_size, &path).await {
+        if let Err(e) = journal.fail(&job.id, journal::JobState::Failed, &err) {
+            println!("更新打印作业状态失败: {}", e);
         }
-        
-    } else if cfg!(target_os = "macos") {
-        return macos::get_jobs_macos(&printer_name);
+        return Err(format!("{}-打印失败: {}", name, err));
+    }
+
+    match dispatcher.dispatch(job) {
+        Ok(handle) => Ok(handle.with_message(format!("{}-已加入打印队列{}", name, settings::unsupported_note(&unsupported)))),
+        Err(err) => Err(format!("{}-打印失败: {}", name, err)),
     }
-    "Unsupported OS".to_string()
 }
 
+// 渲染 PDF 到临时文件并返回路径，用于预览
 #[tauri::command(rename_all = "snake_case")]
-fn get_jobs_by_id(printername: String, jobid: String) -> String {
+async fn render_print_data(data: Vec<render::PrintData>, page_size: render::PageSize) -> Result<String, String> {
+    println!("main render_print_data");
+    let path = temp_pdf_path();
+    render_to_file(&data, &page_size, &path).await?;
+    Ok(path)
+}
+
+fn temp_pdf_path() -> String {
+    fsys::unique_temp_path("print_data", "pdf").display().to_string()
+}
+
+async fn render_to_file(data: &[render::PrintData], page_size: &render::PageSize, path: &str) -> Result<(), String> {
+    let remote = render::fetch_remote_images(data).await?;
+    let pdf = render::render_pdf(data, page_size, &remote)?;
+    std::fs::write(path, pdf).map_err(|err| format!("写入 PDF 失败: {}", err))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_jobs(printer_name: String, backend: State<'_, SharedBackend>) -> Result<Vec<declare::PrintJob>, String> {
+    println!("main get_jobs");
+    let result = backend.get_jobs(printer_name);
+    println!("Result from get_jobs: {:?}", result);
+    result
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_jobs_by_id(printername: String, jobid: String, backend: State<'_, SharedBackend>) -> Result<Option<declare::PrintJob>, String> {
     println!("main get_jobs_by_id");
-    if cfg!(windows) {
-        return windows::get_jobs_by_id(printername, jobid);
-    }
-    "Unsupported OS".to_string()
+    backend.get_job(printername, jobid)
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn resume_job(printername: String, jobid: String) -> String {
+fn resume_job(printername: String, jobid: String, backend: State<'_, SharedBackend>) -> Result<(), String> {
     println!("main resume_job");
-    if cfg!(windows) {
-        return windows::resume_job(printername, jobid);
-    }
-    "Unsupported OS".to_string()
+    backend.resume_job(printername, jobid)
 }
 
 #[tauri::command]
-fn restart_job(printername: String, jobid: String) -> String {
+fn restart_job(printername: String, jobid: String, backend: State<'_, SharedBackend>) -> Result<(), String> {
     println!("main restart_job");
-    if cfg!(target_os = "windows") {
-        return windows::windows_restart_job(printername, jobid);
-    }
-    "Unsupported OS".to_string()
+    backend.restart_job(printername, jobid)
 }
 
 #[tauri::command]
-fn pause_job(printername: String, jobid: String) -> String {
+fn pause_job(printername: String, jobid: String, backend: State<'_, SharedBackend>) -> Result<(), String> {
     println!("main pause_job");
-    if cfg!(windows) {
-        return windows::pause_job(printername, jobid);
-    }
-    "Unsupported OS".to_string()
+    backend.pause_job(printername, jobid)
+}
+
+#[tauri::command]
+fn remove_job(printername: String, jobid: String, backend: State<'_, SharedBackend>) -> Result<(), String> {
+    println!("main remove_job");
+    backend.remove_job(printername, jobid)
 }
 
 #[tauri::command]
@@ -255,12 +306,18 @@ async fn main() {
 
     // 为 WebSocket 管理克隆一个连接对象
     let ws_conn_for_manage = ws_conn.clone();
+    // 打印作业状态变化也通过 WebSocket 推送
+    let ws_conn_for_events = ws_conn.clone();
 
     // 创建应用程序状态，获取应用启动时间（以毫秒为单位）
     let start_time = SystemTime::now().duration_since(UNIX_EPOCH)
                      .expect("Time went backwards")
                      .as_millis();
 
+    // 启动时根据操作系统选定打印后端
+    let printer_backend: SharedBackend = backend::detect();
+    let backend_for_setup = printer_backend.clone();
+
     // 初始化应用程序状态
     let app_state = AppState {
         user_agent: Arc::new(Mutex::new(None)), // 存储用户代理信息
@@ -275,6 +332,34 @@ async fn main() {
                 // 获取应用程序的 handle，用于后续操作
                 let app_handle = app.handle();
 
+                // 加载每台打印机的输出语言配置
+                let data_dir = app.path_resolver().app_data_dir().unwrap_or_else(env::temp_dir);
+                app.manage(language::OutputLanguages::load(data_dir.join("output_languages.json")));
+
+                // 加载打印作业日志，继续打印上次退出前尚未提交的作业
+                let print_journal: SharedJournal = Arc::new(journal::JobJournal::open(data_dir.join("print_jobs.jsonl")));
+                let event_sink = events::EventSink::new(app_handle.clone(), ws_conn_for_events, tokio::runtime::Handle::current());
+                let job_events = event_sink.clone();
+                print_journal.set_listener(move |event| job_events.emit("job-state-changed", event));
+                let print_dispatcher: SharedDispatcher = Arc::new(dispatcher::Dispatcher::new(
+                    print_journal.clone(),
+                    backend_for_setup.clone(),
+                    data_dir.join("dispatcher.json"),
+                ));
+                print_dispatcher.resume();
+
+                // 监控有作业在打印的打印机队列，推送作业状态变化
+                let status_events = event_sink.clone();
+                monitor::JobMonitor::new(print_journal.clone(), backend_for_setup.clone())
+                    .start(move |event| status_events.emit("job-status-changed", event));
+
+                // 定期检查打印机状态，打印机脱机、缺纸等变化时推送
+                let printer_health: monitor::SharedPrinterHealth = Arc::new(monitor::PrinterHealth::new(backend_for_setup.clone()));
+                printer_health.clone().start(move |event| event_sink.emit("printer-state-changed", event));
+                app.manage(printer_health);
+                app.manage(print_journal);
+                app.manage(print_dispatcher);
+
                 // 异步启动 WebSocket 服务器
                 tokio::spawn({
                     let app_handle_clone = app_handle.clone();
@@ -290,6 +375,8 @@ async fn main() {
         .manage(ws_conn_for_manage)
         // 管理刚初始化的 AppState 实例
         .manage(app_state.clone())
+        // 管理打印后端
+        .manage(printer_backend)
         // 处理用户操作调用的命令
         .invoke_handler(tauri::generate_handler![
             apm::report_custom_measurement,
@@ -299,7 +386,23 @@ async fn main() {
             get_printers,
             get_printers_by_name,
             print_pdf,
-            macos::print_pdf_macos,
+            print_to_destination,
+            print_escpos,
+            print_zpl,
+            print_label,
+            print_data,
+            render_print_data,
+            journal::get_print_queue,
+            journal::get_queued_job,
+            dispatcher::get_dead_letters,
+            dispatcher::retry_dead_letter,
+            dispatcher::discard_dead_letter,
+            dispatcher::cancel_queued_job,
+            monitor::get_printer_states,
+            dispatcher::get_print_concurrency,
+            dispatcher::set_print_concurrency,
+            language::get_output_language,
+            language::set_output_language,
             get_jobs,
             get_jobs_by_id,
             resume_job,
@@ -307,10 +410,20 @@ async fn main() {
             remove_job,
             fetch_image,
             restart_job,
+            ipp::ipp_get_printer,
+            ipp::ipp_validate_job,
+            ipp::ipp_print,
+            ipp::ipp_get_jobs,
+            ipp::ipp_get_job,
+            ipp::ipp_cancel_job,
+            ipp::ipp_hold_job,
+            ipp::ipp_release_job,
+            lpd::lpd_get_printer,
+            lpd::lpd_get_jobs,
+            lpd::lpd_queue_state,
             websocket::check_websocket_connection,
             websocket::send_message_to_websocket,
             open_file,
-            macos::get_jobs_macos,
             utils::get_version_from_config,
             apikit::send_request_command,
         ])
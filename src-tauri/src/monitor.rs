@@ -0,0 +1,666 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::State;
+use crate::backend::SharedBackend;
+use crate::declare::{Destination, JobStatus, PrintJob, Printer, PrinterState};
+use crate::journal::{now_millis, JobRecord, JobState, SharedJournal};
+
+// 检查日志中是否有新作业进入打印队列的间隔，只读内存中的记录
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// 轮询打印队列的间隔：作业刚提交或状态有变化时最短，之后没有变化就逐步加倍
+// Windows 上每台打印机每次轮询都要启动一个 PowerShell 进程
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(16);
+// 检查打印机状态的间隔，Windows 上每次都要启动 PowerShell，不宜太频繁
+// 获取失败时（打印服务未启动、没有权限等）逐步加倍，恢复后回到正常间隔
+const HEALTH_INTERVAL: Duration = Duration::from_secs(15);
+const MAX_HEALTH_INTERVAL: Duration = Duration::from_secs(300);
+// 提交后拿不到打印队列作业 ID 的作业无法跟踪，超过这个时间后结束跟踪
+const UNTRACKED_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+// 推送给前端的作业状态，由 Windows JobStatus 位标志归纳而来
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPhase {
+    Spooling,
+    Printing,
+    Paused,
+    Error,
+    PaperOut,
+    Completed,
+    Deleted,
+}
+
+impl JobPhase {
+    // 多个标志位同时置位时，缺纸和错误优先于打印中
+    pub fn from_status(status: Option<&JobStatus>) -> Self {
+        let status = match status {
+            Some(status) => status,
+            None => return JobPhase::Spooling,
+        };
+        if status.has(64) {
+            JobPhase::PaperOut
+        } else if status.has(2 | 32 | 512 | 1024) {
+            JobPhase::Error
+        } else if status.has(4 | 256) {
+            JobPhase::Deleted
+        } else if status.has(128 | 4096) {
+            JobPhase::Completed
+        } else if status.has(16) {
+            JobPhase::Printing
+        } else if status.has(1) {
+            JobPhase::Paused
+        } else {
+            JobPhase::Spooling
+        }
+    }
+}
+
+// 推送给前端的 job-status-changed 事件
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatusChanged {
+    pub printer_name: String,
+    // 打印队列中的作业 ID
+    pub job_id: String,
+    // 本程序提交的作业对应的日志 ID
+    pub print_job_id: Option<String>,
+    pub previous: Option<JobPhase>,
+    pub status: JobPhase,
+    pub job_status: Option<JobStatus>,
+    pub at: u64,
+}
+
+// CUPS 的 lp 返回 "打印机名称-123"，lpstat 解析出的作业 ID 可能只有数字部分
+fn same_job(spooler_job_id: &str, job_id: &str) -> bool {
+    spooler_job_id == job_id || matches!(spooler_job_id.rsplit_once('-'), Some((_, id)) if id == job_id)
+}
+
+// 已进入打印队列、需要跟踪的作业
+fn is_watched(job: &JobRecord) -> bool {
+    matches!(job.destination, Destination::Queue { .. })
+        && matches!(job.state, JobState::Spooled | JobState::Printing)
+        && job.spooler_job_id.is_some()
+}
+
+// 已进入打印队列，但提交时没有拿到作业 ID，无法在打印队列中找到
+fn is_untracked(job: &JobRecord) -> bool {
+    matches!(job.destination, Destination::Queue { .. }) && job.state == JobState::Spooled && job.spooler_job_id.is_none()
+}
+
+// 下一次轮询打印队列的间隔
+fn next_poll_interval(current: Duration, changed: bool) -> Duration {
+    if changed {
+        MIN_POLL_INTERVAL
+    } else {
+        (current * 2).min(MAX_POLL_INTERVAL)
+    }
+}
+
+// 下一次检查打印机状态的间隔
+fn next_health_interval(current: Duration, failed: bool) -> Duration {
+    if failed {
+        (current * 2).min(MAX_HEALTH_INTERVAL)
+    } else {
+        HEALTH_INTERVAL
+    }
+}
+
+fn printer_of(job: &JobRecord) -> Option<String> {
+    match &job.destination {
+        Destination::Queue { id } => Some(id.trim().trim_matches('"').to_string()),
+        _ => None,
+    }
+}
+
+// 打印作业监控：只轮询有本程序作业在打印的打印机，与上一次的队列快照比较
+// 作业状态变化时推送 job-status-changed，并推进日志中作业的生命周期
+pub struct JobMonitor {
+    journal: SharedJournal,
+    backend: SharedBackend,
+    // 打印机名称 -> (作业 ID -> 上一次的状态)
+    snapshots: HashMap<String, HashMap<String, JobPhase>>,
+}
+
+impl JobMonitor {
+    pub fn new(journal: SharedJournal, backend: SharedBackend) -> Self {
+        Self {
+            journal,
+            backend,
+            snapshots: HashMap::new(),
+        }
+    }
+
+    // 在后台线程中持续轮询，每个变化调用一次 listener
+    // 没有需要跟踪的作业时不查询打印队列；有新作业进入打印队列时立即按最短间隔轮询
+    pub fn start<F: Fn(JobStatusChanged) + Send + 'static>(mut self, listener: F) {
+        thread::spawn(move || {
+            let mut seen: HashSet<String> = HashSet::new();
+            let mut interval = MIN_POLL_INTERVAL;
+            let mut next_poll = Instant::now();
+            loop {
+                self.expire_untracked(now_millis());
+                let watched: HashSet<String> = self.watched().into_iter().map(|job| job.id).collect();
+                if !watched.is_subset(&seen) {
+                    interval = MIN_POLL_INTERVAL;
+                    next_poll = Instant::now();
+                }
+                seen = watched;
+                if seen.is_empty() {
+                    // 队列快照只在连续轮询之间有意义
+                    self.snapshots.clear();
+                }
+
+                if !seen.is_empty() && Instant::now() >= next_poll {
+                    let events = self.poll();
+                    interval = next_poll_interval(interval, !events.is_empty());
+                    next_poll = Instant::now() + interval;
+                    for event in events {
+                        listener(event);
+                    }
+                }
+                thread::sleep(CHECK_INTERVAL);
+            }
+        });
+    }
+
+    fn watched(&self) -> Vec<JobRecord> {
+        self.journal.list().into_iter().filter(is_watched).collect()
+    }
+
+    // 没有作业 ID 的作业无法确认打印结果，进入打印队列超过 UNTRACKED_TIMEOUT 后结束，日志中注明最终状态未知
+    pub fn expire_untracked(&self, now: u64) {
+        let timeout = UNTRACKED_TIMEOUT.as_millis() as u64;
+        for job in self.journal.list().into_iter().filter(is_untracked) {
+            if now.saturating_sub(job.updated_at) < timeout {
+                continue;
+            }
+            if let Err(e) = self.journal.transition(&job.id, JobState::Completed, "没有打印队列作业 ID，无法跟踪，最终状态未知") {
+                println!("更新打印作业状态失败: {}", e);
+            }
+        }
+    }
+
+    // 离开打印队列的作业只有在后端确认打印完成时才视为完成，否则视为已删除
+    // Windows 打印队列和 lpstat 查不到已经离开队列的作业，IPP 可以查到已完成或已取消的作业
+    fn final_phase(&self, printer: &str, job_id: &str) -> JobPhase {
+        match self.backend.get_job(printer.to_string(), job_id.to_string()) {
+            Ok(Some(job)) if JobPhase::from_status(job.job_status.as_ref()) == JobPhase::Completed => JobPhase::Completed,
+            Ok(_) => JobPhase::Deleted,
+            Err(e) => {
+                println!("无法确认作业 {} 是否打印完成: {}", job_id, e);
+                JobPhase::Deleted
+            }
+        }
+    }
+
+    // 轮询一次，返回发生变化的作业
+    pub fn poll(&mut self) -> Vec<JobStatusChanged> {
+        let watched = self.watched();
+        let printers: HashSet<String> = watched.iter().filter_map(printer_of).collect();
+        // 不再有作业需要跟踪的打印机不再轮询
+        self.snapshots.retain(|printer, _| printers.contains(printer));
+
+        let mut events = Vec::new();
+        for printer in printers {
+            let jobs = match self.backend.get_jobs(printer.clone()) {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    println!("获取打印作业失败: {} {}", printer, e);
+                    continue;
+                }
+            };
+            let owned: Vec<&JobRecord> = watched.iter().filter(|job| printer_of(job).as_deref() == Some(printer.as_str())).collect();
+            events.extend(self.diff(&printer, &jobs, &owned));
+        }
+        events
+    }
+
+    fn diff(&mut self, printer: &str, jobs: &[PrintJob], owned: &[&JobRecord]) -> Vec<JobStatusChanged> {
+        let previous = self.snapshots.remove(printer).unwrap_or_default();
+        let mut current = HashMap::new();
+        let mut events = Vec::new();
+        let owner = |job_id: &str| {
+            owned
+                .iter()
+                .find(|job| matches!(job.spooler_job_id.as_deref(), Some(id) if same_job(id, job_id)))
+                .map(|job| job.id.clone())
+        };
+
+        for job in jobs {
+            let status = JobPhase::from_status(job.job_status.as_ref());
+            let before = previous.get(&job.job_id).copied();
+            current.insert(job.job_id.clone(), status);
+            if before == Some(status) {
+                continue;
+            }
+            let print_job_id = owner(&job.job_id);
+            if let Some(id) = &print_job_id {
+                self.advance(id, status);
+            }
+            events.push(JobStatusChanged {
+                printer_name: printer.to_string(),
+                job_id: job.job_id.clone(),
+                print_job_id,
+                previous: before,
+                status,
+                job_status: job.job_status.clone(),
+                at: now_millis(),
+            });
+        }
+
+        // 离开队列的作业：离开前已完成或删除中的沿用该状态，其余向后端确认
+        for (job_id, before) in previous {
+            if current.contains_key(&job_id) {
+                continue;
+            }
+            let status = match before {
+                JobPhase::Completed | JobPhase::Deleted => before,
+                _ => self.final_phase(printer, &job_id),
+            };
+            let print_job_id = owner(&job_id);
+            if let Some(id) = &print_job_id {
+                self.advance(id, status);
+            }
+            events.push(JobStatusChanged {
+                printer_name: printer.to_string(),
+                job_id,
+                print_job_id,
+                previous: Some(before),
+                status,
+                job_status: None,
+                at: now_millis(),
+            });
+        }
+
+        // 提交后还没来得及看到就已经离开队列的作业
+        for job in owned {
+            let spooler_job_id = job.spooler_job_id.as_deref().unwrap_or_default();
+            if jobs.iter().any(|item| same_job(spooler_job_id, &item.job_id)) {
+                continue;
+            }
+            if !matches!(self.journal.get(&job.id), Some(job) if is_watched(&job)) {
+                continue;
+            }
+            let status = self.final_phase(printer, spooler_job_id);
+            self.advance(&job.id, status);
+            events.push(JobStatusChanged {
+                printer_name: printer.to_string(),
+                job_id: spooler_job_id.to_string(),
+                print_job_id: Some(job.id.clone()),
+                previous: None,
+                status,
+                job_status: None,
+                at: now_millis(),
+            });
+        }
+
+        self.snapshots.insert(printer.to_string(), current);
+        events
+    }
+
+    // 按打印队列中的状态推进日志中的作业，错误和缺纸只推送事件，作业仍在队列中
+    fn advance(&self, id: &str, status: JobPhase) {
+        let state = match self.journal.get(id) {
+            Some(job) => job.state,
+            None => return,
+        };
+        let (next, reason) = match status {
+            JobPhase::Printing => (JobState::Printing, "正在打印"),
+            JobPhase::Completed => (JobState::Completed, "打印完成"),
+            JobPhase::Deleted => (JobState::Cancelled, "已从打印队列删除"),
+            _ => return,
+        };
+        if state == next || !state.can_transition(next) {
+            return;
+        }
+        if let Err(e) = self.journal.transition(id, next, reason) {
+            println!("更新打印作业状态失败: {}", e);
+        }
+    }
+}
+
+// 推送给前端的 printer-state-changed 事件
+#[derive(Debug, Clone, Serialize)]
+pub struct PrinterStateChanged {
+    pub printer_name: String,
+    pub previous: Option<PrinterState>,
+    pub state: PrinterState,
+    pub at: u64,
+}
+
+pub type SharedPrinterHealth = Arc<PrinterHealth>;
+
+// 打印机健康检查：定期获取全部打印机的状态，状态或原因变化时推送 printer-state-changed
+// 批量打印前就能知道打印机脱机、缺纸或开盖
+pub struct PrinterHealth {
+    backend: SharedBackend,
+    // 打印机名称 -> 最近一次的状态
+    states: Mutex<HashMap<String, PrinterState>>,
+}
+
+impl PrinterHealth {
+    pub fn new(backend: SharedBackend) -> Self {
+        Self {
+            backend,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn start<F: Fn(PrinterStateChanged) + Send + 'static>(self: Arc<Self>, listener: F) {
+        thread::spawn(move || {
+            let mut interval = HEALTH_INTERVAL;
+            loop {
+                let result = self.backend.get_printers();
+                interval = next_health_interval(interval, result.is_err());
+                match result {
+                    Ok(printers) => {
+                        for event in self.update(&printers) {
+                            listener(event);
+                        }
+                    }
+                    Err(e) => println!("检查打印机状态失败，{} 秒后重试: {}", interval.as_secs(), e),
+                }
+                thread::sleep(interval);
+            }
+        });
+    }
+
+    // 与上一次的状态比较，返回发生变化的打印机；已经不存在的打印机不再记录
+    pub fn update(&self, printers: &[Printer]) -> Vec<PrinterStateChanged> {
+        let mut states = self.states.lock();
+        let mut current = HashMap::new();
+        let mut events = Vec::new();
+
+        for printer in printers {
+            let previous = states.get(&printer.name).cloned();
+            if previous.as_ref() != Some(&printer.state) {
+                events.push(PrinterStateChanged {
+                    printer_name: printer.name.clone(),
+                    previous,
+                    state: printer.state.clone(),
+                    at: now_millis(),
+                });
+            }
+            current.insert(printer.name.clone(), printer.state.clone());
+        }
+
+        *states = current;
+        events
+    }
+
+    pub fn states(&self) -> HashMap<String, PrinterState> {
+        self.states.lock().clone()
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_printer_states(health: State<'_, SharedPrinterHealth>) -> HashMap<String, PrinterState> {
+    health.states()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::backend::PrinterBackend;
+    use crate::declare::{PrintOptions, PrinterReason, PrinterStatus};
+    use crate::fsys::unique_temp_path;
+    use crate::journal::JobJournal;
+    use crate::settings::PrintSettings;
+
+    // 返回固定打印队列的后端，记录查询过哪些打印机
+    #[derive(Default)]
+    struct QueueBackend {
+        jobs: Mutex<Vec<PrintJob>>,
+        // 已离开队列、仍可按 ID 查到的作业
+        finished: Mutex<Vec<PrintJob>>,
+        polled: Mutex<Vec<String>>,
+    }
+
+    impl PrinterBackend for QueueBackend {
+        fn name(&self) -> &'static str {
+            "Test"
+        }
+
+        fn get_printers(&self) -> Result<Vec<Printer>, String> {
+            Ok(Vec::new())
+        }
+
+        fn get_printer(&self, _printer_name: String) -> Result<Option<Printer>, String> {
+            Ok(None)
+        }
+
+        fn print_pdf(&self, _options: PrintOptions) -> Result<String, String> {
+            Ok(String::new())
+        }
+
+        fn get_jobs(&self, printer_name: String) -> Result<Vec<PrintJob>, String> {
+            self.polled.lock().push(printer_name.clone());
+            Ok(self.jobs.lock().iter().filter(|job| job.printer_name == printer_name).cloned().collect())
+        }
+
+        fn get_job(&self, printer_name: String, job_id: String) -> Result<Option<PrintJob>, String> {
+            let jobs = self.jobs.lock().iter().chain(self.finished.lock().iter()).cloned().collect::<Vec<_>>();
+            Ok(jobs.into_iter().find(|job| job.printer_name == printer_name && same_job(&job_id, &job.job_id)))
+        }
+    }
+
+    fn monitor() -> (JobMonitor, Arc<QueueBackend>) {
+        let dir = unique_temp_path("monitor_test", "d");
+        fs::create_dir_all(&dir).unwrap();
+        let journal = Arc::new(JobJournal::open(dir.join("print_jobs.jsonl")));
+        let backend = Arc::new(QueueBackend::default());
+        (JobMonitor::new(journal, backend.clone()), backend)
+    }
+
+    // 在日志中记录一个已进入打印队列的作业
+    fn spooled(monitor: &JobMonitor, printer: &str, spooler_job_id: &str) -> String {
+        let journal = &monitor.journal;
+        let job = journal
+            .submit(Destination::Queue { id: printer.to_string() }, "a.pdf".to_string(), PrintSettings::default(), false)
+            .unwrap();
+        journal.start_attempt(&job.id).unwrap();
+        journal.spooled(&job.id, Some(spooler_job_id.to_string())).unwrap();
+        job.id
+    }
+
+    fn queued(printer: &str, job_id: &str, code: u32) -> PrintJob {
+        PrintJob {
+            job_status: Some(JobStatus::from_code(code)),
+            ..PrintJob::new(printer, job_id)
+        }
+    }
+
+    fn phases(events: &[JobStatusChanged]) -> Vec<(&str, Option<JobPhase>, JobPhase)> {
+        events.iter().map(|event| (event.job_id.as_str(), event.previous, event.status)).collect()
+    }
+
+    fn state(monitor: &JobMonitor, id: &str) -> JobState {
+        monitor.journal.get(id).unwrap().state
+    }
+
+    #[test]
+    fn phase_from_status_bits() {
+        let phase = |code: u32| JobPhase::from_status(Some(&JobStatus::from_code(code)));
+        assert_eq!(JobPhase::from_status(None), JobPhase::Spooling);
+        assert_eq!(phase(0), JobPhase::Spooling);
+        assert_eq!(phase(8), JobPhase::Spooling);
+        assert_eq!(phase(16), JobPhase::Printing);
+        assert_eq!(phase(1), JobPhase::Paused);
+        assert_eq!(phase(4), JobPhase::Deleted);
+        assert_eq!(phase(256), JobPhase::Deleted);
+        assert_eq!(phase(128), JobPhase::Completed);
+        assert_eq!(phase(4096), JobPhase::Completed);
+        assert_eq!(phase(2), JobPhase::Error);
+        assert_eq!(phase(1024), JobPhase::Error);
+        // 缺纸和错误优先于打印中
+        assert_eq!(phase(16 | 64), JobPhase::PaperOut);
+        assert_eq!(phase(16 | 32), JobPhase::Error);
+        assert_eq!(phase(16 | 4096), JobPhase::Completed);
+    }
+
+    #[test]
+    fn diff_follows_job_through_queue() {
+        let (mut monitor, backend) = monitor();
+        let id = spooled(&monitor, "P", "P-7");
+
+        // 第一次看到的作业都推送，其他程序提交的作业没有日志 ID
+        let events = monitor.diff("P", &[queued("P", "7", 16), queued("P", "9", 8)], &[&monitor.journal.get(&id).unwrap()]);
+        assert_eq!(phases(&events), vec![("7", None, JobPhase::Printing), ("9", None, JobPhase::Spooling)]);
+        assert_eq!(events[0].print_job_id.as_deref(), Some(id.as_str()));
+        assert_eq!(events[1].print_job_id, None);
+        assert_eq!(state(&monitor, &id), JobState::Printing);
+
+        // 状态没有变化不推送
+        let owned = monitor.journal.get(&id).unwrap();
+        assert!(monitor.diff("P", &[queued("P", "7", 16), queued("P", "9", 8)], &[&owned]).is_empty());
+
+        // 后端确认打印完成的作业离开队列后视为完成
+        backend.finished.lock().push(queued("P", "7", 4096));
+        let events = monitor.diff("P", &[queued("P", "9", 4)], &[&owned]);
+        assert_eq!(phases(&events), vec![("9", Some(JobPhase::Spooling), JobPhase::Deleted), ("7", Some(JobPhase::Printing), JobPhase::Completed)]);
+        assert_eq!(state(&monitor, &id), JobState::Completed);
+
+        // 删除中的作业离开队列视为已删除
+        let events = monitor.diff("P", &[], &[]);
+        assert_eq!(phases(&events), vec![("9", Some(JobPhase::Deleted), JobPhase::Deleted)]);
+    }
+
+    #[test]
+    fn deleted_job_is_cancelled() {
+        let (mut monitor, _) = monitor();
+        let id = spooled(&monitor, "P", "3");
+        let owned = monitor.journal.get(&id).unwrap();
+
+        monitor.diff("P", &[queued("P", "3", 4)], &[&owned]);
+        assert_eq!(state(&monitor, &id), JobState::Cancelled);
+    }
+
+    #[test]
+    fn job_leaving_queue_unconfirmed_is_deleted() {
+        let (mut monitor, _) = monitor();
+        let id = spooled(&monitor, "P", "P-7");
+        let owned = monitor.journal.get(&id).unwrap();
+
+        // 在打印中离开队列，但后端查不到结果，可能是在本程序之外被取消
+        monitor.diff("P", &[queued("P", "7", 16)], &[&owned]);
+        let owned = monitor.journal.get(&id).unwrap();
+        let events = monitor.diff("P", &[], &[&owned]);
+        assert_eq!(phases(&events), vec![("7", Some(JobPhase::Printing), JobPhase::Deleted)]);
+        assert_eq!(state(&monitor, &id), JobState::Cancelled);
+    }
+
+    #[test]
+    fn job_gone_before_first_poll_needs_confirmation() {
+        let (mut monitor, backend) = monitor();
+        let printed = spooled(&monitor, "P", "P-5");
+        let removed = spooled(&monitor, "P", "P-6");
+        backend.finished.lock().push(queued("P", "5", 4096));
+        let owned = [monitor.journal.get(&printed).unwrap(), monitor.journal.get(&removed).unwrap()];
+
+        let events = monitor.diff("P", &[], &[&owned[0], &owned[1]]);
+        assert_eq!(phases(&events), vec![("P-5", None, JobPhase::Completed), ("P-6", None, JobPhase::Deleted)]);
+        assert_eq!(events[1].print_job_id.as_deref(), Some(removed.as_str()));
+        assert_eq!(state(&monitor, &printed), JobState::Completed);
+        assert_eq!(state(&monitor, &removed), JobState::Cancelled);
+    }
+
+    #[test]
+    fn untracked_job_times_out() {
+        let (monitor, _) = monitor();
+        let journal = &monitor.journal;
+        let job = journal
+            .submit(Destination::Queue { id: "P".to_string() }, "a.pdf".to_string(), PrintSettings::default(), false)
+            .unwrap();
+        journal.start_attempt(&job.id).unwrap();
+        let spooled_at = journal.spooled(&job.id, None).unwrap().updated_at;
+        assert!(!is_watched(&journal.get(&job.id).unwrap()));
+
+        monitor.expire_untracked(spooled_at + UNTRACKED_TIMEOUT.as_millis() as u64 - 1);
+        assert_eq!(state(&monitor, &job.id), JobState::Spooled);
+        monitor.expire_untracked(spooled_at + UNTRACKED_TIMEOUT.as_millis() as u64);
+        assert_eq!(state(&monitor, &job.id), JobState::Completed);
+    }
+
+    #[test]
+    fn only_printers_with_spooled_jobs_are_polled() {
+        let (mut monitor, backend) = monitor();
+        assert!(monitor.poll().is_empty());
+        assert!(backend.polled.lock().is_empty());
+
+        // 还没有进入打印队列的作业不需要轮询
+        monitor
+            .journal
+            .submit(Destination::Queue { id: "Idle".to_string() }, "b.pdf".to_string(), PrintSettings::default(), false)
+            .unwrap();
+        let id = spooled(&monitor, "P", "1");
+        backend.jobs.lock().push(queued("P", "1", 16));
+        assert_eq!(phases(&monitor.poll()), vec![("1", None, JobPhase::Printing)]);
+        assert_eq!(*backend.polled.lock(), vec!["P"]);
+
+        let printed: Vec<PrintJob> = backend.jobs.lock().drain(..).collect();
+        backend.finished.lock().extend(printed.into_iter().map(|job| PrintJob { job_status: Some(JobStatus::from_code(4096)), ..job }));
+        monitor.poll();
+        assert_eq!(state(&monitor, &id), JobState::Completed);
+        backend.polled.lock().clear();
+        monitor.poll();
+        assert!(backend.polled.lock().is_empty());
+    }
+
+    #[test]
+    fn health_update_reports_changes_only() {
+        let health = PrinterHealth::new(Arc::new(QueueBackend::default()));
+        let printer = |name: &str, status: u32| Printer {
+            state: PrinterState::from_windows_status(status),
+            ..Printer::new(name)
+        };
+
+        let events = health.update(&[printer("A", 0), printer("B", 8)]);
+        let changes: Vec<(&str, bool)> = events.iter().map(|event| (event.printer_name.as_str(), event.previous.is_some())).collect();
+        assert_eq!(changes, vec![("A", false), ("B", false)]);
+        assert_eq!(health.states()["B"].status, PrinterStatus::Offline);
+
+        assert!(health.update(&[printer("A", 0), printer("B", 8)]).is_empty());
+
+        // 原因变化也推送，即使整体状态相同
+        let events = health.update(&[printer("A", 5), printer("B", 8)]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].previous.as_ref().unwrap().status, PrinterStatus::Idle);
+        assert_eq!(events[0].state.reasons, vec![PrinterReason::PaperOut]);
+
+        // 已经不存在的打印机不再记录，再次出现时当作新打印机
+        health.update(&[printer("A", 5)]);
+        assert_eq!(health.states().len(), 1);
+        let events = health.update(&[printer("A", 5), printer("B", 8)]);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].previous.is_none());
+    }
+
+    #[test]
+    fn health_check_backs_off_on_errors() {
+        let mut interval = HEALTH_INTERVAL;
+        let mut intervals = Vec::new();
+        for _ in 0..6 {
+            interval = next_health_interval(interval, true);
+            intervals.push(interval.as_secs());
+        }
+        assert_eq!(intervals, vec![30, 60, 120, 240, 300, 300]);
+        assert_eq!(next_health_interval(interval, false), HEALTH_INTERVAL);
+    }
+
+    #[test]
+    fn poll_interval_backs_off_until_something_changes() {
+        let mut interval = MIN_POLL_INTERVAL;
+        let mut intervals = Vec::new();
+        for _ in 0..5 {
+            interval = next_poll_interval(interval, false);
+            intervals.push(interval.as_secs());
+        }
+        assert_eq!(intervals, vec![4, 8, 16, 16, 16]);
+        assert_eq!(next_poll_interval(MAX_POLL_INTERVAL, true), MIN_POLL_INTERVAL);
+    }
+}
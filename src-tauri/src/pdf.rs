@@ -0,0 +1,243 @@
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+// 最小的 PDF 写入器：只包含渲染面单所需的页面、矢量图形、中文字体与 RGB 图片
+// 中文使用 PDF 阅读器内置的 STSong-Light (Adobe-GB1)，不需要嵌入字体文件
+
+pub struct PdfImage {
+    pub width: u32,
+    pub height: u32,
+    // 8 位 RGB 像素
+    pub rgb: Vec<u8>,
+}
+
+pub struct PdfPage {
+    // 页面尺寸 (pt)
+    pub width: f32,
+    pub height: f32,
+    // 内容流中的绘图指令
+    pub content: String,
+}
+
+#[derive(Default)]
+pub struct PdfDocument {
+    pub pages: Vec<PdfPage>,
+    pub images: Vec<PdfImage>,
+}
+
+impl PdfDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 添加图片，返回在内容流中引用的名称 /Im{index}
+    pub fn add_image(&mut self, image: PdfImage) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // 对象编号：1 目录，2 页面树，3-5 字体，随后是图片，最后每页两个对象 (页面、内容流)
+        let font_id = 3;
+        let first_image_id = 6;
+        let first_page_id = first_image_id + self.images.len();
+
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        let kids: Vec<String> = (0..self.pages.len())
+            .map(|index| format!("{} 0 R", first_page_id + index * 2))
+            .collect();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len()).into_bytes());
+        objects.push(
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UCS2-H /DescendantFonts [4 0 R] >>".to_vec(),
+        );
+        // CID 1-95 为 ASCII，按半角宽度排版
+        objects.push(
+            b"<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light \
+/CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 2 >> \
+/FontDescriptor 5 0 R /DW 1000 /W [1 95 500] >>"
+                .to_vec(),
+        );
+        objects.push(
+            b"<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 /FontBBox [-25 -254 1000 880] \
+/ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>"
+                .to_vec(),
+        );
+
+        for image in &self.images {
+            let data = compress_to_vec_zlib(&image.rgb, 6);
+            objects.push(stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode",
+                    image.width, image.height
+                ),
+                &data,
+            ));
+        }
+
+        let image_resources: String = (0..self.images.len())
+            .map(|index| format!("/Im{} {} 0 R", index, first_image_id + index))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        for (index, page) in self.pages.iter().enumerate() {
+            let content_id = first_page_id + index * 2 + 1;
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R >> /XObject << {} >> >> /Contents {} 0 R >>",
+                    number(page.width),
+                    number(page.height),
+                    font_id,
+                    image_resources,
+                    content_id
+                )
+                .into_bytes(),
+            );
+            objects.push(stream("/Filter /FlateDecode", &compress_to_vec_zlib(page.content.as_bytes(), 6)));
+        }
+
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_offset).as_bytes(),
+        );
+        out
+    }
+}
+
+fn stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut out = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\nendstream");
+    out
+}
+
+// 数字保留两位小数并去掉多余的 0
+pub fn number(value: f32) -> String {
+    let formatted = format!("{:.2}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" || trimmed.is_empty() {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+// UniGB-UCS2-H 使用 UCS-2 大端编码，BMP 以外的字符替换为问号
+pub fn encode_text(text: &str) -> String {
+    let mut hex = String::with_capacity(text.len() * 4 + 2);
+    hex.push('<');
+    for c in text.chars() {
+        let code = if (c as u32) <= 0xFFFF { c as u32 } else { '?' as u32 };
+        hex.push_str(&format!("{:04X}", code));
+    }
+    hex.push('>');
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).rposition(|window| window == needle)
+    }
+
+    fn line_at(pdf: &[u8], offset: usize) -> &str {
+        let end = pdf[offset..].iter().position(|b| *b == b'\n').map_or(pdf.len(), |n| offset + n);
+        std::str::from_utf8(&pdf[offset..end]).unwrap()
+    }
+
+    // 按 startxref 找到交叉引用表，检查每个偏移都指向对应的对象，返回对象数 (不含 0 号)
+    fn check_xref(pdf: &[u8]) -> usize {
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let startxref = find(pdf, b"startxref\n").unwrap();
+        let xref: usize = line_at(pdf, startxref + "startxref\n".len()).parse().unwrap();
+        assert_eq!(line_at(pdf, xref), "xref");
+
+        let mut offset = xref + "xref\n".len();
+        let header = line_at(pdf, offset);
+        assert!(header.starts_with("0 "));
+        let size: usize = header[2..].parse().unwrap();
+        offset += header.len() + 1;
+        assert_eq!(line_at(pdf, offset), "0000000000 65535 f ");
+        for id in 1..size {
+            // 每一项固定 20 字节
+            let entry = &pdf[offset + id * 20..offset + (id + 1) * 20];
+            let entry = std::str::from_utf8(entry).unwrap();
+            assert!(entry.ends_with(" 00000 n \n"), "{:?}", entry);
+            let object: usize = entry[..10].parse().unwrap();
+            assert_eq!(line_at(pdf, object), format!("{} 0 obj", id));
+        }
+        let trailer = find(pdf, b"trailer\n").unwrap();
+        assert_eq!(trailer, offset + size * 20);
+        assert_eq!(line_at(pdf, trailer + "trailer\n".len()), format!("<< /Size {} /Root 1 0 R >>", size));
+        size - 1
+    }
+
+    fn page(content: &str) -> PdfPage {
+        PdfPage {
+            width: 215.43,
+            height: 368.5,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn xref_points_at_every_object() {
+        let mut document = PdfDocument::new();
+        let index = document.add_image(PdfImage {
+            width: 2,
+            height: 1,
+            rgb: vec![0, 0, 0, 255, 255, 255],
+        });
+        assert_eq!(index, 0);
+        document.pages.push(page("0 0 10 10 re f\n"));
+        document.pages.push(page(&format!("BT /F1 12 Tf 0 0 Td {} Tj ET\n", encode_text("中文"))));
+
+        let pdf = document.to_bytes();
+        // 目录、页面树、3 个字体对象、1 张图片、每页 2 个对象
+        assert_eq!(check_xref(&pdf), 5 + 1 + 2 * 2);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("<< /Type /Pages /Kids [7 0 R 9 0 R] /Count 2 >>"));
+        assert!(text.contains("/MediaBox [0 0 215.43 368.5] /Resources << /Font << /F1 3 0 R >> /XObject << /Im0 6 0 R >> >> /Contents 8 0 R"));
+        assert!(text.contains("/Width 2 /Height 1 /ColorSpace /DeviceRGB"));
+    }
+
+    #[test]
+    fn empty_document_is_still_valid() {
+        let pdf = PdfDocument::new().to_bytes();
+        assert_eq!(check_xref(&pdf), 5);
+        assert!(String::from_utf8_lossy(&pdf).contains("/Kids [] /Count 0"));
+    }
+
+    #[test]
+    fn text_is_ucs2_hex() {
+        assert_eq!(encode_text("A中"), "<00414E2D>");
+        // BMP 以外的字符替换为问号
+        assert_eq!(encode_text("😀b"), "<003F0062>");
+        assert_eq!(encode_text(""), "<>");
+    }
+
+    #[test]
+    fn numbers_are_trimmed() {
+        assert_eq!(number(12.0), "12");
+        assert_eq!(number(1.5), "1.5");
+        assert_eq!(number(0.126), "0.13");
+        assert_eq!(number(-0.001), "0");
+        assert_eq!(number(-2.50), "-2.5");
+    }
+}
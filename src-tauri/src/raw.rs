@@ -0,0 +1,162 @@
+use std::io::{ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use crate::retry::PrintError;
+
+// AppSocket/JetDirect 默认端口
+pub const DEFAULT_PORT: u16 = 9100;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+// 热敏打印机的接收缓冲区较小，分块写入避免一次性塞满
+const CHUNK_SIZE: usize = 4096;
+
+pub fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+// 依次尝试解析出的地址，返回第一个连接成功的，LPD 客户端同样使用
+pub fn connect(host: &str, port: u16, timeout: Duration) -> Result<TcpStream, String> {
+    let address = format!("{}:{}", host, port);
+    let addrs: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("无法解析打印机地址 {}: {}", address, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("无法解析打印机地址 {}", address));
+    }
+
+    let mut last_error = String::new();
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if e.kind() == ErrorKind::TimedOut => {
+                last_error = format!("连接打印机 {} 超时", address);
+            }
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                last_error = format!("打印机 {} 拒绝连接，请检查端口是否正确", address);
+            }
+            Err(e) => {
+                last_error = format!("连接打印机 {} 失败: {}", address, e);
+            }
+        }
+    }
+    Err(last_error)
+}
+
+// 通过 RAW 端口直接发送打印数据的网络打印机
+pub struct RawPrinter {
+    pub host: String,
+    pub port: u16,
+    pub connect_timeout: Duration,
+    pub write_timeout: Duration,
+}
+
+impl RawPrinter {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.trim().to_string(),
+            port,
+            connect_timeout: CONNECT_TIMEOUT,
+            write_timeout: WRITE_TIMEOUT,
+        }
+    }
+
+    fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    // 发送数据，返回写入的字节数
+    // 开始写入前的失败可以重试；开始写入后打印机可能已经打印了一部分，重试会重复打印，按致命错误处理
+    pub fn send(&self, data: &[u8]) -> Result<usize, PrintError> {
+        if data.is_empty() {
+            return Err(PrintError::fatal("打印数据为空".to_string()));
+        }
+
+        let mut stream = connect(&self.host, self.port, self.connect_timeout).map_err(PrintError::transient)?;
+        stream
+            .set_write_timeout(Some(self.write_timeout))
+            .map_err(|e| PrintError::transient(format!("设置写入超时失败: {}", e)))?;
+        println!("已连接打印机 {}，发送 {} 字节", self.address(), data.len());
+
+        let mut written = 0;
+        for chunk in data.chunks(CHUNK_SIZE) {
+            stream.write_all(chunk).map_err(|e| PrintError::fatal(match e.kind() {
+                ErrorKind::TimedOut | ErrorKind::WouldBlock => {
+                    format!("向打印机 {} 写入超时，已发送 {}/{} 字节", self.address(), written, data.len())
+                }
+                _ => format!("向打印机 {} 写入失败，已发送 {}/{} 字节: {}", self.address(), written, data.len(), e),
+            }))?;
+            written += chunk.len();
+        }
+
+        stream
+            .flush()
+            .map_err(|e| PrintError::fatal(format!("向打印机 {} 写入失败: {}", self.address(), e)))?;
+        // 关闭写端，打印机据此判断作业结束
+        let _ = stream.shutdown(Shutdown::Write);
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::ErrorClass;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn send_streams_payload_in_chunks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let receiver = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let payload: Vec<u8> = (0..CHUNK_SIZE * 3 + 17).map(|i| (i % 251) as u8).collect();
+        let written = RawPrinter::new("127.0.0.1", port).send(&payload).unwrap();
+
+        assert_eq!(written, payload.len());
+        assert_eq!(receiver.join().unwrap(), payload);
+    }
+
+    #[test]
+    fn refused_connection_is_transient() {
+        // 绑定后立即释放，得到一个没有监听的端口
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let err = RawPrinter::new("127.0.0.1", port).send(b"^XA^XZ").unwrap_err();
+        assert!(err.message.contains(&format!("127.0.0.1:{}", port)));
+        assert_eq!(err.class, ErrorClass::Transient);
+    }
+
+    #[test]
+    fn failure_after_first_byte_is_fatal() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // 读到一部分数据后断开连接，模拟打印机中途掉线
+        let receiver = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; CHUNK_SIZE];
+            stream.read_exact(&mut buffer).unwrap();
+        });
+
+        let payload = vec![0u8; 64 * 1024 * 1024];
+        let err = RawPrinter::new("127.0.0.1", port).send(&payload).unwrap_err();
+        receiver.join().unwrap();
+
+        assert_eq!(err.class, ErrorClass::Fatal);
+        assert!(err.message.contains("已发送"), "{}", err);
+    }
+
+    #[test]
+    fn empty_payload_is_rejected() {
+        let err = RawPrinter::new("127.0.0.1", DEFAULT_PORT).send(&[]).unwrap_err();
+        assert_eq!(err, PrintError::fatal("打印数据为空".to_string()));
+    }
+}
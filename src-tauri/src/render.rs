@@ -0,0 +1,861 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use crate::barcode::{self, BarcodeMatrix, Symbology};
+use crate::pdf::{encode_text, number, PdfDocument, PdfImage, PdfPage};
+
+// 与前端 index.ts 的容器宽度一致 (CSS px)，内容按此宽度排版后整体缩放到纸张宽度
+const CONTENT_WIDTH: f32 = 300.0;
+const DEFAULT_FONT_SIZE: f32 = 12.0;
+const DEFAULT_LINE_HEIGHT: f32 = 1.3;
+const MM_TO_PT: f32 = 72.0 / 25.4;
+// 下载一张远程图片的超时时间，包括连接与读取
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(15);
+
+// 纸张尺寸，单位为毫米，对应前端 PrintOptions.page_size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+// 前端 types.ts 中的 PrintData
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintData {
+    #[serde(rename = "type")]
+    pub print_type: PrintType,
+    pub value: Option<String>,
+    pub style: Option<HashMap<String, Value>>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub fontsize: Option<f32>,
+    pub display_value: Option<bool>,
+    // barCode 的码制，默认 Code128
+    pub format: Option<Symbology>,
+    pub position: Option<PrintPosition>,
+    pub path: Option<String>,
+    pub url: Option<String>,
+    pub table_header: Option<Vec<TableCell>>,
+    pub table_body: Option<Vec<Vec<TableCell>>>,
+    pub table_footer: Option<Vec<TableCell>>,
+    pub table_header_style: Option<HashMap<String, Value>>,
+    pub table_body_style: Option<HashMap<String, Value>>,
+    pub table_footer_style: Option<HashMap<String, Value>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PrintType {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "barCode")]
+    BarCode,
+    #[serde(rename = "qrCode")]
+    QrCode,
+    #[serde(rename = "image")]
+    Image,
+    #[serde(rename = "table")]
+    Table,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrintPosition {
+    Left,
+    Center,
+    Right,
+}
+
+// 表格单元格可以是字符串，也可以是 PrintTableField
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TableCell {
+    Text(String),
+    Field(TableField),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableField {
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub value: Option<String>,
+    pub path: Option<String>,
+    pub style: Option<HashMap<String, Value>>,
+    pub width: Option<String>,
+    pub height: Option<String>,
+}
+
+// 支持的样式子集：fontSize、fontWeight、textAlign、lineHeight、margin/padding 与 border 宽度
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    font_size: f32,
+    bold: bool,
+    align: PrintPosition,
+    line_height: f32,
+    margin_top: f32,
+    margin_bottom: f32,
+    padding: [f32; 4],
+    border: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            font_size: DEFAULT_FONT_SIZE,
+            bold: false,
+            align: PrintPosition::Left,
+            line_height: DEFAULT_FONT_SIZE * DEFAULT_LINE_HEIGHT,
+            margin_top: 0.0,
+            margin_bottom: 0.0,
+            padding: [0.0; 4],
+            border: 0.0,
+        }
+    }
+}
+
+impl Style {
+    // 在父级样式上叠加 CSS 样式对象，只继承字体相关的属性
+    fn apply(parent: &Style, css: Option<&HashMap<String, Value>>) -> Style {
+        let mut style = Style {
+            font_size: parent.font_size,
+            bold: parent.bold,
+            align: parent.align,
+            line_height: parent.line_height,
+            ..Style::default()
+        };
+        let css = match css {
+            Some(css) => css,
+            None => return style,
+        };
+        let get = |key: &str| css.get(key).map(css_string);
+
+        if let Some(size) = get("fontSize").and_then(|v| parse_length(&v)) {
+            let ratio = style.line_height / style.font_size;
+            style.font_size = size;
+            style.line_height = size * ratio;
+        }
+        if let Some(weight) = get("fontWeight") {
+            style.bold = weight == "bold" || weight == "bolder" || matches!(weight.parse::<u32>(), Ok(w) if w >= 600);
+        }
+        match get("textAlign").as_deref() {
+            Some("center") => style.align = PrintPosition::Center,
+            Some("right") | Some("end") => style.align = PrintPosition::Right,
+            Some("left") | Some("start") => style.align = PrintPosition::Left,
+            _ => {}
+        }
+        if let Some(line_height) = get("lineHeight") {
+            // 不带单位时为字号的倍数
+            style.line_height = match line_height.trim().parse::<f32>() {
+                Ok(multiple) => multiple * style.font_size,
+                Err(_) => parse_length(&line_height).unwrap_or(style.line_height),
+            };
+        }
+        if let Some(margin) = get("margin").and_then(|v| parse_box(&v)) {
+            style.margin_top = margin[0];
+            style.margin_bottom = margin[2];
+        }
+        if let Some(value) = get("marginTop").and_then(|v| parse_length(&v)) {
+            style.margin_top = value;
+        }
+        if let Some(value) = get("marginBottom").and_then(|v| parse_length(&v)) {
+            style.margin_bottom = value;
+        }
+        if let Some(padding) = get("padding").and_then(|v| parse_box(&v)) {
+            style.padding = padding;
+        }
+        for (index, key) in ["paddingTop", "paddingRight", "paddingBottom", "paddingLeft"].iter().enumerate() {
+            if let Some(value) = get(key).and_then(|v| parse_length(&v)) {
+                style.padding[index] = value;
+            }
+        }
+        if let Some(border) = get("border") {
+            // "1px solid #000"，取宽度部分
+            style.border = if border.trim() == "none" {
+                0.0
+            } else {
+                border.split_whitespace().find_map(parse_length).unwrap_or(1.0)
+            };
+        }
+        style
+    }
+}
+
+fn css_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    }
+}
+
+// 解析 CSS 长度，支持 px、pt 与纯数字
+fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    if let Some(px) = value.strip_suffix("px") {
+        return px.trim().parse().ok();
+    }
+    if let Some(pt) = value.strip_suffix("pt") {
+        return pt.trim().parse::<f32>().ok().map(|v| v * 4.0 / 3.0);
+    }
+    value.parse().ok()
+}
+
+// 解析 margin / padding 简写，返回 [上, 右, 下, 左]
+fn parse_box(value: &str) -> Option<[f32; 4]> {
+    let parts: Vec<f32> = value.split_whitespace().filter_map(parse_length).collect();
+    match parts.as_slice() {
+        [all] => Some([*all; 4]),
+        [vertical, horizontal] => Some([*vertical, *horizontal, *vertical, *horizontal]),
+        [top, horizontal, bottom] => Some([*top, *horizontal, *bottom, *horizontal]),
+        [top, right, bottom, left, ..] => Some([*top, *right, *bottom, *left]),
+        _ => None,
+    }
+}
+
+// 字符宽度：ASCII 为半角，其余按全角计算，与 PDF 中声明的字体宽度一致
+fn text_width(text: &str, font_size: f32) -> f32 {
+    text.chars().map(|c| if c.is_ascii() { 0.5 } else { 1.0 }).sum::<f32>() * font_size
+}
+
+// 按宽度折行，英文单词尽量不拆开
+fn wrap_text(text: &str, font_size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut width = 0.0;
+        let mut last_space: Option<usize> = None;
+
+        for c in paragraph.chars() {
+            let char_width = text_width(c.encode_utf8(&mut [0; 4]), font_size);
+            if width + char_width > max_width && !line.is_empty() {
+                match last_space {
+                    // 在最后一个空格处断开，剩余部分移到下一行
+                    Some(index) if c.is_ascii_alphanumeric() => {
+                        let rest = line.split_off(index + 1);
+                        lines.push(line.trim_end().to_string());
+                        line = rest;
+                    }
+                    _ => lines.push(std::mem::take(&mut line)),
+                }
+                width = text_width(&line, font_size);
+                last_space = None;
+            }
+            if c == ' ' {
+                last_space = Some(line.len());
+            }
+            line.push(c);
+            width += char_width;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// 前端 text 的 value 会作为 innerHTML，这里去掉标签，<br> 与块级结束标签转为换行
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut tag = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let name = tag.trim().trim_end_matches('/').trim().to_ascii_lowercase();
+                if name.starts_with("br") || name == "/p" || name == "/div" {
+                    text.push('\n');
+                }
+            }
+            c if in_tag => tag.push(c),
+            c => text.push(c),
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&");
+    text.trim_end_matches('\n').to_string()
+}
+
+// 绘制到当前页，坐标为左上角原点的 CSS px
+struct Canvas {
+    scale: f32,
+    page_width: f32,
+    page_height: f32,
+    content: String,
+}
+
+impl Canvas {
+    fn x(&self, x: f32) -> String {
+        number(x * self.scale)
+    }
+
+    fn y(&self, y: f32) -> String {
+        number(self.page_height - y * self.scale)
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let op = format!(
+            "{} {} {} {} re f\n",
+            self.x(x),
+            self.y(y + height),
+            number(width * self.scale),
+            number(height * self.scale)
+        );
+        self.content.push_str(&op);
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32) {
+        let op = format!(
+            "{} w {} {} m {} {} l S\n",
+            number(width * self.scale),
+            self.x(x1),
+            self.y(y1),
+            self.x(x2),
+            self.y(y2)
+        );
+        self.content.push_str(&op);
+    }
+
+    // baseline 为文字基线的位置
+    fn text(&mut self, x: f32, baseline: f32, font_size: f32, bold: bool, text: &str) {
+        // 粗体用描边模拟
+        let mode = if bold {
+            format!("2 Tr {} w ", number(font_size * self.scale * 0.03))
+        } else {
+            "0 Tr ".to_string()
+        };
+        let op = format!(
+            "BT {}/F1 {} Tf {} {} Td {} Tj ET\n",
+            mode,
+            number(font_size * self.scale),
+            self.x(x),
+            self.y(baseline),
+            encode_text(text)
+        );
+        self.content.push_str(&op);
+    }
+
+    fn image(&mut self, x: f32, y: f32, width: f32, height: f32, index: usize) {
+        let op = format!(
+            "q {} 0 0 {} {} {} cm /Im{} Do Q\n",
+            number(width * self.scale),
+            number(height * self.scale),
+            self.x(x),
+            self.y(y + height),
+            index
+        );
+        self.content.push_str(&op);
+    }
+
+    fn barcode(&mut self, matrix: &BarcodeMatrix, x: f32, y: f32, module_width: f32, module_height: f32) {
+        for row in 0..matrix.height {
+            for (start, length) in matrix.runs(row) {
+                self.fill_rect(
+                    x + start as f32 * module_width,
+                    y + row as f32 * module_height,
+                    length as f32 * module_width,
+                    module_height,
+                );
+            }
+        }
+    }
+}
+
+// 渲染器：顺序排列各个 PrintData，超出页面高度时换页
+struct Renderer<'a> {
+    document: PdfDocument,
+    canvas: Canvas,
+    cursor: f32,
+    page_height_px: f32,
+    // 已嵌入的图片：来源 -> (索引, 像素宽, 像素高)
+    images: HashMap<String, (usize, f32, f32)>,
+    remote: &'a HashMap<String, Vec<u8>>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(page_size: &PageSize, remote: &'a HashMap<String, Vec<u8>>) -> Self {
+        let page_width = page_size.width * MM_TO_PT;
+        let page_height = page_size.height * MM_TO_PT;
+        let scale = page_width / CONTENT_WIDTH;
+        Self {
+            document: PdfDocument::new(),
+            canvas: Canvas {
+                scale,
+                page_width,
+                page_height,
+                content: String::new(),
+            },
+            cursor: 0.0,
+            page_height_px: page_height / scale,
+            images: HashMap::new(),
+            remote,
+        }
+    }
+
+    // 剩余空间不足时换页，页面顶部的内容不再换页
+    fn reserve(&mut self, height: f32) {
+        if self.cursor > 0.0 && self.cursor + height > self.page_height_px {
+            self.new_page();
+        }
+    }
+
+    fn new_page(&mut self) {
+        let content = std::mem::take(&mut self.canvas.content);
+        self.document.pages.push(PdfPage {
+            width: self.canvas.page_width,
+            height: self.canvas.page_height,
+            content,
+        });
+        self.cursor = 0.0;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if !self.canvas.content.is_empty() || self.document.pages.is_empty() {
+            self.new_page();
+        }
+        self.document.to_bytes()
+    }
+
+    fn aligned_x(align: PrintPosition, left: f32, available: f32, width: f32) -> f32 {
+        match align {
+            PrintPosition::Left => left,
+            PrintPosition::Center => left + (available - width).max(0.0) / 2.0,
+            PrintPosition::Right => left + (available - width).max(0.0),
+        }
+    }
+
+    fn render(&mut self, item: &PrintData) -> Result<(), String> {
+        let mut style = Style::apply(&Style::default(), item.style.as_ref());
+        if let Some(size) = item.fontsize {
+            style.line_height = size * style.line_height / style.font_size;
+            style.font_size = size;
+        }
+        self.cursor += style.margin_top;
+
+        match item.print_type {
+            PrintType::Text => self.render_text(item, &style),
+            PrintType::BarCode => self.render_barcode(item, &style)?,
+            PrintType::QrCode => self.render_qrcode(item, &style)?,
+            PrintType::Image => self.render_image(item, &style)?,
+            PrintType::Table => self.render_table(item, &style)?,
+        }
+
+        self.cursor += style.margin_bottom;
+        Ok(())
+    }
+
+    fn render_text(&mut self, item: &PrintData, style: &Style) {
+        let text = strip_html(item.value.as_deref().unwrap_or_default());
+        let left = style.padding[3];
+        let available = CONTENT_WIDTH - style.padding[1] - style.padding[3];
+        self.cursor += style.padding[0];
+
+        for line in wrap_text(&text, style.font_size, available) {
+            self.reserve(style.line_height);
+            self.draw_line(&line, left, available, self.cursor, style);
+            self.cursor += style.line_height;
+        }
+        self.cursor += style.padding[2];
+    }
+
+    // 在行框内垂直居中绘制一行文字
+    fn draw_line(&mut self, line: &str, left: f32, available: f32, top: f32, style: &Style) {
+        let width = text_width(line, style.font_size);
+        let x = Self::aligned_x(style.align, left, available, width);
+        let baseline = top + (style.line_height - style.font_size) / 2.0 + style.font_size * 0.88;
+        self.canvas.text(x, baseline, style.font_size, style.bold, line);
+    }
+
+    // 与 JsBarcode 一致：默认模块宽 4、高 40，两侧各留 10px，整体不超过容器宽度
+    fn render_barcode(&mut self, item: &PrintData, style: &Style) -> Result<(), String> {
+        let symbology = item.format.unwrap_or_default();
+        if !symbology.is_linear() {
+            return self.render_matrix(item, symbology);
+        }
+
+        let value = item.value.as_deref().unwrap_or_default();
+        let matrix = barcode::encode(symbology, value)?;
+        let margin = 10.0;
+        let natural = matrix.width as f32 * item.width.unwrap_or(4.0);
+        let width = natural.min(CONTENT_WIDTH - margin * 2.0);
+        let module_width = width / matrix.width as f32;
+        let bar_height = item.height.unwrap_or(40.0);
+        let show_value = item.display_value.unwrap_or(true);
+        let text_size = item.fontsize.unwrap_or(20.0).min(bar_height);
+        let total = bar_height + margin * 2.0 + if show_value { text_size + 2.0 } else { 0.0 };
+
+        self.reserve(total);
+        let align = item.position.unwrap_or(PrintPosition::Center);
+        let x = Self::aligned_x(align, margin, CONTENT_WIDTH - margin * 2.0, width);
+        let y = self.cursor + margin;
+        self.canvas.barcode(&matrix, x, y, module_width, bar_height);
+
+        if show_value {
+            let text_x = x + (width - text_width(value, text_size)).max(0.0) / 2.0;
+            self.canvas.text(text_x, y + bar_height + 2.0 + text_size * 0.88, text_size, style.bold, value);
+        }
+        self.cursor += total;
+        Ok(())
+    }
+
+    fn render_qrcode(&mut self, item: &PrintData, _style: &Style) -> Result<(), String> {
+        self.render_matrix(item, Symbology::Qrcode)
+    }
+
+    // 二维码制：与 qrcode 包默认值一致，每模块 4px，四周按码制留出静区
+    fn render_matrix(&mut self, item: &PrintData, symbology: Symbology) -> Result<(), String> {
+        let matrix = barcode::encode(symbology, item.value.as_deref().unwrap_or_default())?;
+        let quiet = symbology.quiet_zone() as f32;
+        let columns = matrix.width as f32 + quiet * 2.0;
+        let rows = matrix.height as f32 + quiet * 2.0;
+        let width = item.width.unwrap_or(columns * 4.0).min(CONTENT_WIDTH);
+        let module = width / columns;
+        let height = rows * module;
+
+        self.reserve(height);
+        let x = Self::aligned_x(item.position.unwrap_or(PrintPosition::Left), 0.0, CONTENT_WIDTH, width);
+        self.canvas.barcode(&matrix, x + module * quiet, self.cursor + module * quiet, module, module);
+        self.cursor += height;
+        Ok(())
+    }
+
+    fn render_image(&mut self, item: &PrintData, _style: &Style) -> Result<(), String> {
+        let source = item.path.as_deref().or(item.url.as_deref()).ok_or("图片缺少 path 或 url")?;
+        let (index, natural_width, natural_height) = self.load_image(source)?;
+        let width = item.width.unwrap_or(100.0).min(CONTENT_WIDTH);
+        let height = item.height.unwrap_or(width * natural_height / natural_width);
+
+        self.reserve(height);
+        let x = Self::aligned_x(item.position.unwrap_or(PrintPosition::Left), 0.0, CONTENT_WIDTH, width);
+        self.canvas.image(x, self.cursor, width, height, index);
+        self.cursor += height;
+        Ok(())
+    }
+
+    // 读取并登记图片，同一来源只嵌入一次；返回 (索引, 像素宽, 像素高)
+    fn load_image(&mut self, source: &str) -> Result<(usize, f32, f32), String> {
+        if let Some(loaded) = self.images.get(source) {
+            return Ok(*loaded);
+        }
+
+        let bytes = if let Some(data) = source.strip_prefix("data:") {
+            let encoded = data.split_once("base64,").map(|(_, rest)| rest).ok_or("仅支持 base64 data URL")?;
+            general_purpose::STANDARD.decode(encoded.trim()).map_err(|e| format!("图片 base64 解码失败: {}", e))?
+        } else if let Some(bytes) = self.remote.get(source) {
+            bytes.clone()
+        } else {
+            fs::read(source).map_err(|e| format!("读取图片失败 {}: {}", source, e))?
+        };
+
+        let image = image::load_from_memory(&bytes).map_err(|e| format!("解析图片失败: {}", e))?;
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+
+        // 透明部分与白色背景混合
+        let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+        for pixel in rgba.pixels() {
+            let [r, g, b, a] = pixel.0;
+            for channel in [r, g, b] {
+                rgb.push(((channel as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8);
+            }
+        }
+        let index = self.document.add_image(PdfImage { width, height, rgb });
+        let loaded = (index, width as f32, height as f32);
+        self.images.insert(source.to_string(), loaded);
+        Ok(loaded)
+    }
+
+    fn render_table(&mut self, item: &PrintData, table_style: &Style) -> Result<(), String> {
+        let header = item.table_header.clone().unwrap_or_default();
+        let body = item.table_body.clone().unwrap_or_default();
+        let footer = item.table_footer.clone().unwrap_or_default();
+
+        let columns = std::iter::once(&header)
+            .chain(body.iter())
+            .chain(std::iter::once(&footer))
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0);
+        if columns == 0 {
+            return Ok(());
+        }
+        let widths = column_widths(&header, columns);
+
+        self.render_row_group(&header, item.table_header_style.as_ref(), &widths, table_style)?;
+        for row in &body {
+            self.render_row_group(row, item.table_body_style.as_ref(), &widths, table_style)?;
+        }
+        self.render_row_group(&footer, item.table_footer_style.as_ref(), &widths, table_style)?;
+        Ok(())
+    }
+
+    fn render_row_group(
+        &mut self,
+        row: &[TableCell],
+        section_style: Option<&HashMap<String, Value>>,
+        widths: &[f32],
+        table_style: &Style,
+    ) -> Result<(), String> {
+        if row.is_empty() {
+            return Ok(());
+        }
+        let mut section = Style::apply(table_style, section_style);
+        if section.border == 0.0 {
+            section.border = table_style.border;
+        }
+
+        // 单元格默认 padding 1px，与浏览器的 td 一致
+        let cells: Vec<(Style, Vec<String>, Option<&TableField>)> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| {
+                let (text, field) = match cell {
+                    TableCell::Text(text) => (text.clone(), None),
+                    TableCell::Field(field) => (field.value.clone().unwrap_or_default(), Some(field)),
+                };
+                let mut style = Style::apply(&section, field.and_then(|f| f.style.as_ref()));
+                if style.padding == [0.0; 4] {
+                    style.padding = [1.0; 4];
+                }
+                if style.border == 0.0 {
+                    style.border = section.border;
+                }
+                let available = width - style.padding[1] - style.padding[3];
+                (style, wrap_text(&strip_html(&text), style.font_size, available), field)
+            })
+            .collect();
+
+        let mut height: f32 = 0.0;
+        for ((style, lines, field), width) in cells.iter().zip(widths) {
+            let content = match field {
+                Some(field) if field.field_type == "image" => {
+                    let (_, w, h) = self.load_image(field.path.as_deref().unwrap_or_default())?;
+                    field.height.as_deref().and_then(parse_length).unwrap_or((width - 2.0) * h / w)
+                }
+                _ => lines.len() as f32 * style.line_height,
+            };
+            height = height.max(content + style.padding[0] + style.padding[2]);
+        }
+
+        self.reserve(height);
+        let top = self.cursor;
+        let mut left = 0.0;
+        for ((style, lines, field), width) in cells.iter().zip(widths) {
+            let inner_left = left + style.padding[3];
+            let inner_width = width - style.padding[1] - style.padding[3];
+            match field {
+                Some(field) if field.field_type == "image" => {
+                    let (index, w, h) = self.load_image(field.path.as_deref().unwrap_or_default())?;
+                    let image_height = field.height.as_deref().and_then(parse_length).unwrap_or(inner_width * h / w);
+                    let image_width = image_height * w / h;
+                    let x = Self::aligned_x(style.align, inner_left, inner_width, image_width);
+                    self.canvas.image(x, top + style.padding[0], image_width, image_height, index);
+                }
+                _ => {
+                    for (line_index, line) in lines.iter().enumerate() {
+                        let line_top = top + style.padding[0] + line_index as f32 * style.line_height;
+                        self.draw_line(line, inner_left, inner_width, line_top, style);
+                    }
+                }
+            }
+            if style.border > 0.0 {
+                let b = style.border;
+                self.canvas.line(left, top, left + width, top, b);
+                self.canvas.line(left, top + height, left + width, top + height, b);
+                self.canvas.line(left, top, left, top + height, b);
+                self.canvas.line(left + width, top, left + width, top + height, b);
+            }
+            left += width;
+        }
+        self.cursor += height;
+        Ok(())
+    }
+}
+
+// 表头单元格可以指定宽度 ("30%" 或 "80px")，其余列平分剩余宽度
+fn column_widths(header: &[TableCell], columns: usize) -> Vec<f32> {
+    let mut widths: Vec<Option<f32>> = vec![None; columns];
+    for (index, cell) in header.iter().enumerate() {
+        if let TableCell::Field(TableField { width: Some(width), .. }) = cell {
+            widths[index] = match width.trim().strip_suffix('%') {
+                Some(percent) => percent.trim().parse::<f32>().ok().map(|p| CONTENT_WIDTH * p / 100.0),
+                None => parse_length(width),
+            };
+        }
+    }
+
+    let fixed: f32 = widths.iter().flatten().sum();
+    let flexible = widths.iter().filter(|w| w.is_none()).count();
+    let rest = if flexible > 0 { (CONTENT_WIDTH - fixed).max(0.0) / flexible as f32 } else { 0.0 };
+    widths.into_iter().map(|w| w.unwrap_or(rest)).collect()
+}
+
+// 需要下载的远程图片地址
+pub fn remote_image_urls(data: &[PrintData]) -> Vec<String> {
+    let mut urls = Vec::new();
+    for item in data {
+        if item.print_type == PrintType::Image && item.path.is_none() {
+            if let Some(url) = &item.url {
+                if url.starts_with("http://") || url.starts_with("https://") {
+                    urls.push(url.clone());
+                }
+            }
+        }
+    }
+    urls
+}
+
+// 下载远程图片，渲染时按地址查找；404 之类的错误页面不当作图片内容
+pub async fn fetch_remote_images(data: &[PrintData]) -> Result<HashMap<String, Vec<u8>>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(IMAGE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+    let mut images = HashMap::new();
+    for url in remote_image_urls(data) {
+        if images.contains_key(&url) {
+            continue;
+        }
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("下载图片失败 {}: {}", url, e))?;
+        let bytes = response.bytes().await.map_err(|e| format!("下载图片失败 {}: {}", url, e))?;
+        images.insert(url, bytes.to_vec());
+    }
+    Ok(images)
+}
+
+// 把 PrintData 渲染为矢量 PDF
+pub fn render_pdf(data: &[PrintData], page_size: &PageSize, remote: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>, String> {
+    if page_size.width <= 0.0 || page_size.height <= 0.0 {
+        return Err("纸张尺寸无效".to_string());
+    }
+
+    let mut renderer = Renderer::new(page_size, remote);
+    for item in data {
+        renderer.render(item)?;
+    }
+    Ok(renderer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_count(pdf: &[u8]) -> usize {
+        let text = String::from_utf8_lossy(pdf);
+        let start = text.find("/Count ").unwrap() + "/Count ".len();
+        text[start..].split_whitespace().next().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn wrap_by_width() {
+        // 字号 10 时 ASCII 宽 5，每行最多 10 个字符
+        assert_eq!(wrap_text("hello world foo", 10.0, 50.0), vec!["hello", "world foo"]);
+        assert_eq!(wrap_text("abcdefghijkl", 10.0, 50.0), vec!["abcdefghij", "kl"]);
+        assert_eq!(wrap_text("中文测试", 10.0, 25.0), vec!["中文", "测试"]);
+        assert_eq!(wrap_text("a\nb", 10.0, 50.0), vec!["a", "b"]);
+        assert_eq!(wrap_text("", 10.0, 50.0), vec![""]);
+        // 宽度放不下一个字符时每行仍输出一个字符
+        assert_eq!(wrap_text("中文", 10.0, 5.0), vec!["中", "文"]);
+    }
+
+    #[test]
+    fn html_is_stripped() {
+        assert_eq!(strip_html("收件人<br/>张三<p>地址</p><div>x</div>"), "收件人\n张三地址\nx");
+        assert_eq!(strip_html("a<BR>b<br />c"), "a\nb\nc");
+        assert_eq!(strip_html("<b>&lt;b&gt;&nbsp;&amp;&quot;</b>"), "<b> &\"");
+        assert_eq!(strip_html("&amp;lt;"), "&lt;");
+    }
+
+    #[test]
+    fn box_shorthand() {
+        assert_eq!(parse_box("4px"), Some([4.0; 4]));
+        assert_eq!(parse_box("1 2"), Some([1.0, 2.0, 1.0, 2.0]));
+        assert_eq!(parse_box("1px 2px 3px"), Some([1.0, 2.0, 3.0, 2.0]));
+        assert_eq!(parse_box("1 2 3 4"), Some([1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(parse_box("6pt 0"), Some([8.0, 0.0, 8.0, 0.0]));
+        assert_eq!(parse_box("auto"), None);
+    }
+
+    #[test]
+    fn header_widths() {
+        let header: Vec<TableCell> = serde_json::from_value(serde_json::json!([
+            { "type": "text", "value": "商品", "width": "30%" },
+            "数量",
+            { "type": "text", "value": "单价", "width": "60px" }
+        ]))
+        .unwrap();
+        // 固定宽度 90 + 60，其余两列平分 150
+        assert_eq!(column_widths(&header, 4), vec![90.0, 75.0, 60.0, 75.0]);
+        assert_eq!(column_widths(&[], 3), vec![100.0; 3]);
+
+        // 固定宽度超过容器时其余列宽为 0
+        let header: Vec<TableCell> = serde_json::from_value(serde_json::json!([
+            { "type": "text", "width": "80%" },
+            { "type": "text", "width": "90px" }
+        ]))
+        .unwrap();
+        assert_eq!(column_widths(&header, 3), vec![240.0, 90.0, 0.0]);
+    }
+
+    #[test]
+    fn reserve_starts_new_page() {
+        let remote = HashMap::new();
+        let mut renderer = Renderer::new(&PageSize { width: 76.0, height: 20.0 }, &remote);
+        let page_height = renderer.page_height_px;
+
+        // 页面顶部的内容即使超高也不换页
+        renderer.reserve(page_height * 2.0);
+        assert!(renderer.document.pages.is_empty());
+
+        renderer.cursor = page_height - 10.0;
+        renderer.reserve(10.0);
+        assert!(renderer.document.pages.is_empty());
+        renderer.reserve(10.5);
+        assert_eq!(renderer.document.pages.len(), 1);
+        assert_eq!(renderer.cursor, 0.0);
+    }
+
+    #[test]
+    fn long_text_spans_pages() {
+        // 20mm 高的页面约 79px，行高 15.6px，每页 5 行
+        let value = (1..=12).map(|n| n.to_string()).collect::<Vec<_>>().join("<br>");
+        let data: Vec<PrintData> = serde_json::from_value(serde_json::json!([{ "type": "text", "value": value }])).unwrap();
+        let pdf = render_pdf(&data, &PageSize { width: 76.0, height: 20.0 }, &HashMap::new()).unwrap();
+        assert_eq!(page_count(&pdf), 3);
+
+        let pdf = render_pdf(&[], &PageSize { width: 76.0, height: 20.0 }, &HashMap::new()).unwrap();
+        assert_eq!(page_count(&pdf), 1);
+    }
+
+    #[test]
+    fn invalid_page_size_is_rejected() {
+        for (width, height) in [(0.0, 100.0), (76.0, 0.0), (-1.0, 100.0)] {
+            assert!(render_pdf(&[], &PageSize { width, height }, &HashMap::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn only_http_images_are_fetched() {
+        let data: Vec<PrintData> = serde_json::from_value(serde_json::json!([
+            { "type": "image", "url": "https://example.com/a.png" },
+            { "type": "image", "url": "file:///tmp/a.png" },
+            { "type": "image", "url": "http://example.com/b.png", "path": "/tmp/b.png" },
+            { "type": "text", "url": "http://example.com/c.png" }
+        ]))
+        .unwrap();
+        assert_eq!(remote_image_urls(&data), vec!["https://example.com/a.png"]);
+    }
+}
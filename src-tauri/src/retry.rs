@@ -0,0 +1,219 @@
+use std::fmt;
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use crate::declare::{Printer, PrinterStatus};
+use crate::journal::{now_millis, JobJournal, JobState};
+
+// 打印失败的错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    // 打印机离线、队列忙、网络超时等，稍后重试可能成功
+    Transient,
+    // 文件或打印设置有误、打印机不存在等，重试没有意义
+    Fatal,
+}
+
+// 各类错误的重试策略
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    // 最多提交几次，包括第一次
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn for_class(class: ErrorClass) -> Self {
+        match class {
+            ErrorClass::Transient => RetryPolicy {
+                max_attempts: 5,
+                base_delay: Duration::from_secs(5),
+                max_delay: Duration::from_secs(120),
+            },
+            ErrorClass::Fatal => RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+            },
+        }
+    }
+
+    // 第 attempts 次提交失败后等待的时间，每次翻倍，不超过 max_delay
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 1u32 << attempts.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+// 打印失败的错误，由产生错误的地方给出类别，不从错误信息中猜测
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintError {
+    pub class: ErrorClass,
+    pub message: String,
+}
+
+impl PrintError {
+    pub fn transient(message: String) -> Self {
+        PrintError { class: ErrorClass::Transient, message }
+    }
+
+    pub fn fatal(message: String) -> Self {
+        PrintError { class: ErrorClass::Fatal, message }
+    }
+}
+
+// 没有明确类别的错误按致命错误处理，避免重复打印
+impl From<String> for PrintError {
+    fn from(message: String) -> Self {
+        PrintError::fatal(message)
+    }
+}
+
+impl fmt::Display for PrintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+// 按提交失败后查询到的打印机状态判断系统队列的错误类别
+// 查询失败说明打印服务本身不可用（如 Spooler 未启动），打印机脱机、暂停或出错时稍后重试都可能成功
+// 打印机不存在，或者状态正常却提交失败，重试也没有意义
+pub fn classify_printer(printer: &Result<Option<Printer>, String>) -> ErrorClass {
+    match printer {
+        Err(_) => ErrorClass::Transient,
+        Ok(None) => ErrorClass::Fatal,
+        Ok(Some(printer)) => match printer.state.status {
+            PrinterStatus::Offline | PrinterStatus::Paused | PrinterStatus::Error => ErrorClass::Transient,
+            PrinterStatus::Idle | PrinterStatus::Printing | PrinterStatus::Unknown => ErrorClass::Fatal,
+        },
+    }
+}
+
+// 提交失败后按重试策略更新作业状态，需要重试时返回等待时间
+// 致命错误直接失败，临时错误重试次数用完后进入死信列表
+pub fn after_failure(journal: &JobJournal, id: &str, error: &PrintError) -> Option<Duration> {
+    let attempts = journal.get(id).map(|job| job.attempts).unwrap_or_default();
+    let class = error.class;
+    let policy = RetryPolicy::for_class(class);
+    let error = error.message.as_str();
+
+    let update = if class == ErrorClass::Fatal {
+        println!("作业 {} 打印失败: {}", id, error);
+        journal.fail(id, JobState::Failed, error)
+    } else if attempts >= policy.max_attempts {
+        println!("作业 {} 重试 {} 次后仍然失败，已移入死信列表: {}", id, attempts, error);
+        journal.fail(id, JobState::DeadLetter, error)
+    } else {
+        let delay = policy.delay(attempts);
+        println!("作业 {} 第 {} 次打印失败，{} 秒后重试: {}", id, attempts, delay.as_secs(), error);
+        let next_attempt_at = now_millis() + delay.as_millis() as u64;
+        return match journal.schedule_retry(id, error, next_attempt_at) {
+            Ok(_) => Some(delay),
+            Err(e) => {
+                println!("更新打印作业状态失败: {}", e);
+                None
+            }
+        };
+    };
+
+    if let Err(e) = update {
+        println!("更新打印作业状态失败: {}", e);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::declare::{Destination, PrinterReason, PrinterState};
+    use crate::fsys::unique_temp_path;
+    use crate::settings::PrintSettings;
+
+    fn printer(printing: bool, reasons: Vec<PrinterReason>) -> Result<Option<Printer>, String> {
+        Ok(Some(Printer {
+            state: PrinterState::new(printing, reasons),
+            ..Printer::new("P")
+        }))
+    }
+
+    fn journal() -> (JobJournal, String) {
+        let dir = unique_temp_path("retry_test", "d");
+        fs::create_dir_all(&dir).unwrap();
+        let journal = JobJournal::open(dir.join("print_jobs.jsonl"));
+        let destination = Destination::Queue { id: "P".to_string() };
+        let job = journal
+            .submit(destination, dir.join("a.pdf").display().to_string(), PrintSettings::default(), false)
+            .unwrap();
+        (journal, job.id)
+    }
+
+    #[test]
+    fn printer_state_decides_queue_errors() {
+        assert_eq!(classify_printer(&Err("RPC 服务器不可用".to_string())), ErrorClass::Transient);
+        assert_eq!(classify_printer(&Ok(None)), ErrorClass::Fatal);
+        assert_eq!(classify_printer(&printer(false, vec![PrinterReason::Offline])), ErrorClass::Transient);
+        assert_eq!(classify_printer(&printer(false, vec![PrinterReason::Paused])), ErrorClass::Transient);
+        assert_eq!(classify_printer(&printer(false, vec![PrinterReason::PaperOut])), ErrorClass::Transient);
+        // 打印机一切正常却提交失败，多半是文件或设置有误
+        assert_eq!(classify_printer(&printer(false, Vec::new())), ErrorClass::Fatal);
+        assert_eq!(classify_printer(&printer(true, Vec::new())), ErrorClass::Fatal);
+        assert_eq!(classify_printer(&Ok(Some(Printer::new("P")))), ErrorClass::Fatal);
+    }
+
+    #[test]
+    fn unclassified_errors_are_fatal() {
+        // 错误信息中的关键字不再影响分类
+        let error = PrintError::from("连接打印机 10.0.0.1:9100 超时".to_string());
+        assert_eq!(error.class, ErrorClass::Fatal);
+        assert_eq!(error.to_string(), "连接打印机 10.0.0.1:9100 超时");
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::for_class(ErrorClass::Transient);
+        assert_eq!(policy.delay(0), Duration::from_secs(5));
+        assert_eq!(policy.delay(1), Duration::from_secs(5));
+        assert_eq!(policy.delay(2), Duration::from_secs(10));
+        assert_eq!(policy.delay(5), Duration::from_secs(80));
+        assert_eq!(policy.delay(6), Duration::from_secs(120));
+        // 位移次数有上限，不会溢出
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(120));
+
+        let fatal = RetryPolicy::for_class(ErrorClass::Fatal);
+        assert_eq!(fatal.max_attempts, 1);
+        assert_eq!(fatal.delay(3), Duration::ZERO);
+    }
+
+    #[test]
+    fn transient_failures_end_in_dead_letter() {
+        let (journal, id) = journal();
+        let error = PrintError::transient("打印机脱机".to_string());
+        for attempt in 1..5 {
+            journal.start_attempt(&id).unwrap();
+            let before = now_millis();
+            let delay = after_failure(&journal, &id, &error).unwrap();
+            assert_eq!(delay, RetryPolicy::for_class(ErrorClass::Transient).delay(attempt));
+
+            let job = journal.get(&id).unwrap();
+            assert_eq!(job.state, JobState::Retrying);
+            assert!(job.next_attempt_at.unwrap() >= before + delay.as_millis() as u64);
+        }
+
+        journal.start_attempt(&id).unwrap();
+        assert_eq!(after_failure(&journal, &id, &error), None);
+        let job = journal.get(&id).unwrap();
+        assert_eq!(job.state, JobState::DeadLetter);
+        assert_eq!(job.attempts, 5);
+        assert_eq!(job.error.as_deref(), Some("打印机脱机"));
+    }
+
+    #[test]
+    fn fatal_failure_is_not_retried() {
+        let (journal, id) = journal();
+        journal.start_attempt(&id).unwrap();
+        assert_eq!(after_failure(&journal, &id, &PrintError::fatal("文件不存在".to_string())), None);
+        assert_eq!(journal.get(&id).unwrap().state, JobState::Failed);
+    }
+}
@@ -0,0 +1,388 @@
+use serde::{Serialize, Deserialize};
+
+// 打印设置，字段与前端 types.ts 中的 PrintSettings 保持一致
+// 未设置的选项沿用打印机默认值，由各打印后端翻译为自己的命令行参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrintSettings {
+    pub paper: Option<Paper>,
+    pub method: Option<Method>,
+    pub scale: Option<Scale>,
+    pub color_type: Option<ColorType>,
+    pub orientation: Option<Orientation>,
+    pub repeat: Option<u32>,
+    pub range: Option<PageRange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Paper {
+    A2,
+    A3,
+    A4,
+    A5,
+    A6,
+    #[serde(rename = "letter")]
+    Letter,
+    #[serde(rename = "legal")]
+    Legal,
+    #[serde(rename = "tabloid")]
+    Tabloid,
+    // 自定义尺寸 (毫米)，例如 76x130 的快递面单，JSON 为 { "custom": { "width_mm": 76, "height_mm": 130 } }
+    #[serde(rename = "custom")]
+    Custom { width_mm: u32, height_mm: u32 },
+}
+
+// 单双面打印，duplexshort 为短边翻转
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    Duplex,
+    Duplexshort,
+    Simplex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+    Noscale,
+    Shrink,
+    Fit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorType {
+    Color,
+    Monochrome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+// 页码范围：{ from, to } 或 "1-3,5" 形式的字符串
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PageRange {
+    Span { from: u32, to: u32 },
+    List(String),
+}
+
+impl Paper {
+    // 与 SumatraPDF 的 paper= 取值相同，SumatraPDF 不支持自定义尺寸
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self {
+            Paper::A2 => "A2",
+            Paper::A3 => "A3",
+            Paper::A4 => "A4",
+            Paper::A5 => "A5",
+            Paper::A6 => "A6",
+            Paper::Letter => "letter",
+            Paper::Legal => "legal",
+            Paper::Tabloid => "tabloid",
+            Paper::Custom { .. } => return None,
+        };
+        Some(name)
+    }
+
+    // 自定义尺寸的宽高，不能为 0
+    pub fn custom_size(&self) -> Result<Option<(u32, u32)>, String> {
+        match *self {
+            Paper::Custom { width_mm, height_mm } if width_mm == 0 || height_mm == 0 => {
+                Err(format!("无效的纸张尺寸: {}x{}mm", width_mm, height_mm))
+            }
+            Paper::Custom { width_mm, height_mm } => Ok(Some((width_mm, height_mm))),
+            _ => Ok(None),
+        }
+    }
+
+    // 纸张名称，或 "76x130mm" / CUPS 的 "Custom.76x130mm" 形式的自定义尺寸
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.to_ascii_lowercase();
+        if let Some(size) = value.strip_prefix("custom.").unwrap_or(&value).strip_suffix("mm") {
+            let (width, height) = size.split_once('x')?;
+            return Some(Paper::Custom { width_mm: width.parse().ok()?, height_mm: height.parse().ok()? });
+        }
+        let paper = match value.as_str() {
+            "a2" => Paper::A2,
+            "a3" => Paper::A3,
+            "a4" => Paper::A4,
+            "a5" => Paper::A5,
+            "a6" => Paper::A6,
+            "letter" => Paper::Letter,
+            "legal" => Paper::Legal,
+            "tabloid" => Paper::Tabloid,
+            _ => return None,
+        };
+        Some(paper)
+    }
+}
+
+impl PageRange {
+    // 校验并规范为 "1-3,5" 形式，页码从 1 开始
+    pub fn normalized(&self) -> Result<String, String> {
+        let value = match self {
+            PageRange::Span { from, to } => format!("{}-{}", from, to),
+            PageRange::List(list) => list.trim().trim_end_matches(',').replace(' ', ""),
+        };
+
+        let mut parts = Vec::new();
+        for part in value.split(',') {
+            let (from, to) = part.split_once('-').unwrap_or((part, part));
+            let from: u32 = from.parse().map_err(|_| format!("无效的页码范围: {}", value))?;
+            let to: u32 = to.parse().map_err(|_| format!("无效的页码范围: {}", value))?;
+            if from == 0 || to < from {
+                return Err(format!("无效的页码范围: {}", value));
+            }
+            parts.push(if from == to { from.to_string() } else { format!("{}-{}", from, to) });
+        }
+        Ok(parts.join(","))
+    }
+}
+
+impl PrintSettings {
+    // 已设置的选项名称，与 types.ts 中的字段名一致
+    pub fn specified(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.paper.is_some() {
+            names.push("paper");
+        }
+        if self.method.is_some() {
+            names.push("method");
+        }
+        if self.scale.is_some() {
+            names.push("scale");
+        }
+        if self.color_type.is_some() {
+            names.push("color_type");
+        }
+        if self.orientation.is_some() {
+            names.push("orientation");
+        }
+        if self.repeat.is_some() {
+            names.push("repeat");
+        }
+        if self.range.is_some() {
+            names.push("range");
+        }
+        names
+    }
+
+    // 打印份数，未设置时为 1
+    pub fn copies(&self) -> Result<u32, String> {
+        match self.repeat {
+            Some(0) => Err("打印份数必须大于 0".to_string()),
+            Some(repeat) => Ok(repeat),
+            None => Ok(1),
+        }
+    }
+
+    // 解析旧版前端拼接的 SumatraPDF 参数，例如 "-print-settings A4,simplex,fit,color,1x"
+    pub fn from_legacy(value: &str) -> Result<Self, String> {
+        let value = value.trim().trim_start_matches("-print-settings").trim().trim_matches(|c| c == '"' || c == '\'');
+        let mut settings = PrintSettings::default();
+        let mut pages = Vec::new();
+
+        for token in value.split(',').map(str::trim).filter(|token| !token.is_empty()) {
+            let lower = token.to_ascii_lowercase();
+            let paper = lower.strip_prefix("paper=").unwrap_or(&lower);
+            if let Some(paper) = Paper::parse(paper) {
+                settings.paper = Some(paper);
+                continue;
+            }
+            match lower.as_str() {
+                "simplex" => settings.method = Some(Method::Simplex),
+                "duplex" | "duplexlong" => settings.method = Some(Method::Duplex),
+                "duplexshort" => settings.method = Some(Method::Duplexshort),
+                "noscale" => settings.scale = Some(Scale::Noscale),
+                "shrink" => settings.scale = Some(Scale::Shrink),
+                "fit" => settings.scale = Some(Scale::Fit),
+                "color" => settings.color_type = Some(ColorType::Color),
+                "monochrome" => settings.color_type = Some(ColorType::Monochrome),
+                "portrait" => settings.orientation = Some(Orientation::Portrait),
+                "landscape" => settings.orientation = Some(Orientation::Landscape),
+                _ => {
+                    if let Some(repeat) = lower.strip_suffix('x').and_then(|n| n.parse().ok()) {
+                        settings.repeat = Some(repeat);
+                    } else if lower.chars().all(|c| c.is_ascii_digit() || c == '-') {
+                        pages.push(lower);
+                    } else {
+                        return Err(format!("无法识别的打印设置: {}", token));
+                    }
+                }
+            }
+        }
+
+        if !pages.is_empty() {
+            settings.range = Some(PageRange::List(pages.join(",")));
+        }
+        Ok(settings)
+    }
+}
+
+// 前端传入的打印设置：types.ts 中的 PrintSettings 对象，或旧版的 -print-settings 字符串
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum PrintSettingInput {
+    Settings(PrintSettings),
+    Legacy(String),
+}
+
+impl PrintSettingInput {
+    pub fn into_settings(self) -> Result<PrintSettings, String> {
+        match self {
+            PrintSettingInput::Settings(settings) => Ok(settings),
+            PrintSettingInput::Legacy(value) => PrintSettings::from_legacy(&value),
+        }
+    }
+}
+
+// 打印设置翻译为某个后端的命令行参数后的结果
+#[derive(Debug, Clone, Default)]
+pub struct TranslatedSettings {
+    pub args: Vec<String>,
+    // 该后端无法实现、不会生效的选项
+    pub unsupported: Vec<&'static str>,
+}
+
+// 追加在"打印成功"之后，提示哪些设置没有生效
+pub fn unsupported_note(unsupported: &[&str]) -> String {
+    if unsupported.is_empty() {
+        String::new()
+    } else {
+        format!(" (以下打印设置未生效: {})", unsupported.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_string() {
+        let settings = PrintSettings::from_legacy("-print-settings \"A4,simplex,fit,color,2x,1-3,5\"").unwrap();
+        assert_eq!(
+            settings,
+            PrintSettings {
+                paper: Some(Paper::A4),
+                method: Some(Method::Simplex),
+                scale: Some(Scale::Fit),
+                color_type: Some(ColorType::Color),
+                orientation: None,
+                repeat: Some(2),
+                range: Some(PageRange::List("1-3,5".to_string())),
+            }
+        );
+
+        let settings = PrintSettings::from_legacy("paper=Letter, DUPLEXLONG, noscale, monochrome, landscape").unwrap();
+        assert_eq!(settings.paper, Some(Paper::Letter));
+        assert_eq!(settings.method, Some(Method::Duplex));
+        assert_eq!(settings.scale, Some(Scale::Noscale));
+        assert_eq!(settings.color_type, Some(ColorType::Monochrome));
+        assert_eq!(settings.orientation, Some(Orientation::Landscape));
+
+        assert_eq!(PrintSettings::from_legacy("").unwrap(), PrintSettings::default());
+        assert_eq!(PrintSettings::from_legacy("-print-settings").unwrap(), PrintSettings::default());
+        assert!(PrintSettings::from_legacy("A4,bogus").is_err());
+        assert!(PrintSettings::from_legacy("paper=B5").is_err());
+
+        let waybill = Some(Paper::Custom { width_mm: 76, height_mm: 130 });
+        assert_eq!(PrintSettings::from_legacy("76x130mm,2x").unwrap().paper, waybill);
+        assert_eq!(PrintSettings::from_legacy("paper=Custom.76x130mm").unwrap().paper, waybill);
+        assert!(PrintSettings::from_legacy("paper=76xmm").is_err());
+    }
+
+    #[test]
+    fn custom_paper_size() {
+        assert_eq!(Paper::Custom { width_mm: 76, height_mm: 130 }.custom_size(), Ok(Some((76, 130))));
+        assert!(Paper::Custom { width_mm: 0, height_mm: 130 }.custom_size().is_err());
+        assert_eq!(Paper::A4.custom_size(), Ok(None));
+        assert_eq!(Paper::A4.name(), Some("A4"));
+        assert_eq!(Paper::Custom { width_mm: 76, height_mm: 130 }.name(), None);
+
+        let input: PrintSettingInput = serde_json::from_value(serde_json::json!({
+            "paper": { "custom": { "width_mm": 76, "height_mm": 130 } }
+        }))
+        .unwrap();
+        let settings = input.into_settings().unwrap();
+        assert_eq!(settings.paper, Some(Paper::Custom { width_mm: 76, height_mm: 130 }));
+        assert_eq!(serde_json::to_value(&settings).unwrap()["paper"]["custom"]["height_mm"], 130);
+    }
+
+    #[test]
+    fn page_range_normalization() {
+        assert_eq!(PageRange::Span { from: 2, to: 4 }.normalized().unwrap(), "2-4");
+        assert_eq!(PageRange::Span { from: 3, to: 3 }.normalized().unwrap(), "3");
+        assert_eq!(PageRange::List(" 1-3, 5 ,".to_string()).normalized().unwrap(), "1-3,5");
+        assert_eq!(PageRange::List("2-2,7".to_string()).normalized().unwrap(), "2,7");
+        for invalid in ["3-1", "0", "0-2", "a", "1,,2", "1-", ""] {
+            assert!(PageRange::List(invalid.to_string()).normalized().is_err(), "{}", invalid);
+        }
+        assert!(PageRange::Span { from: 0, to: 1 }.normalized().is_err());
+    }
+
+    #[test]
+    fn copies_default_to_one() {
+        assert_eq!(PrintSettings::default().copies(), Ok(1));
+        let settings = PrintSettings { repeat: Some(3), ..PrintSettings::default() };
+        assert_eq!(settings.copies(), Ok(3));
+        let settings = PrintSettings { repeat: Some(0), ..PrintSettings::default() };
+        assert!(settings.copies().is_err());
+    }
+
+    #[test]
+    fn input_is_object_or_legacy_string() {
+        let input: PrintSettingInput = serde_json::from_value(serde_json::json!({
+            "paper": "letter",
+            "method": "duplexshort",
+            "range": { "from": 2, "to": 4 }
+        }))
+        .unwrap();
+        let settings = input.into_settings().unwrap();
+        assert_eq!(settings.paper, Some(Paper::Letter));
+        assert_eq!(settings.method, Some(Method::Duplexshort));
+        assert_eq!(settings.range, Some(PageRange::Span { from: 2, to: 4 }));
+        assert_eq!(settings.specified(), vec!["paper", "method", "range"]);
+
+        let input: PrintSettingInput = serde_json::from_value(serde_json::json!({ "range": "1-3,5" })).unwrap();
+        assert_eq!(input.into_settings().unwrap().range, Some(PageRange::List("1-3,5".to_string())));
+
+        let input: PrintSettingInput = serde_json::from_value(serde_json::json!("-print-settings A5,shrink")).unwrap();
+        let settings = input.into_settings().unwrap();
+        assert_eq!(settings.paper, Some(Paper::A5));
+        assert_eq!(settings.scale, Some(Scale::Shrink));
+
+        let input: PrintSettingInput = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(input.into_settings().unwrap(), PrintSettings::default());
+
+        // 对象中的取值无效时不会退回到字符串格式
+        assert!(serde_json::from_value::<PrintSettingInput>(serde_json::json!({ "paper": "B5" })).is_err());
+        let input: PrintSettingInput = serde_json::from_value(serde_json::json!("B5")).unwrap();
+        assert!(input.into_settings().is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let settings = PrintSettings {
+            paper: Some(Paper::Tabloid),
+            method: Some(Method::Duplex),
+            scale: Some(Scale::Shrink),
+            color_type: Some(ColorType::Monochrome),
+            orientation: Some(Orientation::Portrait),
+            repeat: Some(2),
+            range: Some(PageRange::Span { from: 1, to: 2 }),
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["paper"], "tabloid");
+        assert_eq!(json["color_type"], "monochrome");
+        assert_eq!(serde_json::from_value::<PrintSettings>(json).unwrap(), settings);
+    }
+
+    #[test]
+    fn unsupported_note_lists_options() {
+        assert_eq!(unsupported_note(&[]), "");
+        assert_eq!(unsupported_note(&["orientation", "range"]), " (以下打印设置未生效: orientation, range)");
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+
+// 打印队列返回的时间，各平台格式不同，统一转为 UTC 毫秒时间戳和带时区的 ISO 8601 字符串
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timestamp(DateTime<FixedOffset>);
+
+impl Timestamp {
+    // UTC 毫秒时间戳，按本机时区显示
+    pub fn from_millis(millis: i64) -> Option<Self> {
+        let time = Utc.timestamp_millis_opt(millis).single()?;
+        Some(Self(time.with_timezone(&Local).fixed_offset()))
+    }
+
+    // ConvertTo-Json 把 DateTime 序列化为 "/Date(1700000000000)/"，PowerShell 2.0 之类的旧版本会带上 "+0800"
+    // 数字部分总是 UTC 毫秒，时区只影响显示
+    pub fn from_ps_date(value: &str) -> Option<Self> {
+        let body = value.trim().strip_prefix("/Date(")?.strip_suffix(")/")?;
+        let split = body.char_indices().skip(1).find(|(_, c)| *c == '+' || *c == '-').map(|(index, _)| index);
+        let (millis, zone) = match split {
+            Some(index) => body.split_at(index),
+            None => (body, ""),
+        };
+        let millis: i64 = millis.parse().ok()?;
+        if zone.is_empty() {
+            return Self::from_millis(millis);
+        }
+        let offset = parse_zone(zone)?;
+        Some(Self(Utc.timestamp_millis_opt(millis).single()?.with_timezone(&offset)))
+    }
+
+    // WMI 的 CIM_DATETIME，形如 "20240101120000.000000+480"，末尾是相对 UTC 的分钟数
+    pub fn from_cim(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.len() != 25 || !value.is_ascii() {
+            return None;
+        }
+        let local = NaiveDateTime::parse_from_str(&value[..14], "%Y%m%d%H%M%S").ok()?;
+        let micros: i64 = value[15..21].parse().ok()?;
+        let minutes: i32 = value[22..].parse().ok()?;
+        let minutes = match &value[21..22] {
+            "+" => minutes,
+            "-" => -minutes,
+            _ => return None,
+        };
+        let offset = FixedOffset::east_opt(minutes * 60)?;
+        let time = offset.from_local_datetime(&local).single()?;
+        Some(Self(time + Duration::microseconds(micros)))
+    }
+
+    // lpstat 的作业提交时间，按本机时区解释
+    pub fn from_cups(value: &str) -> Option<Self> {
+        Self::from_cups_in(value, &Local)
+    }
+
+    // 以 C locale 运行时形如 "Sat Oct 18 10:00:00 2026"，英文环境下形如 "Sat 18 Oct 2026 10:00:00 AM CST"
+    // 星期不参与计算，时区缩写有歧义，都忽略后按给定时区解释
+    fn from_cups_in<Tz: TimeZone>(value: &str, zone: &Tz) -> Option<Self> {
+        let mut parts: Vec<&str> = value.split_whitespace().collect();
+        if matches!(parts.first(), Some(first) if first.chars().all(|c| c.is_ascii_alphabetic())) {
+            parts.remove(0);
+        }
+        if let Some(last) = parts.last() {
+            if last.chars().all(|c| c.is_ascii_alphabetic()) && !matches!(*last, "AM" | "PM") {
+                parts.pop();
+            }
+        }
+        let value = parts.join(" ");
+        let local = ["%b %d %H:%M:%S %Y", "%d %b %Y %I:%M:%S %p", "%d %b %Y %H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(&value, format).ok())?;
+        let time = zone.from_local_datetime(&local).earliest()?;
+        Some(Self(time.fixed_offset()))
+    }
+
+    pub fn millis(&self) -> i64 {
+        self.0.timestamp_millis()
+    }
+
+    // 例如 "2024-01-01T12:00:00.000+08:00"
+    pub fn iso(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::Millis, false)
+    }
+}
+
+// "+0800" 或 "-0530"
+fn parse_zone(zone: &str) -> Option<FixedOffset> {
+    if zone.len() != 5 || !zone.is_ascii() {
+        return None;
+    }
+    let hours: i32 = zone[1..3].parse().ok()?;
+    let minutes: i32 = zone[3..].parse().ok()?;
+    let seconds = (hours * 60 + minutes) * 60;
+    match &zone[..1] {
+        "+" => FixedOffset::east_opt(seconds),
+        "-" => FixedOffset::west_opt(seconds),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beijing() -> FixedOffset {
+        FixedOffset::east_opt(8 * 3600).unwrap()
+    }
+
+    #[test]
+    fn ps_date() {
+        let time = Timestamp::from_ps_date("/Date(1700000000000)/").unwrap();
+        assert_eq!(time.millis(), 1_700_000_000_000);
+
+        let time = Timestamp::from_ps_date("/Date(1700000000000+0800)/").unwrap();
+        assert_eq!(time.millis(), 1_700_000_000_000);
+        assert_eq!(time.iso(), "2023-11-15T06:13:20.000+08:00");
+
+        let time = Timestamp::from_ps_date("/Date(-1000-0530)/").unwrap();
+        assert_eq!(time.millis(), -1000);
+        assert_eq!(time.iso(), "1969-12-31T18:29:59.000-05:30");
+
+        assert_eq!(Timestamp::from_ps_date("1700000000000"), None);
+        assert_eq!(Timestamp::from_ps_date("/Date(abc)/"), None);
+    }
+
+    #[test]
+    fn cim_datetime() {
+        let time = Timestamp::from_cim("20240101120000.000000+480").unwrap();
+        assert_eq!(time.iso(), "2024-01-01T12:00:00.000+08:00");
+        assert_eq!(time.millis(), 1_704_081_600_000);
+
+        let time = Timestamp::from_cim("20240101120000.250000-300").unwrap();
+        assert_eq!(time.iso(), "2024-01-01T12:00:00.250-05:00");
+        assert_eq!(time.millis(), 1_704_128_400_250);
+
+        assert_eq!(Timestamp::from_cim("2024010112****.000000+480"), None);
+        assert_eq!(Timestamp::from_cim(""), None);
+    }
+
+    #[test]
+    fn cups_date() {
+        let expected = 1_792_288_800_000;
+        for value in ["Sun Oct 18 10:00:00 2026", "Sun Oct  18 10:00:00 2026", "Sun 18 Oct 2026 10:00:00 AM CST"] {
+            let time = Timestamp::from_cups_in(value, &beijing()).unwrap();
+            assert_eq!(time.millis(), expected, "{}", value);
+            assert_eq!(time.iso(), "2026-10-18T10:00:00.000+08:00");
+        }
+        assert_eq!(Timestamp::from_cups_in("Sun 18 Oct 2026 10:00:00 PM", &beijing()).unwrap().millis(), expected + 12 * 3600 * 1000);
+        assert_eq!(Timestamp::from_cups_in("2026年10月18日 星期日 10时00分00秒", &beijing()), None);
+    }
+}
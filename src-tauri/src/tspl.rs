@@ -0,0 +1,181 @@
+use crate::barcode::{self, Symbology};
+use crate::bitmap::MonoBitmap;
+use crate::label::{LabelElement, LabelLayout};
+
+// 打印机内置的 24x24 点阵简体中文字库，同时包含 ASCII
+const DEFAULT_FONT: &str = "TSS24.BF2";
+const FONT_DOTS: u32 = 24;
+
+// 生成 TSPL/TSPL2 作业，文本按 GB18030 编码
+pub fn render_tspl(layout: &LabelLayout) -> Result<Vec<u8>, String> {
+    let mut tspl: Vec<u8> = Vec::new();
+    let font = layout.font.as_deref().unwrap_or(DEFAULT_FONT);
+
+    push_line(&mut tspl, &format!("SIZE {} mm,{} mm", layout.width, layout.height));
+    push_line(&mut tspl, &format!("GAP {} mm,0 mm", layout.gap));
+    push_line(&mut tspl, "DIRECTION 1");
+    push_line(&mut tspl, "CLS");
+
+    for element in &layout.elements {
+        match element {
+            LabelElement::Text { x, y, content, font_size, bold } => {
+                // TSS24 只能整数倍放大
+                let scale = ((layout.dots(*font_size) as f32 / FONT_DOTS as f32).round() as u32).clamp(1, 10);
+                let (x, y) = (layout.dots(*x), layout.dots(*y));
+                push_text(&mut tspl, x, y, font, scale, content);
+                // 错开一个点重复打印模拟粗体
+                if *bold {
+                    push_text(&mut tspl, x + 1, y, font, scale, content);
+                }
+            }
+            LabelElement::Barcode { x, y, data, symbology, height, module_width, human_readable } => {
+                let (x, y) = (layout.dots(*x), layout.dots(*y));
+                let module = module_width.unwrap_or(if symbology.is_linear() { 2 } else { 4 }).clamp(1, 10);
+                let height = layout.dots(*height).max(1);
+                match symbology {
+                    // "128" 由打印机自动切换 Code128 字符集
+                    Symbology::Code128 => push_line(&mut tspl, &format!(
+                        "BARCODE {},{},\"128\",{},{},0,{},{},{}",
+                        x,
+                        y,
+                        height,
+                        *human_readable as u8,
+                        module,
+                        module,
+                        quote(data)
+                    )),
+                    Symbology::Qrcode => push_line(&mut tspl, &format!("QRCODE {},{},M,{},A,0,{}", x, y, module, quote(data))),
+                    // GS1-128、DataMatrix、PDF417 在各型号上的支持不一致，统一用位图输出
+                    _ => {
+                        let matrix = barcode::encode(*symbology, data)?;
+                        let row_height = if symbology.is_linear() { height } else { module };
+                        push_bitmap(&mut tspl, x, y, &matrix.to_bitmap(module, row_height));
+                    }
+                }
+            }
+            LabelElement::Qrcode { x, y, data, module_size } => {
+                push_line(&mut tspl, &format!(
+                    "QRCODE {},{},M,{},A,0,{}",
+                    layout.dots(*x),
+                    layout.dots(*y),
+                    module_size.unwrap_or(4).clamp(1, 10),
+                    quote(data)
+                ));
+            }
+            LabelElement::Box { x, y, width, height, thickness } => {
+                let (x, y) = (layout.dots(*x), layout.dots(*y));
+                push_line(&mut tspl, &format!(
+                    "BOX {},{},{},{},{}",
+                    x,
+                    y,
+                    x + layout.dots(*width),
+                    y + layout.dots(*height),
+                    layout.dots(*thickness).max(1)
+                ));
+            }
+            LabelElement::Line { x1, y1, x2, y2, thickness } => {
+                let thickness = layout.dots(*thickness).max(1);
+                let (left, top) = (layout.dots(x1.min(*x2)), layout.dots(y1.min(*y2)));
+                let width = layout.dots((x2 - x1).abs());
+                let height = layout.dots((y2 - y1).abs());
+                if width == 0 || height == 0 {
+                    push_line(&mut tspl, &format!("BAR {},{},{},{}", left, top, width.max(thickness), height.max(thickness)));
+                } else {
+                    push_line(&mut tspl, &format!(
+                        "DIAGONAL {},{},{},{},{}",
+                        layout.dots(*x1),
+                        layout.dots(*y1),
+                        layout.dots(*x2),
+                        layout.dots(*y2),
+                        thickness
+                    ));
+                }
+            }
+            LabelElement::Image { x, y, data, width } => {
+                let bitmap = MonoBitmap::from_base64(data, width.map(|w| layout.dots(w)))?;
+                push_bitmap(&mut tspl, layout.dots(*x), layout.dots(*y), &bitmap);
+            }
+        }
+    }
+
+    push_line(&mut tspl, &format!("PRINT {},1", layout.copies.max(1)));
+    Ok(tspl)
+}
+
+fn push_line(tspl: &mut Vec<u8>, line: &str) {
+    tspl.extend_from_slice(line.as_bytes());
+    tspl.extend_from_slice(b"\r\n");
+}
+
+fn push_bitmap(tspl: &mut Vec<u8>, x: u32, y: u32, bitmap: &MonoBitmap) {
+    tspl.extend_from_slice(format!("BITMAP {},{},{},{},0,", x, y, bitmap.bytes_per_row(), bitmap.height).as_bytes());
+    // TSPL 的位图中 0 表示黑点，与 MonoBitmap 相反
+    tspl.extend(bitmap.data.iter().map(|byte| !byte));
+    tspl.extend_from_slice(b"\r\n");
+}
+
+fn push_text(tspl: &mut Vec<u8>, x: u32, y: u32, font: &str, scale: u32, content: &str) {
+    let line = format!("TEXT {},{},\"{}\",0,{},{},{}", x, y, font, scale, scale, quote(content));
+    let (encoded, _, _) = encoding_rs::GB18030.encode(&line);
+    tspl.extend_from_slice(&encoded);
+    tspl.extend_from_slice(b"\r\n");
+}
+
+// 字符串参数用双引号包裹，内部的双引号写作 \["]，换行替换为空格
+fn quote(value: &str) -> String {
+    let escaped: String = value
+        .chars()
+        .map(|c| match c {
+            '"' => "\\[\"]".to_string(),
+            '\r' | '\n' => " ".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_commands() {
+        let layout: LabelLayout = serde_json::from_value(serde_json::json!({
+            "width": 40,
+            "height": 30,
+            "elements": [
+                { "type": "text", "x": 5, "y": 5, "content": "中\"A", "bold": true },
+                { "type": "barcode", "x": 0, "y": 0, "data": "AB12", "height": 10 },
+                { "type": "qrcode", "x": 0, "y": 0, "data": "hi" },
+                { "type": "box", "x": 0, "y": 0, "width": 10, "height": 5 },
+                { "type": "line", "x1": 0, "y1": 5, "x2": 10, "y2": 5 },
+                { "type": "line", "x1": 0, "y1": 10, "x2": 10, "y2": 0 }
+            ]
+        }))
+        .unwrap();
+
+        let expected: Vec<u8> = [
+            &b"SIZE 40 mm,30 mm\r\nGAP 2 mm,0 mm\r\nDIRECTION 1\r\nCLS\r\n"[..],
+            // 文本按 GB18030 编码，"中" 为 D6 D0
+            b"TEXT 40,40,\"TSS24.BF2\",0,1,1,\"\xD6\xD0\\[\"]A\"\r\n",
+            b"TEXT 41,40,\"TSS24.BF2\",0,1,1,\"\xD6\xD0\\[\"]A\"\r\n",
+            b"BARCODE 0,0,\"128\",80,0,0,2,2,\"AB12\"\r\n",
+            b"QRCODE 0,0,M,4,A,0,\"hi\"\r\n",
+            b"BOX 0,0,80,40,2\r\n",
+            b"BAR 0,40,80,2\r\n",
+            b"DIAGONAL 0,80,80,0,2\r\n",
+            b"PRINT 1,1\r\n",
+        ]
+        .concat();
+        assert_eq!(render_tspl(&layout).unwrap(), expected);
+    }
+
+    #[test]
+    fn bitmap_is_inverted() {
+        let mut bitmap = MonoBitmap::new(9, 1);
+        bitmap.set(0, 0, true);
+        let mut tspl = Vec::new();
+        push_bitmap(&mut tspl, 1, 2, &bitmap);
+        assert_eq!(tspl, b"BITMAP 1,2,2,1,0,\x7F\xFF\r\n".to_vec());
+    }
+}
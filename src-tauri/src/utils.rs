@@ -1,3 +1,4 @@
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::fs;
 
@@ -13,3 +14,18 @@ pub fn get_version_from_config() -> Result<String, String> {
         .map(|s| s.to_string())
         .ok_or_else(|| "Version not found in config".to_string())
 }
+
+// 解析 JSON 列表输出：PowerShell ConvertTo-Json 只有一条结果时不带数组包裹，没有结果时输出为空
+pub fn parse_json_list<T: DeserializeOwned>(output: &str) -> Result<Vec<T>, String> {
+    let trimmed = output.trim();
+    if trimmed.is_empty() {
+        return Ok(Vec::new());
+    }
+    if trimmed.starts_with('[') {
+        serde_json::from_str(trimmed).map_err(|err| format!("解析 JSON 失败: {}", err))
+    } else {
+        serde_json::from_str::<T>(trimmed)
+            .map(|item| vec![item])
+            .map_err(|err| format!("解析 JSON 失败: {}", err))
+    }
+}
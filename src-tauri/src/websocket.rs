@@ -1,19 +1,43 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
-use tokio_tungstenite::accept_async;
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
 use tokio_tungstenite::tungstenite::protocol::Message;
 use tokio_tungstenite::WebSocketStream;
 use futures_util::{stream::StreamExt, SinkExt};
 use tauri::State;
 use tauri::AppHandle;
 use tauri::Manager; 
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use crate::dispatcher::SharedDispatcher;
+use crate::journal::SharedJournal;
+use crate::monitor::SharedPrinterHealth;
 
 // 定义类型
 pub type SharedWebSocket = Arc<Mutex<Option<WebSocketStream<TcpStream>>>>;
 
+// 当前连接是否可以执行修改作业的命令，只在持有 SharedWebSocket 的锁时读写，与连接保持一致
+static CONNECTION_TRUSTED: AtomicBool = AtomicBool::new(false);
+
+// 本应用自己的页面 (Windows 上为 https://tauri.localhost，其他系统为 tauri://localhost)
+const TRUSTED_ORIGINS: [&str; 2] = ["tauri://localhost", "https://tauri.localhost"];
+
+// 浏览器发起的连接总会带上 Origin 且无法伪造，本机程序的连接没有 Origin
+// 任意网页都能连接 127.0.0.1，只有本机程序和本应用的页面可以执行修改作业的命令
+fn origin_is_trusted(origin: Option<&str>) -> bool {
+    match origin {
+        Some(origin) => TRUSTED_ORIGINS.contains(&origin),
+        None => true,
+    }
+}
+
 // 启动 WebSocket 服务器
+// 握手回调的返回类型由 tungstenite 的 Callback 规定，无法缩小其错误类型
+#[allow(clippy::result_large_err)]
 pub async fn start_websocket_server(app_handle: AppHandle, ws_conn: SharedWebSocket) {
     let addr = "127.0.0.1:14528";
     let listener = TcpListener::bind(addr).await.expect("Failed to bind");
@@ -21,11 +45,18 @@ pub async fn start_websocket_server(app_handle: AppHandle, ws_conn: SharedWebSoc
     println!("WebSocket server listening on {}", addr);
 
     while let Ok((stream, _)) = listener.accept().await {
-        let ws_stream = accept_async(stream).await.expect("Error during WebSocket handshake");
+        let mut origin = None;
+        let ws_stream = accept_hdr_async(stream, |request: &Request, response: Response| {
+            origin = request.headers().get("origin").and_then(|value| value.to_str().ok()).map(str::to_string);
+            Ok(response)
+        })
+        .await
+        .expect("Error during WebSocket handshake");
         {
             let mut guard = ws_conn.lock().await;
             *guard = Some(ws_stream);
-            println!("WebSocket 连接已存储");
+            CONNECTION_TRUSTED.store(origin_is_trusted(origin.as_deref()), Ordering::SeqCst);
+            println!("WebSocket 连接已存储, Origin: {:?}", origin);
         }
         // 这里会阻塞
         tokio::spawn(handle_connection(ws_conn.clone(), app_handle.clone()));
@@ -49,24 +80,139 @@ pub async fn send_message_to_websocket(message: String, ws_conn: State<'_, Share
     }
 }
 
+// 主动推送给 WebSocket 客户端的通知，没有连接时直接丢弃
+pub async fn notify(ws_conn: SharedWebSocket, message: String) {
+    let mut ws_stream_guard = ws_conn.lock().await;
+    if let Some(ws_stream) = ws_stream_guard.as_mut() {
+        if let Err(e) = ws_stream.send(Message::Text(message)).await {
+            eprintln!("发送通知错误: {:?}", e);
+        }
+    }
+}
+
+// 命令协议的版本，字段含义变化时递增
+const RPC_VERSION: u32 = 1;
+
+// WebSocket 命令请求，例如 {"type": "rpc", "version": 1, "id": 1, "command": "retry_dead_letter", "args": {"job_id": "..."}}
+// 只有 type 为 rpc 的消息才按命令处理，其他消息即使带有 command 字段也按原来的方式转发给前端
+#[derive(Deserialize)]
+struct CommandRequest {
+    version: u32,
+    command: String,
+    #[serde(default)]
+    args: Value,
+}
+
+// 命令的返回结果，id 与请求中的 id 相同
+#[derive(Serialize)]
+struct CommandResponse {
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    version: u32,
+    id: Value,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl CommandResponse {
+    fn new(id: Value, result: Result<Value, String>) -> Self {
+        let (ok, data, error) = match result {
+            Ok(data) => (true, Some(data), None),
+            Err(error) => (false, None, Some(error)),
+        };
+        CommandResponse {
+            message_type: "rpc_result",
+            version: RPC_VERSION,
+            id,
+            ok,
+            data,
+            error,
+        }
+    }
+}
+
+// 解析命令消息，不是 rpc 消息时返回 None；格式或版本不对的 rpc 消息返回错误，连同请求 id 一起回复
+fn parse_command(text: &str) -> Option<(Value, Result<CommandRequest, String>)> {
+    let message: Value = serde_json::from_str(text).ok()?;
+    if message.get("type").and_then(Value::as_str) != Some("rpc") {
+        return None;
+    }
+    let id = message.get("id").cloned().unwrap_or(Value::Null);
+    let request = serde_json::from_value::<CommandRequest>(message)
+        .map_err(|e| format!("无效的命令: {}", e))
+        .and_then(|request| {
+            if request.version == RPC_VERSION {
+                Ok(request)
+            } else {
+                Err(format!("不支持的命令版本: {}，当前版本: {}", request.version, RPC_VERSION))
+            }
+        });
+    Some((id, request))
+}
+
+// 只读取状态的命令，任何连接都可以执行
+fn is_read_only(command: &str) -> bool {
+    matches!(command, "get_print_queue" | "get_queued_job" | "get_dead_letters" | "get_printer_states")
+}
+
+// 处理 WebSocket 命令，不是命令消息时返回 None，按原来的方式转发给前端
+fn route_command(app_handle: &AppHandle, text: &str, trusted: bool) -> Option<String> {
+    let (id, request) = parse_command(text)?;
+    let result = request.and_then(|request| {
+        println!("收到 WebSocket 命令: {}", request.command);
+        if !trusted && !is_read_only(&request.command) {
+            return Err(format!("网页连接不能执行该命令: {}", request.command));
+        }
+        run_command(app_handle, &request.command, &request.args)
+    });
+    serde_json::to_string(&CommandResponse::new(id, result)).ok()
+}
+
+fn run_command(app_handle: &AppHandle, command: &str, args: &Value) -> Result<Value, String> {
+    let journal = app_handle.state::<SharedJournal>();
+    let dispatcher = app_handle.state::<SharedDispatcher>();
+    let data = match command {
+        "get_print_queue" => serde_json::to_value(journal.list()),
+        "get_queued_job" => serde_json::to_value(journal.get(&job_id_arg(args)?)),
+        "get_dead_letters" => serde_json::to_value(dispatcher.dead_letters()),
+        "retry_dead_letter" => serde_json::to_value(dispatcher.retry_dead_letter(&job_id_arg(args)?)?),
+        "discard_dead_letter" => serde_json::to_value(dispatcher.discard_dead_letter(&job_id_arg(args)?)?),
+        "cancel_queued_job" => serde_json::to_value(dispatcher.cancel(&job_id_arg(args)?)?),
+        "get_printer_states" => serde_json::to_value(app_handle.state::<SharedPrinterHealth>().states()),
+        _ => return Err(format!("未知命令: {}", command)),
+    };
+    data.map_err(|e| e.to_string())
+}
+
+fn job_id_arg(args: &Value) -> Result<String, String> {
+    args.get("job_id")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "缺少参数 job_id".to_string())
+}
+
 fn send_message(app_handle: AppHandle, message: String) {
     app_handle.emit_all("websocket-message", message).unwrap();
 }
 
 async fn handle_connection(ws_conn: SharedWebSocket, app_handle: AppHandle) {
     loop {
-        let message = {
+        let (message, trusted) = {
             let mut ws_stream_guard = ws_conn.lock().await;
             if let Some(ws_stream) = ws_stream_guard.as_mut() {
                 let (_write, mut read) = ws_stream.split();
 
-                tokio::select! {
+                let message = tokio::select! {
                     message = read.next() => message,
                     _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {
                         println!("等待消息超时，释放锁");
                         None
                     }
-                }
+                };
+                (message, CONNECTION_TRUSTED.load(Ordering::SeqCst))
             } else {
                 // 没有活跃的连接，等待一段时间后重试
                 drop(ws_stream_guard);
@@ -78,11 +224,18 @@ async fn handle_connection(ws_conn: SharedWebSocket, app_handle: AppHandle) {
             Some(Ok(msg)) => {
                 // println!("收到消息: {:?}", msg);
                 if let Ok(message_str) = msg.to_text() {
-                    send_message(app_handle.clone(), message_str.to_string());
+                    // 命令消息直接返回执行结果，其他消息转发给前端并回声
+                    let reply = match route_command(&app_handle, message_str, trusted) {
+                        Some(response) => response,
+                        None => {
+                            send_message(app_handle.clone(), message_str.to_string());
+                            "回声: ".to_string() + message_str
+                        }
+                    };
 
                     let mut ws_stream_guard = ws_conn.lock().await;
                     if let Some(ws_stream) = ws_stream_guard.as_mut() {
-                        if let Err(e) = ws_stream.send(Message::Text("回声: ".to_string() + message_str)).await {
+                        if let Err(e) = ws_stream.send(Message::Text(reply)).await {
                             eprintln!("发送消息错误: {:?}", e);
                             break;
                         }
@@ -104,3 +257,54 @@ async fn handle_connection(ws_conn: SharedWebSocket, app_handle: AppHandle) {
     let mut ws_stream_guard = ws_conn.lock().await;
     *ws_stream_guard = None;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn only_rpc_messages_are_commands() {
+        assert!(parse_command("hello").is_none());
+        assert!(parse_command("[1, 2]").is_none());
+        // 业务消息中恰好有 command 字段，仍然转发给前端
+        assert!(parse_command(r#"{"command": "print", "data": "..."}"#).is_none());
+        assert!(parse_command(r#"{"type": "order", "command": "get_print_queue"}"#).is_none());
+
+        let (id, request) = parse_command(r#"{"type": "rpc", "version": 1, "id": 7, "command": "retry_dead_letter", "args": {"job_id": "1-0"}}"#).unwrap();
+        let request = request.unwrap();
+        assert_eq!(id, json!(7));
+        assert_eq!(request.command, "retry_dead_letter");
+        assert_eq!(job_id_arg(&request.args).unwrap(), "1-0");
+    }
+
+    #[test]
+    fn malformed_rpc_is_answered_with_error() {
+        let (id, request) = parse_command(r#"{"type": "rpc", "version": 2, "id": "a", "command": "get_print_queue"}"#).unwrap();
+        assert_eq!(id, json!("a"));
+        assert!(request.err().unwrap().contains("不支持的命令版本"));
+
+        let (id, request) = parse_command(r#"{"type": "rpc", "version": 1}"#).unwrap();
+        assert_eq!(id, Value::Null);
+        assert!(request.is_err());
+
+        let response = serde_json::to_value(CommandResponse::new(json!(3), Err("未知命令: x".to_string()))).unwrap();
+        assert_eq!(response, json!({"type": "rpc_result", "version": 1, "id": 3, "ok": false, "error": "未知命令: x"}));
+    }
+
+    #[test]
+    fn only_local_programs_and_app_pages_are_trusted() {
+        assert!(origin_is_trusted(None));
+        assert!(origin_is_trusted(Some("tauri://localhost")));
+        assert!(origin_is_trusted(Some("https://tauri.localhost")));
+        assert!(!origin_is_trusted(Some("https://example.com")));
+        assert!(!origin_is_trusted(Some("http://localhost:8080")));
+        assert!(!origin_is_trusted(Some("null")));
+
+        assert!(is_read_only("get_print_queue"));
+        assert!(is_read_only("get_printer_states"));
+        for command in ["retry_dead_letter", "discard_dead_letter", "cancel_queued_job", "unknown"] {
+            assert!(!is_read_only(command), "{}", command);
+        }
+    }
+}
@@ -2,37 +2,115 @@ use std::{sync::mpsc};
 use std::thread;
 
 use std::env;
-use tauri::api::process::Command;
-use tauri::api::Error as TauriError;
-use tauri::api::process::Output;
-use std::path::{ PathBuf};
-use crate::{declare::PrintOptions, fsys::remove_file};
-use std::str::from_utf8;
+use std::path::PathBuf;
+use serde::Deserialize;
+use crate::{backend::PrinterBackend, declare::{JobStatus, PrintJob, PrintOptions, Printer, PrinterState}, fsys::remove_file};
+use crate::settings::{ColorType, Method, PrintSettings, Scale, TranslatedSettings};
+use crate::journal::now_millis;
+use crate::utils::parse_json_list;
+use crate::timestamp::Timestamp;
+use crate::command::{decode_output, hidden_command, job_id, path_arg, ps_quote, run_powershell, PsCommand};
+
+// Get-Printer | ConvertTo-Json 输出的打印机字段
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PsPrinter {
+    name: String,
+    driver_name: Option<String>,
+    job_count: Option<u32>,
+    print_processor: Option<String>,
+    port_name: Option<String>,
+    share_name: Option<String>,
+    computer_name: Option<String>,
+    printer_status: Option<u32>,
+    shared: Option<bool>,
+    #[serde(rename = "Type")]
+    printer_type: Option<u32>,
+    priority: Option<u32>,
+}
+
+impl From<PsPrinter> for Printer {
+    fn from(item: PsPrinter) -> Self {
+        Printer {
+            driver_name: item.driver_name.unwrap_or_default(),
+            job_count: item.job_count.unwrap_or_default(),
+            print_processor: item.print_processor.unwrap_or_default(),
+            port_name: item.port_name.unwrap_or_default(),
+            share_name: item.share_name.unwrap_or_default(),
+            computer_name: item.computer_name.unwrap_or_default(),
+            printer_status: item.printer_status.unwrap_or_default(),
+            shared: item.shared.unwrap_or_default(),
+            printer_type: item.printer_type.unwrap_or_default(),
+            priority: item.priority.unwrap_or_default(),
+            state: item.printer_status.map(PrinterState::from_windows_status).unwrap_or_default(),
+            ..Printer::new(&item.name)
+        }
+    }
+}
+
+// Get-PrintJob | ConvertTo-Json 输出的作业字段
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PsPrintJob {
+    id: u32,
+    printer_name: Option<String>,
+    computer_name: Option<String>,
+    datatype: Option<String>,
+    document_name: Option<String>,
+    user_name: Option<String>,
+    size: Option<u64>,
+    job_time: Option<u64>,
+    pages_printed: Option<u32>,
+    total_pages: Option<u32>,
+    position: Option<u32>,
+    priority: Option<u32>,
+    submitted_time: Option<String>,
+    job_status: Option<u32>,
+}
+
+impl PsPrintJob {
+    fn into_job(self, printer_name: &str) -> PrintJob {
+        let printer_name = self.printer_name.unwrap_or_else(|| printer_name.to_string());
+        PrintJob {
+            job_status: self.job_status.map(JobStatus::from_code),
+            computer_name: self.computer_name.unwrap_or_default(),
+            data_type: self.datatype.unwrap_or_default(),
+            document_name: self.document_name.unwrap_or_default(),
+            size: self.size.unwrap_or_default(),
+            job_time: self.job_time.unwrap_or_default(),
+            pages_printed: self.pages_printed.unwrap_or_default(),
+            total_pages: self.total_pages.unwrap_or_default(),
+            position: self.position.unwrap_or_default(),
+            priority: self.priority.unwrap_or_default(),
+            username: self.user_name.unwrap_or_default(),
+            ..PrintJob::new(&printer_name, &self.id.to_string())
+                .with_submitted(self.submitted_time.as_deref().and_then(Timestamp::from_ps_date))
+        }
+    }
+}
+
+const PRINTER_FIELDS: &str = "Name, DriverName, JobCount, PrintProcessor, PortName, ShareName, ComputerName, PrinterStatus, Shared, Type, Priority";
+const JOB_FIELDS: &str = "Id, DocumentName, TotalPages, Position, Size, UserName, PagesPrinted, JobTime, ComputerName, Datatype, PrinterName, Priority, SubmittedTime, JobStatus";
 
 /**
  * Get printers by name on windows using powershell
  */
-pub fn get_printers_by_name(printername: String) -> String {
-    let output = Command::new("powershell")
-        .args([
-            "Get-Printer -Name \"", 
-            &printername, 
-            "\" | Select-Object Name, DriverName, JobCount, PrintProcessor, PortName, ShareName, ComputerName, PrinterStatus, Shared, Type, Priority | ConvertTo-Json"
-        ])
-        .output()
-        .unwrap();
-    output.stdout
+pub fn get_printers_by_name(printername: String) -> Result<Option<Printer>, String> {
+    let script = PsCommand::new("Get-Printer")
+        .param("Name", &printername)
+        .pipe(&format!("Select-Object {}", PRINTER_FIELDS))
+        .pipe("ConvertTo-Json")
+        .build();
+    let stdout = run_powershell(&script)?;
+    let printers: Vec<PsPrinter> = parse_json_list(&stdout)?;
+    Ok(printers.into_iter().next().map(Printer::from))
 }
 
-pub fn get_printers() -> String {
+pub fn get_printers() -> Result<Vec<Printer>, String> {
     // Create a channel for communication
     let (sender, receiver) = mpsc::channel();
     println!("Detected Windows 8 or higher");
-    let command = 
-        vec![
-            "-Command",
-            "Get-Printer | Select-Object Name, DriverName, JobCount, PrintProcessor, PortName, ShareName, ComputerName, PrinterStatus, Shared, Type, Priority | ConvertTo-Json"
-        ];
+    let script = format!("Get-Printer | Select-Object {} | ConvertTo-Json", PRINTER_FIELDS);
   
     
     // Spawn a new thread to execute the command
@@ -40,189 +118,406 @@ pub fn get_printers() -> String {
         println!("Spawned thread to execute PowerShell command.");
 
         // Execute the PowerShell command
-        let output = Command::new("powershell")
-            .args(&command)
-            .output();
-        
-        match output {
-            Ok(output) => {
-                println!("PowerShell command executed successfully.");
-
-               let stdout_string = String::from_utf8_lossy(output.stdout.as_bytes()).to_string();
-                println!("Command output: {}", stdout_string);
-                
-                if let Err(e) = sender.send(stdout_string) {
-                    println!("Failed to send output through channel: {:?}", e);
-                }
-            }
-            Err(e) => {
-                println!("Failed to execute PowerShell command: {:?}", e);
-                if let Err(send_err) = sender.send(String::new()) {
-                    println!("Failed to send error through channel: {:?}", send_err);
-                }
-            }
+        let output = run_powershell(&script);
+        if let Ok(stdout_string) = &output {
+            println!("Command output: {}", stdout_string);
+        }
+
+        if let Err(e) = sender.send(output) {
+            println!("Failed to send output through channel: {:?}", e);
         }
     });
 
     println!("Main thread is doing other non-blocking work.");
 
     // Receive the result from the spawned thread
-    match receiver.recv() {
+    let stdout = match receiver.recv() {
         Ok(res) => {
             println!("Successfully received result from the spawned thread.");
-            res
+            res?
         }
         Err(e) => {
             println!("Failed to receive result from the spawned thread: {:?}", e);
-            String::new()
+            return Err(format!("Failed to receive result from the spawned thread: {:?}", e));
+        }
+    };
+
+    let printers: Vec<PsPrinter> = parse_json_list(&stdout)?;
+    Ok(printers.into_iter().map(Printer::from).collect())
+}
+
+
+// 打印设置翻译为 SumatraPDF 的 -print-settings，SumatraPDF 无法指定纸张方向
+pub fn sumatra_print_settings(settings: &PrintSettings) -> Result<TranslatedSettings, String> {
+    let mut items = Vec::new();
+    let mut unsupported = Vec::new();
+
+    if let Some(range) = &settings.range {
+        items.push(range.normalized()?);
+    }
+    if let Some(paper) = settings.paper {
+        match paper.name() {
+            Some(name) => items.push(format!("paper={}", name)),
+            None => unsupported.push("paper"),
         }
     }
+    if let Some(method) = settings.method {
+        items.push(match method {
+            Method::Simplex => "simplex",
+            Method::Duplex => "duplex",
+            Method::Duplexshort => "duplexshort",
+        }.to_string());
+    }
+    if let Some(scale) = settings.scale {
+        items.push(match scale {
+            Scale::Noscale => "noscale",
+            Scale::Shrink => "shrink",
+            Scale::Fit => "fit",
+        }.to_string());
+    }
+    if let Some(color_type) = settings.color_type {
+        items.push(match color_type {
+            ColorType::Color => "color",
+            ColorType::Monochrome => "monochrome",
+        }.to_string());
+    }
+    if settings.orientation.is_some() {
+        unsupported.push("orientation");
+    }
+    if settings.repeat.is_some() {
+        items.push(format!("{}x", settings.copies()?));
+    }
+
+    let args = if items.is_empty() {
+        Vec::new()
+    } else {
+        vec!["-print-settings".to_string(), items.join(",")]
+    };
+    Ok(TranslatedSettings { args, unsupported })
 }
 
+// SumatraPDF 的命令行参数，打印机名称和文件路径各自作为独立参数
+pub fn sumatra_args(options: &PrintOptions) -> Result<Vec<String>, String> {
+    let mut args = vec!["-print-to".to_string(), options.id.trim().trim_matches('"').to_string()];
+    args.extend(sumatra_print_settings(&options.print_setting)?.args);
+    args.push(path_arg(&options.path));
+    Ok(args)
+}
 
-pub fn print_pdf(options: PrintOptions) -> Result<String, String> {
-    // 获取临时目录路径
-    let dir: PathBuf = env::temp_dir();
-    println!("临时目录: {}", dir.display());
+// 启动时释放到临时目录的 SumatraPDF
+pub fn sumatra_path() -> PathBuf {
+    env::temp_dir().join("sm.exe")
+}
 
+// 打印服务记录的提交时间可能来自打印服务器，与本机时钟不完全一致，往前放宽一点
+const SUBMIT_SLACK_MILLIS: u64 = 2000;
+
+// SumatraPDF 不返回作业 ID，只能按提交者和提交时间在打印队列中找回刚提交的作业
+// 生成 $since 变量：开始打印的本地时间，started_at 为毫秒时间戳
+pub fn submitted_since(started_at: u64) -> String {
+    format!(
+        "$since = (New-Object DateTime 1970, 1, 1, 0, 0, 0, ([DateTimeKind]::Utc)).AddMilliseconds({}).ToLocalTime()",
+        started_at.saturating_sub(SUBMIT_SLACK_MILLIS)
+    )
+}
+
+// 当前用户在开始打印之后提交到该打印机的作业中，ID 最大的一个
+fn spooled_job_script(printer_name: &str, started_at: u64) -> String {
+    let query = PsCommand::new("Get-PrintJob")
+        .param("PrinterName", printer_name)
+        .pipe("Where-Object { $_.UserName -eq $env:USERNAME -and $_.SubmittedTime -ge $since }")
+        .pipe("Sort-Object Id")
+        .pipe("Select-Object -Last 1 -ExpandProperty Id")
+        .build();
+    format!("{}; {}", submitted_since(started_at), query)
+}
+
+// 作业可能已经打印完并离开队列，此时返回 None
+fn find_spooled_job(printer_name: &str, started_at: u64) -> Option<String> {
+    match run_powershell(&spooled_job_script(printer_name, started_at)) {
+        Ok(output) => Some(output.trim().to_string()).filter(|id| !id.is_empty()),
+        Err(e) => {
+            println!("查找打印作业失败: {}", e);
+            None
+        }
+    }
+}
+
+// 提交打印任务，返回打印队列中的作业 ID，找不到作业时返回空字符串
+pub fn print_pdf(options: PrintOptions) -> Result<String, String> {
     // 构建打印命令
-    let print_arg = format!("-print-to {}", options.id);
-    let shell_command = format!("{}sm {} {} {}", dir.display(), print_arg, options.print_setting, options.path);
-    println!("生成的命令: {}", shell_command);
+    let sumatra = sumatra_path();
+    let args = sumatra_args(&options)?;
+    let started_at = now_millis();
+    println!("生成的命令: {} {:?}", sumatra.display(), args);
 
     // 执行命令
-    let output = Command::new("powershell")
-        .args(&["-Command", &shell_command]) // 使用 args 方法传递多个参数
+    let output = hidden_command(&sumatra)
+        .args(&args)
         .output()
         .map_err(|e| format!("执行命令失败: {}", e))?;
 
     // 根据命令执行结果返回相应的信息
     if output.status.success() {
         println!("打印成功");
+        let job_id = find_spooled_job(options.id.trim().trim_matches('"'), started_at).unwrap_or_default();
+        println!("打印队列作业 ID: {}", job_id);
+        // 作业已经提交，删除失败只记录日志，返回错误会被当作打印失败而重试
         if options.remove_after_print {
-            remove_file(&options.path).map_err(|e| format!("删除文件失败: {}", e))?;
-            println!("文件已删除: {}", options.path);
+            match remove_file(&options.path) {
+                Ok(_) => println!("文件已删除: {}", options.path),
+                Err(e) => println!("删除文件失败: {}", e),
+            }
         }
-        Ok("Windows-打印成功".to_string())
+        Ok(job_id)
     } else {
-        let error_message = String::from_utf8_lossy(output.stderr.as_bytes()); // 使用 as_bytes() 转换为 &[u8]
+        let error_message = decode_output(&output.stderr);
         eprintln!("打印失败: {}", error_message);
         Err(format!("Windows-打印失败: {}", error_message))
     }
 }
 
- pub fn get_jobs(printer_name: String) -> String {
-    use std::process::Command;
-
-    let command = format!(
-        "Get-PrintJob -PrinterName \"{}\" | Select-Object DocumentName,SubmittedTime,UserName,PrinterName| ConvertTo-Json",
-        printer_name
-    );
-
-    let output = Command::new("powershell")
-        .arg("-Command")
-        .arg(command)
-        .output()
-        .map_err(|e| e.to_string());
-
-    match output {
-        Ok(output_data) => {
-            if output_data.status.success() {
-                from_utf8(&output_data.stdout)
-                    .unwrap_or("Error converting output to UTF-8")
-                    .to_string()
-            } else {
-                let error_message = from_utf8(&output_data.stderr)
-                    .unwrap_or("Error converting error output to UTF-8")
-                    .to_string();
-                format!("Command failed with error: {}", error_message)
-            }
-        }
-        Err(error) => format!("Failed to execute PowerShell command: {}", error)
-    }
+pub fn get_jobs(printer_name: String) -> Result<Vec<PrintJob>, String> {
+    let script = PsCommand::new("Get-PrintJob")
+        .param("PrinterName", &printer_name)
+        .pipe(&format!("Select-Object {}", JOB_FIELDS))
+        .pipe("ConvertTo-Json")
+        .build();
+    let stdout = run_powershell(&script)?;
+    let jobs: Vec<PsPrintJob> = parse_json_list(&stdout)?;
+    Ok(jobs.into_iter().map(|job| job.into_job(&printer_name)).collect())
 }
 
 /**
  * Get printer job by id on windows using powershell
  */
-pub fn get_jobs_by_id(printername: String, jobid: String) -> String {
-    let output = Command::new("powershell")
-        .args([
-            "Get-PrintJob -PrinterName \"", 
-            &printername, 
-            "\" -ID \"", 
-            &jobid, 
-            "\"  | Select-Object DocumentName,Id,TotalPages,Position,Size,SubmmitedTime,UserName,PagesPrinted,JobTime,ComputerName,Datatype,PrinterName,Priority,SubmittedTime,JobStatus | ConvertTo-Json"
-        ])
-        .output()
-        .unwrap();
-    output.stdout
+pub fn get_jobs_by_id(printername: String, jobid: String) -> Result<Option<PrintJob>, String> {
+    let script = PsCommand::new("Get-PrintJob")
+        .param("PrinterName", &printername)
+        .param("ID", &job_id(&jobid)?.to_string())
+        .pipe(&format!("Select-Object {}", JOB_FIELDS))
+        .pipe("ConvertTo-Json")
+        .build();
+    let stdout = run_powershell(&script)?;
+    let jobs: Vec<PsPrintJob> = parse_json_list(&stdout)?;
+    Ok(jobs.into_iter().next().map(|job| job.into_job(&printername)))
+}
+
+// 对单个作业执行 *-PrintJob 命令
+fn run_job_command(cmdlet: &'static str, printername: &str, jobid: &str) -> Result<(), String> {
+    let script = PsCommand::new(cmdlet)
+        .param("PrinterName", printername)
+        .param("ID", &job_id(jobid)?.to_string())
+        .build();
+    run_powershell(&script).map(|_| ())
 }
 
 /**
  * Resume printers job on windows using powershell
  */
-pub fn resume_job(printername: String, jobid: String) -> String {
-    let output = Command::new("powershell")
-        .args([
-            "Resume-PrintJob -PrinterName \"", 
-            &printername, 
-            "\" -ID \"", 
-            &jobid, 
-            "\" "
-        ])
-        .output()
-        .unwrap();
-    output.stdout
+pub fn resume_job(printername: String, jobid: String) -> Result<(), String> {
+    run_job_command("Resume-PrintJob", &printername, &jobid)
 }
 
 /**
  * Restart printers job on windows using powershell
  */
-pub fn windows_restart_job(printername: String, jobid: String) -> String {
-    let output = Command::new("powershell")
-        .args([
-            "Restart-PrintJob -PrinterName \"", 
-            &printername, 
-            "\" -ID \"", 
-            &jobid, 
-            "\" "
-        ])
-        .output()
-        .unwrap();
-    output.stdout
+pub fn windows_restart_job(printername: String, jobid: String) -> Result<(), String> {
+    run_job_command("Restart-PrintJob", &printername, &jobid)
 }
 
 /**
  * Pause printers job on windows using powershell
  */
-pub fn pause_job(printername: String, jobid: String) -> String {
-    let output = Command::new("powershell")
-        .args([
-            "Suspend-PrintJob -PrinterName \"", 
-            &printername, 
-            "\" -ID \"", 
-            &jobid, 
-            "\" "
-        ])
-        .output()
-        .unwrap();
-    output.stdout
+pub fn pause_job(printername: String, jobid: String) -> Result<(), String> {
+    run_job_command("Suspend-PrintJob", &printername, &jobid)
 }
 
 /**
  * Remove printers job on windows using powershell
  */
-pub fn remove_job(printername: String, jobid: String) -> String {
-    let output = Command::new("powershell")
-        .args([
-            "Remove-PrintJob -PrinterName \"", 
-            &printername, 
-            "\" -ID \"", 
-            &jobid, 
-            "\" "
-        ])
-        .output()
-        .unwrap();
-    output.stdout
+pub fn remove_job(printername: String, jobid: String) -> Result<(), String> {
+    run_job_command("Remove-PrintJob", &printername, &jobid)
+}
+
+// 通过 winspool 的 WritePrinter 以 RAW 数据类型提交作业，Win7 自带的 PowerShell 2.0 同样支持 Add-Type
+const RAW_PRINTER_TYPE: &str = r#"
+Add-Type -TypeDefinition @"
+using System;
+using System.ComponentModel;
+using System.Runtime.InteropServices;
+public static class RawPrinterHelper {
+    [StructLayout(LayoutKind.Sequential, CharSet = CharSet.Unicode)]
+    public class DOCINFO { public string pDocName; public string pOutputFile; public string pDataType; }
+    [DllImport("winspool.drv", CharSet = CharSet.Unicode, SetLastError = true)]
+    public static extern bool OpenPrinter(string name, out IntPtr handle, IntPtr defaults);
+    [DllImport("winspool.drv", SetLastError = true)]
+    public static extern bool ClosePrinter(IntPtr handle);
+    [DllImport("winspool.drv", CharSet = CharSet.Unicode, SetLastError = true)]
+    public static extern int StartDocPrinter(IntPtr handle, int level, [In] DOCINFO info);
+    [DllImport("winspool.drv", SetLastError = true)]
+    public static extern bool EndDocPrinter(IntPtr handle);
+    [DllImport("winspool.drv", SetLastError = true)]
+    public static extern bool StartPagePrinter(IntPtr handle);
+    [DllImport("winspool.drv", SetLastError = true)]
+    public static extern bool EndPagePrinter(IntPtr handle);
+    [DllImport("winspool.drv", SetLastError = true)]
+    public static extern bool WritePrinter(IntPtr handle, byte[] data, int count, out int written);
+    public static int Send(string printer, string document, byte[] data) {
+        IntPtr handle;
+        if (!OpenPrinter(printer, out handle, IntPtr.Zero)) throw new Win32Exception();
+        try {
+            DOCINFO info = new DOCINFO();
+            info.pDocName = document;
+            info.pDataType = "RAW";
+            int job = StartDocPrinter(handle, 1, info);
+            if (job == 0) throw new Win32Exception();
+            int written = 0;
+            StartPagePrinter(handle);
+            bool ok = WritePrinter(handle, data, data.Length, out written);
+            EndPagePrinter(handle);
+            EndDocPrinter(handle);
+            if (!ok || written != data.Length) throw new Win32Exception();
+            return job;
+        } finally {
+            ClosePrinter(handle);
+        }
+    }
+}
+"@
+"#;
+
+// 把文件内容原样发送到打印队列 (ESC/POS、ZPL、TSPL 等)，返回 Spooler 作业 ID
+pub fn print_raw(printer_name: String, path: String, document_name: String) -> Result<String, String> {
+    let printer_name = printer_name.trim().trim_matches('"');
+    let script = format!(
+        "{}\n[RawPrinterHelper]::Send({}, {}, [System.IO.File]::ReadAllBytes({}))",
+        RAW_PRINTER_TYPE,
+        ps_quote(printer_name),
+        ps_quote(&document_name),
+        ps_quote(&path)
+    );
+    let output = run_powershell(&script)?;
+    let job_id = output.trim().to_string();
+    println!("RAW 打印成功，作业 ID: {}", job_id);
+    Ok(job_id)
+}
+
+/**
+ * Printer backend for Windows 8 and newer, backed by the PrintManagement cmdlets
+ */
+pub struct WindowsBackend;
+
+impl PrinterBackend for WindowsBackend {
+    fn name(&self) -> &'static str {
+        "Windows"
+    }
+
+    fn get_printers(&self) -> Result<Vec<Printer>, String> {
+        get_printers()
+    }
+
+    fn get_printer(&self, printer_name: String) -> Result<Option<Printer>, String> {
+        get_printers_by_name(printer_name)
+    }
+
+    fn print_pdf(&self, options: PrintOptions) -> Result<String, String> {
+        print_pdf(options)
+    }
+
+    fn unsupported_settings(&self, settings: &PrintSettings) -> Vec<&'static str> {
+        sumatra_print_settings(settings).map(|translated| translated.unsupported).unwrap_or_default()
+    }
+
+    fn print_raw(&self, printer_name: String, path: String, document_name: String) -> Result<String, String> {
+        print_raw(printer_name, path, document_name)
+    }
+
+    fn get_jobs(&self, printer_name: String) -> Result<Vec<PrintJob>, String> {
+        get_jobs(printer_name)
+    }
+
+    fn get_job(&self, printer_name: String, job_id: String) -> Result<Option<PrintJob>, String> {
+        get_jobs_by_id(printer_name, job_id)
+    }
+
+    fn pause_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        pause_job(printer_name, job_id)
+    }
+
+    fn resume_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        resume_job(printer_name, job_id)
+    }
+
+    fn restart_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        windows_restart_job(printer_name, job_id)
+    }
+
+    fn remove_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        remove_job(printer_name, job_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_settings_round_trip() {
+        // 旧版前端按 SumatraPDF 的顺序拼接，解析后再翻译应得到相同的参数
+        for legacy in ["1-3,5,paper=A4,simplex,fit,color,2x", "paper=letter,duplexshort,noscale,monochrome", "2x", "paper=A6"] {
+            let settings = PrintSettings::from_legacy(&format!("-print-settings {}", legacy)).unwrap();
+            let translated = sumatra_print_settings(&settings).unwrap();
+            assert_eq!(translated.args, vec!["-print-settings".to_string(), legacy.to_string()]);
+            assert!(translated.unsupported.is_empty());
+        }
+
+        // 纸张名称统一为 paper=，空格与多余的逗号去掉
+        let settings = PrintSettings::from_legacy("A4, duplexlong ,").unwrap();
+        assert_eq!(sumatra_print_settings(&settings).unwrap().args, vec!["-print-settings", "paper=A4,duplex"]);
+    }
+
+    #[test]
+    fn orientation_is_unsupported() {
+        let settings = PrintSettings::from_legacy("landscape").unwrap();
+        let translated = sumatra_print_settings(&settings).unwrap();
+        assert!(translated.args.is_empty());
+        assert_eq!(translated.unsupported, vec!["orientation"]);
+
+        // SumatraPDF 只能按名称选择纸张
+        let settings = PrintSettings::from_legacy("76x130mm,2x").unwrap();
+        let translated = sumatra_print_settings(&settings).unwrap();
+        assert_eq!(translated.args, vec!["-print-settings", "2x"]);
+        assert_eq!(translated.unsupported, vec!["paper"]);
+
+        let invalid = PrintSettings { repeat: Some(0), ..PrintSettings::default() };
+        assert!(sumatra_print_settings(&invalid).is_err());
+    }
+
+    #[test]
+    fn sumatra_args_keep_printer_and_path_separate() {
+        let options = PrintOptions {
+            id: "\"Front Desk\"".to_string(),
+            path: "-a.pdf".to_string(),
+            print_setting: PrintSettings::from_legacy("A4,2x").unwrap(),
+            remove_after_print: false,
+        };
+        let args = sumatra_args(&options).unwrap();
+        assert_eq!(args[..4], ["-print-to", "Front Desk", "-print-settings", "paper=A4,2x"]);
+        assert_eq!(args[4], path_arg("-a.pdf"));
+        assert!(!args[4].starts_with('-'));
+    }
+
+    #[test]
+    fn spooled_job_is_matched_by_owner_and_time() {
+        let script = spooled_job_script("O'Brien", 1_700_000_005_000);
+        assert_eq!(
+            script,
+            "$since = (New-Object DateTime 1970, 1, 1, 0, 0, 0, ([DateTimeKind]::Utc)).AddMilliseconds(1700000003000).ToLocalTime(); \
+             Get-PrintJob -PrinterName 'O''Brien' \
+             | Where-Object { $_.UserName -eq $env:USERNAME -and $_.SubmittedTime -ge $since } \
+             | Sort-Object Id | Select-Object -Last 1 -ExpandProperty Id"
+        );
+        assert!(submitted_since(500).contains(".AddMilliseconds(0)"));
+    }
 }
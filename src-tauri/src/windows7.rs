@@ -1,20 +1,206 @@
-use std::{process::Command, sync::mpsc, thread};
-use std::env;
-use crate::{declare::PrintOptions, fsys::remove_file};
-use std::path::{PathBuf};
+use crate::{backend::PrinterBackend, declare::{JobStatus, PrintJob, PrintOptions, Printer, PrinterState}, fsys::remove_file};
+use crate::settings::PrintSettings;
+use crate::journal::now_millis;
+use crate::utils::parse_json_list;
+use crate::windows::{submitted_since, sumatra_args, sumatra_path, sumatra_print_settings};
+use crate::timestamp::Timestamp;
+use crate::command::{decode_output, hidden_command, job_id, powershell, powershell_error, ps_quote, run_powershell, wql_like, wql_string};
+use serde::Deserialize;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Win32_Printer 中与 Get-Printer 对应的字段，ComputerName 取自 SystemName
+// JobCount 是 Win32_PrintJob 中属于该打印机的作业数，由 WMI_JOB_COUNTS 统计
+const WMI_PRINTER_FIELDS: &str = "Name = $_.Name; DriverName = $_.DriverName; JobCount = [int]$counts[$_.Name]; \
+PrintProcessor = $_.PrintProcessor; PortName = $_.PortName; ShareName = $_.ShareName; ComputerName = $_.SystemName; \
+PrinterStatus = $_.PrinterStatus; DetectedErrorState = $_.DetectedErrorState; WorkOffline = $_.WorkOffline; \
+Shared = $_.Shared; Local = $_.Local; Priority = $_.Priority";
+
+const WMI_JOB_COUNTS: &str = "$counts = @{}
+Get-WmiObject -Class Win32_PrintJob | Where-Object { $_.Name -match '^(.*), \\d+$' } | ForEach-Object { $counts[$matches[1]] = [int]$counts[$matches[1]] + 1 }";
+
+// Win32_PrintJob 中与 Get-PrintJob 对应的字段，StatusMask 与 Get-PrintJob 的 JobStatus 是同一组位标志
+const WMI_JOB_FIELDS: &str = "Name = $_.Name; JobId = $_.JobId; Document = $_.Document; Owner = $_.Owner; \
+HostPrintQueue = $_.HostPrintQueue; DataType = $_.DataType; TotalPages = $_.TotalPages; PagesPrinted = $_.PagesPrinted; \
+Size = $_.Size; Position = $_.Position; Priority = $_.Priority; TimeSubmitted = $_.TimeSubmitted; StatusMask = $_.StatusMask";
+
+// Win32_Printer 的字段
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct WmiPrinter {
+    name: String,
+    driver_name: Option<String>,
+    job_count: Option<u32>,
+    print_processor: Option<String>,
+    port_name: Option<String>,
+    share_name: Option<String>,
+    computer_name: Option<String>,
+    printer_status: Option<u32>,
+    detected_error_state: Option<u32>,
+    work_offline: Option<bool>,
+    shared: Option<bool>,
+    local: Option<bool>,
+    priority: Option<u32>,
+}
+
+impl From<WmiPrinter> for Printer {
+    fn from(item: WmiPrinter) -> Self {
+        Printer {
+            driver_name: item.driver_name.unwrap_or_default(),
+            job_count: item.job_count.unwrap_or_default(),
+            print_processor: item.print_processor.unwrap_or_default(),
+            port_name: item.port_name.unwrap_or_default(),
+            share_name: item.share_name.unwrap_or_default(),
+            computer_name: item.computer_name.unwrap_or_default(),
+            printer_status: item.printer_status.unwrap_or_default(),
+            shared: item.shared.unwrap_or_default(),
+            // 与 Get-Printer 的 Type 一致：0 本地打印机，1 网络连接
+            printer_type: if item.local == Some(false) { 1 } else { 0 },
+            priority: item.priority.unwrap_or_default(),
+            state: item
+                .printer_status
+                .map(|status| {
+                    PrinterState::from_wmi(status, item.detected_error_state.unwrap_or_default(), item.work_offline.unwrap_or_default())
+                })
+                .unwrap_or_default(),
+            ..Printer::new(&item.name)
+        }
+    }
+}
+
+// Win32_PrintJob 的字段
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct WmiPrintJob {
+    // "打印机名称, 作业ID"
+    name: String,
+    job_id: u32,
+    document: Option<String>,
+    owner: Option<String>,
+    host_print_queue: Option<String>,
+    data_type: Option<String>,
+    total_pages: Option<u32>,
+    pages_printed: Option<u32>,
+    size: Option<u64>,
+    position: Option<u32>,
+    priority: Option<u32>,
+    time_submitted: Option<String>,
+    status_mask: Option<u32>,
+}
+
+impl WmiPrintJob {
+    fn printer_name(&self) -> &str {
+        self.name.rsplit_once(", ").map_or(self.name.as_str(), |(printer, _)| printer)
+    }
+
+    fn into_job(self) -> PrintJob {
+        let printer_name = self.printer_name().to_string();
+        PrintJob {
+            job_status: Some(JobStatus::from_code(self.status_mask.unwrap_or_default())),
+            computer_name: self.host_print_queue.unwrap_or_default().trim_start_matches('\\').to_string(),
+            data_type: self.data_type.unwrap_or_default(),
+            document_name: self.document.unwrap_or_default(),
+            size: self.size.unwrap_or_default(),
+            pages_printed: self.pages_printed.unwrap_or_default(),
+            total_pages: self.total_pages.unwrap_or_default(),
+            position: self.position.unwrap_or_default(),
+            priority: self.priority.unwrap_or_default(),
+            username: self.owner.unwrap_or_default(),
+            ..PrintJob::new(&printer_name, &self.job_id.to_string())
+                .with_submitted(self.time_submitted.as_deref().and_then(Timestamp::from_cim))
+        }
+    }
+}
+
+// 查询单台打印机的 WQL，名称按字面量比较
+fn printer_query(printer_name: &str) -> String {
+    format!("SELECT * FROM Win32_Printer WHERE Name={}", wql_string(printer_name))
+}
+
+// Win32_PrintJob 的 Name 以 "打印机名称, " 开头，不能用 '%名称%'，否则会匹配到名称包含它的其他打印机
+fn jobs_query(printer_name: &str) -> String {
+    format!(
+        "SELECT * FROM Win32_PrintJob WHERE Name LIKE {}",
+        wql_string(&format!("{}, %", wql_like(printer_name)))
+    )
+}
+
+// 按 "打印机名称, 作业ID" 查询单个作业的 WQL
+fn job_query(printer_name: &str, job: &str) -> Result<String, String> {
+    Ok(format!(
+        "SELECT * FROM Win32_PrintJob WHERE Name={}",
+        wql_string(&format!("{}, {}", printer_name, job_id(job)?))
+    ))
+}
+
+// 查询打印机上作业的提交者和提交时间，用于找回刚提交的作业
+fn spooled_job_query(printer_name: &str) -> String {
+    format!(
+        "SELECT JobId, Owner, TimeSubmitted FROM Win32_PrintJob WHERE Name LIKE {}",
+        wql_string(&format!("{}, %", wql_like(printer_name)))
+    )
+}
+
+// 当前用户在开始打印之后提交的作业中 JobId 最大的一个，TimeSubmitted 为 WMI 日期字符串，转为本地时间后比较
+fn spooled_job_script(printer_name: &str, started_at: u64) -> String {
+    format!(
+        "{}; Get-WmiObject -Query {} \
+         | Where-Object {{ $_.Owner -eq $env:USERNAME -and $_.TimeSubmitted -and \
+         [Management.ManagementDateTimeConverter]::ToDateTime($_.TimeSubmitted) -ge $since }} \
+         | Sort-Object JobId | Select-Object -Last 1 -ExpandProperty JobId",
+        submitted_since(started_at),
+        ps_quote(&spooled_job_query(printer_name))
+    )
+}
+
+// 执行 WQL 查询并把每条结果的 fields 输出为 JSON 数组
+// Windows 7 自带的 PowerShell 2.0 没有 ConvertTo-Json，使用 .NET 3.5 的 JavaScriptSerializer 序列化，名称中的引号和反斜杠都能正确转义
+fn wmi_json_script(wql: &str, prelude: &str, fields: &str) -> String {
+    format!(
+        "Add-Type -AssemblyName System.Web.Extensions
+$serializer = New-Object System.Web.Script.Serialization.JavaScriptSerializer
+$serializer.MaxJsonLength = [int]::MaxValue
+{}
+$rows = @(Get-WmiObject -Query {} | ForEach-Object {{ @{{ {} }} }})
+$serializer.Serialize($rows)",
+        prelude,
+        ps_quote(wql),
+        fields
+    )
+}
+
+fn query_printers(wql: &str) -> Result<Vec<Printer>, String> {
+    parse_printers(&run_powershell(&wmi_json_script(wql, WMI_JOB_COUNTS, WMI_PRINTER_FIELDS))?)
+}
+
+fn query_jobs(wql: &str, printer_name: &str) -> Result<Vec<PrintJob>, String> {
+    parse_jobs(&run_powershell(&wmi_json_script(wql, "", WMI_JOB_FIELDS))?, printer_name)
+}
+
+fn parse_printers(output: &str) -> Result<Vec<Printer>, String> {
+    let printers: Vec<WmiPrinter> = parse_json_list(output)?;
+    Ok(printers.into_iter().map(Printer::from).collect())
+}
+
+// LIKE 只能按前缀粗筛，这里再按打印机名称精确过滤，名称本身带 ", " 的打印机也不会混入
+fn parse_jobs(output: &str, printer_name: &str) -> Result<Vec<PrintJob>, String> {
+    let jobs: Vec<WmiPrintJob> = parse_json_list(output)?;
+    Ok(jobs
+        .into_iter()
+        .filter(|job| job.printer_name() == printer_name)
+        .map(WmiPrintJob::into_job)
+        .collect())
+}
 
 // 检查 PowerShell 版本的函数
 fn check_powershell_version() -> String {
     // 执行 PowerShell 命令获取版本信息
-    let output = Command::new("powershell")
-        .arg("-Command")
-        .arg("Get-Host | Select-Object Version | ConvertTo-Json")
-        .output();
+    let output = powershell("Get-Host | Select-Object Version | ConvertTo-Json").output();
 
     match output {
         // 成功获取版本返回版本字符串
         Ok(output) => {
-            let version = String::from_utf8_lossy(&output.stdout).to_string();
+            let version = decode_output(&output.stdout);
             println!("PowerShell version: {}", version);
             version
         }
@@ -29,15 +215,12 @@ fn check_powershell_version() -> String {
 // 检查用户是否具有管理员权限的函数
 fn check_admin_privileges() -> bool {
     // 执行 PowerShell 命令检查管理员权限 (SID: S-1-5-32-544)
-    let output = Command::new("powershell")
-        .arg("-Command")
-        .arg("whoami /groups | Select-String -Pattern 'S-1-5-32-544'")
-        .output();
+    let output = powershell("whoami /groups | Select-String -Pattern 'S-1-5-32-544'").output();
 
     match output {
         // 如果输出包含管理员组，则拥有管理员权限
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stdout = decode_output(&output.stdout);
             let is_admin = stdout.contains("S-1-5-32-544");
             println!("Running as admin: {}", is_admin);
             is_admin
@@ -50,230 +233,253 @@ fn check_admin_privileges() -> bool {
     }
 }
 
-// 获取所有打印机的函数
-pub fn get_printers_win7() -> String {
-    let powershell_version = check_powershell_version();
-    println!("PowerShell version is: {}", powershell_version);
+// PowerShell 版本和管理员权限在进程运行期间不会变化，只在第一次获取打印机时检查
+// 打印机状态会定期刷新，每次都检查要多启动两个 PowerShell 进程
+static ENVIRONMENT_CHECK: Once = Once::new();
+static IS_ADMIN: AtomicBool = AtomicBool::new(false);
+
+fn is_admin() -> bool {
+    ENVIRONMENT_CHECK.call_once(|| {
+        let powershell_version = check_powershell_version();
+        println!("PowerShell version is: {}", powershell_version);
+        IS_ADMIN.store(check_admin_privileges(), Ordering::Relaxed);
+    });
+    IS_ADMIN.load(Ordering::Relaxed)
+}
 
-    let is_admin = check_admin_privileges();
-    if !is_admin {
+// 获取所有打印机的函数
+pub fn get_printers_win7() -> Result<Vec<Printer>, String> {
+    if !is_admin() {
         println!("User does not have administrative privileges.");
-        return "Administrative privileges required.".to_string();
+        return Err("Administrative privileges required.".to_string());
     }
 
-    // 创建线程间通信的通道
-    let (sender, receiver) = mpsc::channel();
-
-    // PowerShell 命令以获取打印机信息
-    let command = vec![
-        "-Command",
-        r#"
-        $printers = Get-WmiObject -Query 'SELECT * FROM Win32_Printer' |
-        Select-Object Name, DriverName, JobCount, PrintProcessor |
-        ForEach-Object {
-            $json = '{' +
-            '"id":"' + ($_.Name.Trim() -replace '"', '\"') + '",' +
-            '"name":"' + ($_.Name.Trim() -replace '"', '\"') + '"' +
-            '}'
-            $json
-        }
-        '[' + ($printers -join ',') + ']'
-        "#
-    ];
-
-    println!("Executing command: powershell {:?}", command.join(" "));
-
-    // 新建线程来执行 PowerShell 命令
-    thread::spawn(move || {
-        println!("Spawned thread to execute PowerShell command.");
-
-        let output = Command::new("powershell")
-            .args(&command)
-            .output();
-
-        match output {
-            // 成功时发送命令输出
-            Ok(output) => {
-                println!("PowerShell command executed successfully.");
-                if !output.stderr.is_empty() {
-                    eprintln!("Command stderr: {}", String::from_utf8_lossy(&output.stderr));
-                }
-                let stdout_string = String::from_utf8_lossy(&output.stdout).to_string();
-                println!("Command stdout: {}", stdout_string); 
-                let _ = sender.send(stdout_string);
-            }
-            // 失败时发送空字符串
-            Err(e) => {
-                eprintln!("Failed to execute PowerShell command: {:?}", e);
-                let _ = sender.send(String::new());
+    query_printers("SELECT * FROM Win32_Printer")
+}
+
+// 根据打印机名称获取打印机信息的函数
+pub fn get_printers_by_name_win7(printername: String) -> Result<Option<Printer>, String> {
+    Ok(query_printers(&printer_query(&printername))?.into_iter().next())
+}
+
+// 获取打印作业信息的函数
+pub fn get_jobs_win7(printer_name: String) -> Result<Vec<PrintJob>, String> {
+    query_jobs(&jobs_query(&printer_name), &printer_name)
+}
+
+// 打印PDF文件的函数 (适用于Windows 7)，返回打印队列中的作业 ID，找不到作业时返回空字符串
+pub fn print_pdf_win7(options: PrintOptions) -> Result<String, String> {
+    // 构建打印命令
+    let sumatra = sumatra_path();
+    let args = sumatra_args(&options)?;
+    let started_at = now_millis();
+    println!("生成的命令: {} {:?}", sumatra.display(), args);
+    // 执行命令
+    let output = hidden_command(&sumatra)
+        .args(&args)
+        .output()
+        .map_err(|e| format!("执行命令失败: {}", e))?;
+
+    // 根据命令执行结果返回相应的信息
+    if output.status.success() {
+        println!("打印成功_win7");
+        let job_id = find_spooled_job_win7(options.id.trim().trim_matches('"'), started_at).unwrap_or_default();
+        println!("打印队列作业 ID: {}", job_id);
+        // 作业已经提交，删除失败只记录日志，返回错误会被当作打印失败而重试
+        if options.remove_after_print {
+            match remove_file(&options.path) {
+                Ok(_) => println!("文件已删除: {}", options.path),
+                Err(e) => println!("删除文件失败: {}", e),
             }
         }
-    });
+        Ok(job_id)
+    } else {
+        eprintln!("打印失败");
+        Err("Windows-打印失败".to_string())
+    }
+}
 
-    println!("Main thread is doing other non-blocking work.");
+// 根据作业 ID 获取打印作业信息的函数
+pub fn get_job_by_id_win7(printer_name: String, job_id: String) -> Result<Option<PrintJob>, String> {
+    Ok(query_jobs(&job_query(&printer_name, &job_id)?, &printer_name)?.into_iter().next())
+}
 
-    // 接收线程发回的结果
-    match receiver.recv() {
-        Ok(res) => {
-            println!("Successfully received result from the spawned thread.");
-            res
+// 作业可能已经打印完并离开队列，此时返回 None
+fn find_spooled_job_win7(printer_name: &str, started_at: u64) -> Option<String> {
+    match powershell(&spooled_job_script(printer_name, started_at)).output() {
+        Ok(output) if output.status.success() => {
+            Some(decode_output(&output.stdout).trim().to_string()).filter(|id| !id.is_empty())
+        }
+        Ok(output) => {
+            println!("查找打印作业失败: {}", powershell_error(&decode_output(&output.stderr)));
+            None
         }
         Err(e) => {
-            eprintln!("Failed to receive result from the spawned thread: {:?}", e);
-            String::new()
+            println!("查找打印作业失败: {}", e);
+            None
         }
     }
 }
 
-// 根据打印机名称获取打印机信息的函数
-pub fn get_printers_by_name_win7(printername: String) -> String {
-    // 格式化 WMI 查询以选择指定打印机名称
+// 对指定打印作业调用 Win32_PrintJob 的 WMI 方法（Pause / Resume / Delete）
+fn invoke_job_method_win7(printer_name: &str, job_id: &str, method: &'static str) -> Result<(), String> {
     let query = format!(
-        r#"
-        $printers = Get-WmiObject -Query "SELECT * FROM Win32_Printer WHERE Name='{}'" |
-        Select-Object Name, DriverName, JobCount, PrintProcessor, PortName, ShareName, SystemName, PrinterStatus, Shared, Type, Priority |
-        ForEach-Object {{
-            '{{' +
-            '"id":"' + ($_.Name.Trim() -replace '"', '\"') + '",' +
-            '"name":"' + ($_.Name.Trim() -replace '"', '\"') + '",' +
-            '"DriverName":"' + ($_.DriverName.Trim() -replace '"', '\"') + '",' +
-            '"JobCount":' + [string]($_.JobCount) + ',' +
-            '"PrintProcessor":"' + ($_.PrintProcessor.Trim() -replace '"', '\"') + '",' +
-            '"PortName":"' + ($_.PortName.Trim() -replace '"', '\"') + '",' +
-            '"ShareName":"' + ($_.ShareName.Trim() -replace '"', '\"') + '",' +
-            '"ComputerName":"' + ($_.SystemName.Trim() -replace '"', '\"') + '",' +
-            '"PrinterStatus":' + [string]($_.PrinterStatus) + ',' +
-            '"Shared":' + [string]($_.Shared) + ',' +
-            '"Type":"' + [string]($_.Type) + '",' +
-            '"Priority":' + [string]($_.Priority) +
-            '}}'
-        }}
-        '[' + ($printers -join ',') + ']'
-        "#,
-        printername
+        r#"Get-WmiObject -Query {} | ForEach-Object {{ $_.{}() | Out-Null }}"#,
+        ps_quote(&job_query(printer_name, job_id)?),
+        method
     );
+    println!("invoke_job_method_win7 {}", query);
 
-
-
-    // 设置 PowerShell 命令
-    let command = vec![
-        "-Command",
-        &query,
-    ];
-
-    println!("Executing command: powershell {:?}", command.join(" "));
-
-    // 执行命令
-    let output = Command::new("powershell")
-        .args(&command)
-        .output();
+    let output = powershell(&query).output();
 
     match output {
-        // 成功时返回输出
-        Ok(output) => {
-            if !output.stderr.is_empty() {
-                eprintln!("Command stderr: {}", String::from_utf8_lossy(&output.stderr));
-            }
-            let stdout_string = String::from_utf8_lossy(&output.stdout).to_string();
-            println!("Command stdout get_printers_by_name_win7: {}", stdout_string); 
-            stdout_string
-        }
-        // 失败时返回空字符串
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(format!("Command failed with error: {}", powershell_error(&decode_output(&output.stderr)))),
         Err(e) => {
             eprintln!("Failed to execute PowerShell command: {:?}", e);
-            String::new()
+            Err(format!("Failed to execute PowerShell command: {}", e))
         }
     }
 }
 
-// 获取打印作业信息的函数
-pub fn get_jobs_win7(printer_name: String) -> String {
-    use std::process::Command;
-    use std::str::from_utf8;
+// Windows 7 打印后端，基于 WMI 查询
+pub struct Windows7Backend;
 
-    // 格式化 WMI 查询以获取特定打印机的作业信息
-    let query = format!(
-        r#"
-        $jobs = Get-WmiObject -Query "SELECT * FROM Win32_PrintJob WHERE Name LIKE '%{}%'" |
-        Select-Object Document, JobId, TotalPages, Position, Size, TimeSubmitted, Owner, PagesPrinted, StartTime, HostPrintQueue, DataType, PrinterName, Priority, JobStatus |
-        ForEach-Object {{
-            '{{' +
-            '"DocumentName":"' + $_.Document + '",' +
-            '"SubmittedTime":"' + $_.TimeSubmitted + '",' +
-            '"UserName":"' + $_.Owner + '",' +
-            '"PrinterName":"' + $_.PrinterName + '"' +
-            '}}'
-        }}
-        '[' + ($jobs -join ',') + ']'
-        "#,
-        printer_name
-    );
+impl PrinterBackend for Windows7Backend {
+    fn name(&self) -> &'static str {
+        "Windows7"
+    }
 
-    let command = vec![
-        "-Command",
-        &query,
-    ];
-    let full_command = command.join(" ");
-    println!("get_jobs_win7  {}", full_command);
+    fn get_printers(&self) -> Result<Vec<Printer>, String> {
+        get_printers_win7()
+    }
 
-    let output = Command::new("powershell")
-        .args(&command)
-        .output();
+    fn get_printer(&self, printer_name: String) -> Result<Option<Printer>, String> {
+        get_printers_by_name_win7(printer_name)
+    }
 
-    match output {
-        // 成功时返回作业信息
-        Ok(output_data) => {
-            if output_data.status.success() {
-                from_utf8(&output_data.stdout)
-                    .unwrap_or("Error converting output to UTF-8")
-                    .trim() // 去除空白和换行
-                    .to_string()
-            } else {
-                // 失败时返回错误信息
-                let error_message = from_utf8(&output_data.stderr)
-                    .unwrap_or("Error converting error output to UTF-8")
-                    .trim() // 去除空白和换行
-                    .to_string();
-                format!("Command failed with error: {}", error_message)
-            }
-        }
-        Err(error) => format!("Failed to execute PowerShell command: {}", error)
+    fn print_pdf(&self, options: PrintOptions) -> Result<String, String> {
+        print_pdf_win7(options)
+    }
+
+    fn unsupported_settings(&self, settings: &PrintSettings) -> Vec<&'static str> {
+        sumatra_print_settings(settings).map(|translated| translated.unsupported).unwrap_or_default()
+    }
+
+    fn print_raw(&self, printer_name: String, path: String, document_name: String) -> Result<String, String> {
+        crate::windows::print_raw(printer_name, path, document_name)
+    }
+
+    fn get_jobs(&self, printer_name: String) -> Result<Vec<PrintJob>, String> {
+        get_jobs_win7(printer_name)
+    }
+
+    fn get_job(&self, printer_name: String, job_id: String) -> Result<Option<PrintJob>, String> {
+        get_job_by_id_win7(printer_name, job_id)
+    }
+
+    fn pause_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        invoke_job_method_win7(&printer_name, &job_id, "Pause")
+    }
+
+    fn resume_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        invoke_job_method_win7(&printer_name, &job_id, "Resume")
+    }
+
+    fn remove_job(&self, printer_name: String, job_id: String) -> Result<(), String> {
+        invoke_job_method_win7(&printer_name, &job_id, "Delete")
     }
 }
 
-// 打印PDF文件的函数 (适用于Windows 7)
-pub fn print_pdf_win7(options: PrintOptions) -> Result<String, String> {
-    // 获取临时目录路径
-    let dir: PathBuf = env::temp_dir();
-    println!("临时目录: {}", dir.display());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // 构建打印命令
-    let print_arg = format!("-print-to {}", options.id);
-    let shell_command = format!(
-        "{}sm {} {}",
-        dir.display(),
-        print_arg,
-        // options.print_setting,
-        options.path
-    );
-    println!("生成的命令: {}", shell_command);
-    // 执行命令
-    let output = Command::new("powershell")
-        .args(&["-Command", &shell_command])
-        .output()
-        .map_err(|e| format!("执行命令失败: {}", e))?;
+    // JavaScriptSerializer 对 Get-WmiObject Win32_Printer 的输出
+    const PRINTERS_JSON: &str = r#"[{"Name":"\\\\PRINTSRV\\财务部打印机","DriverName":"HP Universal Printing PCL 6","JobCount":2,"PrintProcessor":"winprint","PortName":"IP_192.168.1.20","ShareName":null,"ComputerName":"PRINTSRV","PrinterStatus":3,"DetectedErrorState":0,"WorkOffline":false,"Shared":false,"Local":false,"Priority":1},{"Name":"O'Brien \"Label\"","DriverName":"ZDesigner GK888t (EPL)","JobCount":0,"PrintProcessor":"winprint","PortName":"USB001","ShareName":"Label","ComputerName":"FRONT-DESK","PrinterStatus":7,"DetectedErrorState":4,"WorkOffline":true,"Shared":true,"Local":true,"Priority":1}]"#;
 
-    // 根据命令执行结果返回相应的信息
-    if output.status.success() {
-        println!("打印成功_win7");
-        if options.remove_after_print {
-            // 打印成功后按需删除文件
-            remove_file(&options.path).map_err(|e| format!("删除文件失败: {}", e))?;
-            println!("文件已删除: {}", options.path);
-        }
-        Ok("Windows7-打印成功".to_string())
-    } else {
-        eprintln!("打印失败");
-        Err(format!("Windows-打印失败"))
+    // JavaScriptSerializer 对 Get-WmiObject Win32_PrintJob 的输出，第二个作业属于名称以 "HP" 开头的另一台打印机
+    const JOBS_JSON: &str = r#"[{"Name":"HP, 12","JobId":12,"Document":"C:\\Temp\\a1b2-面单.pdf","Owner":"张三","HostPrintQueue":"\\\\FRONT-DESK","DataType":"NT EMF 1.008","TotalPages":3,"PagesPrinted":1,"Size":204800,"Position":1,"Priority":1,"TimeSubmitted":"20240101120000.000000+480","StatusMask":16},{"Name":"HP, Color, 13","JobId":13,"Document":"b.pdf","Owner":"李四","HostPrintQueue":"\\\\FRONT-DESK","DataType":"RAW","TotalPages":1,"PagesPrinted":0,"Size":1024,"Position":2,"Priority":1,"TimeSubmitted":null,"StatusMask":8}]"#;
+
+    #[test]
+    fn printer_query_compares_literal_name() {
+        assert_eq!(printer_query("财务部打印机"), "SELECT * FROM Win32_Printer WHERE Name='财务部打印机'");
+        assert_eq!(
+            printer_query("\\\\srv\\O'Brien"),
+            "SELECT * FROM Win32_Printer WHERE Name='\\\\\\\\srv\\\\O\\'Brien'"
+        );
+    }
+
+    #[test]
+    fn jobs_query_matches_printer_prefix() {
+        assert_eq!(jobs_query("HP"), "SELECT * FROM Win32_PrintJob WHERE Name LIKE 'HP, %'");
+        assert_eq!(jobs_query("50%_off[1]"), "SELECT * FROM Win32_PrintJob WHERE Name LIKE '50[%][_]off[[]1], %'");
+        assert_eq!(jobs_query("O'Brien"), "SELECT * FROM Win32_PrintJob WHERE Name LIKE 'O\\'Brien, %'");
+    }
+
+    #[test]
+    fn job_query_requires_numeric_id() {
+        assert_eq!(job_query("HP", " 12 ").unwrap(), "SELECT * FROM Win32_PrintJob WHERE Name='HP, 12'");
+        assert!(job_query("HP", "12' OR Name LIKE '%").is_err());
+    }
+
+    #[test]
+    fn spooled_job_is_matched_by_owner_and_time() {
+        assert_eq!(
+            spooled_job_query("HP_1"),
+            "SELECT JobId, Owner, TimeSubmitted FROM Win32_PrintJob WHERE Name LIKE 'HP[_]1, %'"
+        );
+
+        let script = spooled_job_script("O'Brien", 1_700_000_005_000);
+        assert!(script.starts_with(&submitted_since(1_700_000_005_000)));
+        assert!(script.contains(&format!("Get-WmiObject -Query {} |", ps_quote(&spooled_job_query("O'Brien")))));
+        assert!(script.contains("$_.Owner -eq $env:USERNAME"));
+        assert!(script.ends_with("Sort-Object JobId | Select-Object -Last 1 -ExpandProperty JobId"));
+    }
+
+    #[test]
+    fn wql_is_one_powershell_literal() {
+        let wql = jobs_query("x'; Remove-Item C:\\ ; '");
+        let script = wmi_json_script(&wql, "", WMI_JOB_FIELDS);
+        assert!(script.contains(&format!("Get-WmiObject -Query {} |", ps_quote(&wql))));
+        assert!(script.ends_with("$serializer.Serialize($rows)"));
+    }
+
+    #[test]
+    fn parse_printer_fields() {
+        let printers = parse_printers(PRINTERS_JSON).unwrap();
+        assert_eq!(printers.len(), 2);
+        assert_eq!(printers[0].name, "\\\\PRINTSRV\\财务部打印机");
+        assert_eq!(printers[0].driver_name, "HP Universal Printing PCL 6");
+        assert_eq!(printers[0].port_name, "IP_192.168.1.20");
+        assert_eq!(printers[0].share_name, "");
+        assert_eq!(printers[0].job_count, 2);
+        assert_eq!(printers[0].printer_type, 1);
+        assert_eq!(printers[1].name, "O'Brien \"Label\"");
+        assert_eq!(printers[1].printer_type, 0);
+        assert!(printers[1].shared);
+        assert_eq!(printers[1].state, PrinterState::from_wmi(7, 4, true));
+        assert!(parse_printers("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_job_fields() {
+        let jobs = parse_jobs(JOBS_JSON, "HP").unwrap();
+        assert_eq!(jobs.len(), 1);
+        let job = &jobs[0];
+        assert_eq!(job.printer_name, "HP");
+        assert_eq!(job.job_id, "12");
+        assert_eq!(job.document_name, "C:\\Temp\\a1b2-面单.pdf");
+        assert_eq!(job.username, "张三");
+        assert_eq!(job.computer_name, "FRONT-DESK");
+        assert_eq!(job.data_type, "NT EMF 1.008");
+        assert_eq!((job.total_pages, job.pages_printed, job.size, job.position), (3, 1, 204800, 1));
+        assert_eq!(job.job_status.as_ref().map(|status| status.name.as_str()), Some("Printing"));
+        assert_eq!(job.submitted_time, Some(1_704_081_600_000));
+
+        let jobs = parse_jobs(JOBS_JSON, "HP, Color").unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_id, "13");
+        assert_eq!(jobs[0].submitted_time, None);
     }
 }
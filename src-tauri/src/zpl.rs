@@ -0,0 +1,256 @@
+use base64::{Engine as _, engine::general_purpose};
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use crate::barcode::{self, Code128Code, Code128Set, Symbology};
+use crate::bitmap::MonoBitmap;
+use crate::label::{LabelElement, LabelLayout};
+
+// 打印中文默认使用的打印机字体，需要预先下载到打印机
+const DEFAULT_CJK_FONT: &str = "E:SIMSUN.TTF";
+
+// 生成 ZPL II 作业
+pub fn render_zpl(layout: &LabelLayout) -> Result<String, String> {
+    let mut zpl = String::new();
+    // ^CI28 使用 UTF-8 编码
+    zpl.push_str("^XA\n^CI28\n");
+    zpl.push_str(&format!("^PW{}\n^LL{}\n^LH0,0\n", layout.dots(layout.width), layout.dots(layout.height)));
+
+    for element in &layout.elements {
+        match element {
+            LabelElement::Text { x, y, content, font_size, bold } => {
+                let height = layout.dots(*font_size).max(10);
+                let font = if content.is_ascii() {
+                    format!("^A0N,{},{}", height, height)
+                } else {
+                    let font = layout.font.as_deref().unwrap_or(DEFAULT_CJK_FONT);
+                    format!("^A@N,{},{},{}", height, height, font)
+                };
+                let (x, y) = (layout.dots(*x), layout.dots(*y));
+                zpl.push_str(&text_field(x, y, &font, content));
+                // ZPL 没有粗体，错开一个点重复打印
+                if *bold {
+                    zpl.push_str(&text_field(x + 1, y, &font, content));
+                }
+            }
+            LabelElement::Barcode { x, y, data, symbology, height, module_width, human_readable } => {
+                let (x, y) = (layout.dots(*x), layout.dots(*y));
+                let module = module_width.unwrap_or(if symbology.is_linear() { 2 } else { 4 }).clamp(1, 10);
+                let height = layout.dots(*height).max(1);
+                let interpretation = if *human_readable { "Y" } else { "N" };
+                match symbology {
+                    // ^BC 的模式 A 由打印机自动切换 Code128 字符集
+                    Symbology::Code128 => zpl.push_str(&format!(
+                        "^FO{},{}^BY{},3,{}^BCN,{},{},N,N,A^FH^FD{}^FS\n",
+                        x, y, module, height, height, interpretation, escape_field(data)
+                    )),
+                    // GS1-128 用模式 N 按已编排好的字符集切换与 FNC1 输出
+                    Symbology::Gs1128 => zpl.push_str(&format!(
+                        "^FO{},{}^BY{},3,{}^BCN,{},{},N,N,N^FH^FD{}^FS\n",
+                        x, y, module, height, height, interpretation, code128_field(&barcode::gs1_128_codes(data)?)
+                    )),
+                    Symbology::Qrcode => zpl.push_str(&qr_field(x, y, module, data)),
+                    // ECC200，质量等级 200
+                    Symbology::DataMatrix => zpl.push_str(&format!(
+                        "^FO{},{}^BXN,{},200^FH^FD{}^FS\n",
+                        x, y, module, escape_field(data)
+                    )),
+                    // PDF417 使用与 PDF 预览相同的编码结果，以图形字段输出
+                    Symbology::Pdf417 => {
+                        let bitmap = barcode::encode(*symbology, data)?.to_bitmap(module, module);
+                        zpl.push_str(&format!("^FO{},{}{}^FS\n", x, y, graphic_field(&bitmap)));
+                    }
+                }
+            }
+            LabelElement::Qrcode { x, y, data, module_size } => {
+                zpl.push_str(&qr_field(layout.dots(*x), layout.dots(*y), module_size.unwrap_or(4).clamp(1, 10), data));
+            }
+            LabelElement::Box { x, y, width, height, thickness } => {
+                zpl.push_str(&format!(
+                    "^FO{},{}^GB{},{},{}^FS\n",
+                    layout.dots(*x),
+                    layout.dots(*y),
+                    layout.dots(*width),
+                    layout.dots(*height),
+                    layout.dots(*thickness).max(1)
+                ));
+            }
+            LabelElement::Line { x1, y1, x2, y2, thickness } => {
+                zpl.push_str(&line(layout, *x1, *y1, *x2, *y2, *thickness));
+            }
+            LabelElement::Image { x, y, data, width } => {
+                let max_width = width.map(|w| layout.dots(w));
+                let bitmap = MonoBitmap::from_base64(data, max_width)?;
+                zpl.push_str(&format!("^FO{},{}{}^FS\n", layout.dots(*x), layout.dots(*y), graphic_field(&bitmap)));
+            }
+        }
+    }
+
+    zpl.push_str(&format!("^PQ{}\n^XZ\n", layout.copies.max(1)));
+    Ok(zpl)
+}
+
+// 模型 2，纠错等级 M，自动选择编码模式
+fn qr_field(x: u32, y: u32, module_size: u32, data: &str) -> String {
+    format!("^FO{},{}^BQN,2,{}^FH^FDMA,{}^FS\n", x, y, module_size, escape_field(data))
+}
+
+// ^BC 模式 N 的字段数据，字符集切换与 FNC1 使用 > 开头的调用码
+fn code128_field(codes: &[Code128Code]) -> String {
+    let mut field = String::new();
+    for code in codes {
+        match code {
+            Code128Code::Start(Code128Set::A) => field.push_str(">9"),
+            Code128Code::Start(Code128Set::B) => field.push_str(">:"),
+            Code128Code::Start(Code128Set::C) => field.push_str(">;"),
+            Code128Code::Switch(Code128Set::A) => field.push_str(">7"),
+            Code128Code::Switch(Code128Set::B) => field.push_str(">6"),
+            Code128Code::Switch(Code128Set::C) => field.push_str(">5"),
+            Code128Code::Fnc1 => field.push_str(">8"),
+            Code128Code::Pair(pair) => field.push_str(&format!("{:02}", pair)),
+            Code128Code::Char(b'>') => field.push_str("><"),
+            Code128Code::Char(c) if *c < 0x20 || matches!(*c, b'_' | b'^' | b'~') => field.push_str(&format!("_{:02X}", c)),
+            Code128Code::Char(c) => field.push(*c as char),
+        }
+    }
+    field
+}
+
+fn text_field(x: u32, y: u32, font: &str, content: &str) -> String {
+    format!("^FO{},{}{}^FH^FD{}^FS\n", x, y, font, escape_field(content))
+}
+
+// ^FH 模式下用 _XX 转义 ZPL 的控制字符与转义符本身
+fn escape_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '_' => escaped.push_str("_5F"),
+            '^' => escaped.push_str("_5E"),
+            '~' => escaped.push_str("_7E"),
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 水平、垂直线用 ^GB 画，斜线用 ^GD
+fn line(layout: &LabelLayout, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32) -> String {
+    let thickness = layout.dots(thickness).max(1);
+    let (left, top) = (layout.dots(x1.min(x2)), layout.dots(y1.min(y2)));
+    let width = layout.dots((x2 - x1).abs());
+    let height = layout.dots((y2 - y1).abs());
+
+    if width == 0 || height == 0 {
+        format!("^FO{},{}^GB{},{},{}^FS\n", left, top, width.max(thickness), height.max(thickness), thickness)
+    } else {
+        // 从左下到右上为 R (/)，从左上到右下为 L (\)
+        let direction = if (x2 - x1) * (y2 - y1) < 0.0 { "R" } else { "L" };
+        format!("^FO{},{}^GD{},{},{},B,{}^FS\n", left, top, width, height, thickness, direction)
+    }
+}
+
+// ^GF 图形字段，数据使用 Z64 压缩：zlib 压缩后 base64 编码，末尾附 CRC
+fn graphic_field(bitmap: &MonoBitmap) -> String {
+    let total = bitmap.data.len();
+    let encoded = general_purpose::STANDARD.encode(compress_to_vec_zlib(&bitmap.data, 6));
+    format!(
+        "^GFA,{},{},{},:Z64:{}:{:04X}",
+        total,
+        total,
+        bitmap.bytes_per_row(),
+        encoded,
+        crc16_ccitt(encoded.as_bytes())
+    )
+}
+
+// CRC-16/XMODEM (多项式 0x1021，初始值 0)，Z64 对 base64 文本计算
+fn crc16_ccitt(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(elements: serde_json::Value) -> LabelLayout {
+        serde_json::from_value(serde_json::json!({
+            "width": 50.8,
+            "height": 25.4,
+            "copies": 2,
+            "elements": elements
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn label_commands() {
+        let layout = layout(serde_json::json!([
+            { "type": "text", "x": 2.54, "y": 2.54, "content": "A_B^~" },
+            { "type": "text", "x": 2.54, "y": 5.08, "content": "中", "bold": true },
+            { "type": "barcode", "x": 0, "y": 5.08, "data": "AB12", "height": 10, "human_readable": true },
+            { "type": "barcode", "x": 0, "y": 0, "data": "(01)09501101530003(10)AB", "symbology": "gs1_128", "height": 10 },
+            { "type": "qrcode", "x": 0, "y": 0, "data": "hi" },
+            { "type": "box", "x": 0, "y": 0, "width": 10, "height": 5 },
+            { "type": "line", "x1": 0, "y1": 5, "x2": 10, "y2": 5 },
+            { "type": "line", "x1": 0, "y1": 10, "x2": 10, "y2": 0 },
+            { "type": "line", "x1": 0, "y1": 0, "x2": 10, "y2": 10 }
+        ]));
+
+        let expected = [
+            "^XA",
+            "^CI28",
+            "^PW406",
+            "^LL203",
+            "^LH0,0",
+            "^FO20,20^A0N,24,24^FH^FDA_5FB_5E_7E^FS",
+            "^FO20,41^A@N,24,24,E:SIMSUN.TTF^FH^FD中^FS",
+            "^FO21,41^A@N,24,24,E:SIMSUN.TTF^FH^FD中^FS",
+            "^FO0,41^BY2,3,80^BCN,80,Y,N,N,A^FH^FDAB12^FS",
+            // C 字符集起始，FNC1，(01) 与 (10) 的数字成对编码，最后切换到 B
+            "^FO0,0^BY2,3,80^BCN,80,N,N,N,N^FH^FD>;>8010950110153000310>6AB^FS",
+            "^FO0,0^BQN,2,4^FH^FDMA,hi^FS",
+            "^FO0,0^GB80,40,2^FS",
+            "^FO0,40^GB80,2,2^FS",
+            "^FO0,0^GD80,80,2,B,R^FS",
+            "^FO0,0^GD80,80,2,B,L^FS",
+            "^PQ2",
+            "^XZ",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(render_zpl(&layout).unwrap(), expected);
+    }
+
+    #[test]
+    fn code128_escapes() {
+        let codes = [
+            Code128Code::Start(Code128Set::A),
+            Code128Code::Char(b'>'),
+            Code128Code::Char(0x1D),
+            Code128Code::Char(b'_'),
+            Code128Code::Switch(Code128Set::C),
+            Code128Code::Pair(7),
+        ];
+        assert_eq!(code128_field(&codes), ">9><_1D_5F>507");
+    }
+
+    #[test]
+    fn z64_crc() {
+        // CRC-16/XMODEM 的标准校验值
+        assert_eq!(crc16_ccitt(b"123456789"), 0x31C3);
+
+        let mut bitmap = MonoBitmap::new(9, 1);
+        bitmap.set(0, 0, true);
+        let field = graphic_field(&bitmap);
+        assert!(field.starts_with("^GFA,2,2,2,:Z64:"));
+        let (encoded, crc) = field["^GFA,2,2,2,:Z64:".len()..].split_once(':').unwrap();
+        assert_eq!(crc, format!("{:04X}", crc16_ccitt(encoded.as_bytes())));
+    }
+}